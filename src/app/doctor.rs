@@ -0,0 +1,373 @@
+use crate::app::process::{self, ProcessStore};
+use crate::app::store::{GifConfig, Store, ALIGNMENTS};
+use crate::decoder;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use sysinfo::System;
+
+// Library health check: compares store.json, the managed gifs/ folder and running.json,
+// and knows how to repair each kind of problem it finds.
+
+/// A problem found in the library
+#[derive(Clone, Debug)]
+pub enum Issue {
+    /// An entry's file is gone
+    MissingFile { name: String, path: PathBuf },
+    /// An entry's file exists but can't be decoded
+    Undecodable { name: String, path: PathBuf, error: String },
+    /// A file in the managed folder that no entry uses
+    Orphaned { path: PathBuf },
+    /// Several files with the same content; the first one is kept
+    Duplicate { paths: Vec<PathBuf> },
+    /// A running.json entry whose process is gone or isn't a player anymore
+    StaleProcess { pid: u32, name: String },
+    InvalidAlign { name: String, align: String },
+    InvalidMonitor { name: String, monitor: usize, available: usize },
+}
+
+impl Issue {
+    /// What `repair` will do, for buttons and prompts
+    pub fn repair_label(&self) -> &'static str {
+        match self {
+            Issue::MissingFile { .. } => "Remove entry",
+            Issue::Undecodable { .. } => "Remove entry and file",
+            Issue::Orphaned { .. } => "Add to library",
+            Issue::Duplicate { .. } => "Share one copy",
+            Issue::StaleProcess { .. } => "Forget process",
+            Issue::InvalidAlign { .. } => "Reset to center",
+            Issue::InvalidMonitor { .. } => "Use monitor 0",
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MissingFile { name, path } => write!(f, "{}: file missing ({})", name, path.display()),
+            Issue::Undecodable { name, path, error } => write!(f, "{}: can't decode {} ({})", name, path.display(), error),
+            Issue::Orphaned { path } => write!(f, "{}: not used by any entry", path.display()),
+            Issue::Duplicate { paths } => {
+                let names: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "Same content: {}", names.join(", "))
+            }
+            Issue::StaleProcess { pid, name } => write!(f, "{}: process {} is no longer running", name, pid),
+            Issue::InvalidAlign { name, align } => write!(f, "{}: unknown alignment '{}'", name, align),
+            Issue::InvalidMonitor { name, monitor, available } => {
+                write!(f, "{}: monitor {} doesn't exist ({} connected)", name, monitor, available)
+            }
+        }
+    }
+}
+
+/// Look for problems. `monitors` is the number of connected monitors, if known.
+pub fn check(store: &Store, processes: &ProcessStore, monitors: Option<usize>) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let mut names: Vec<&String> = store.gifs.keys().collect();
+    names.sort();
+
+    for name in &names {
+        let config = &store.gifs[*name];
+        if !config.path.exists() {
+            issues.push(Issue::MissingFile { name: config.name.clone(), path: config.path.clone() });
+        } else if let Err(e) = decoder::read_frame_info(&config.path) {
+            issues.push(Issue::Undecodable { name: config.name.clone(), path: config.path.clone(), error: e.to_string() });
+        }
+
+        if !ALIGNMENTS.contains(&config.align.as_str()) {
+            issues.push(Issue::InvalidAlign { name: config.name.clone(), align: config.align.clone() });
+        }
+        if let Some(available) = monitors.filter(|&n| config.monitor >= n) {
+            issues.push(Issue::InvalidMonitor { name: config.name.clone(), monitor: config.monitor, available });
+        }
+    }
+
+    // Files on disk: used ones first (in name order), then the rest of the managed folder
+    let used: Vec<PathBuf> = names.iter().map(|n| canonical(&store.gifs[*n].path)).collect();
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in &used {
+        if path.exists() && !files.contains(path) {
+            files.push(path.clone());
+        }
+    }
+    let mut orphans = managed_files();
+    orphans.retain(|path| !used.contains(path));
+    files.extend(orphans.iter().cloned());
+
+    // Same size and hash means same content
+    let mut groups: HashMap<(u64, u64), Vec<PathBuf>> = HashMap::new();
+    let mut order = Vec::new();
+    for path in files {
+        let Ok(data) = fs::read(&path) else { continue };
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&data);
+        let key = (data.len() as u64, hasher.finish());
+        if !groups.contains_key(&key) {
+            order.push(key);
+        }
+        groups.entry(key).or_default().push(path);
+    }
+    let mut duplicates = Vec::new();
+    for key in order {
+        let paths = groups.remove(&key).unwrap_or_default();
+        if paths.len() > 1 {
+            duplicates.push(paths);
+        }
+    }
+
+    // An orphan that copies a used file is cleaned up by the duplicate repair instead
+    for path in &orphans {
+        let copies_used = duplicates.iter().any(|d| d.contains(path) && used.contains(&d[0]));
+        if !copies_used {
+            issues.push(Issue::Orphaned { path: path.clone() });
+        }
+    }
+    issues.extend(duplicates.into_iter().map(|paths| Issue::Duplicate { paths }));
+
+    let system = System::new_all();
+    let mut pids: Vec<&u32> = processes.processes.keys().collect();
+    pids.sort();
+    for pid in pids {
+        if !process::is_player(&system, *pid) {
+            issues.push(Issue::StaleProcess { pid: *pid, name: processes.processes[pid].name.clone() });
+        }
+    }
+
+    issues
+}
+
+/// Fix one issue, changing `store` and `processes` in memory; the caller saves them.
+/// Returns what was done.
+pub fn repair(issue: &Issue, store: &mut Store, processes: &mut ProcessStore) -> Result<String, String> {
+    match issue {
+        Issue::MissingFile { name, .. } => {
+            remove_entry(store, name);
+            Ok(format!("Removed {}", name))
+        }
+        Issue::Undecodable { name, path, .. } => {
+            remove_entry(store, name);
+            if Store::is_managed(path) && !store.gifs.values().any(|c| canonical(&c.path) == canonical(path)) {
+                fs::remove_file(path).map_err(|e| e.to_string())?;
+            }
+            Ok(format!("Removed {}", name))
+        }
+        Issue::Orphaned { path } => {
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let mut name = stem.clone();
+            let mut n = 2;
+            while store.gifs.contains_key(&name) {
+                name = format!("{} {}", stem, n);
+                n += 1;
+            }
+            store.gifs.insert(name.clone(), GifConfig::new(name.clone(), path.clone()));
+            Ok(format!("Added {}", name))
+        }
+        Issue::Duplicate { paths } => {
+            let Some((keep, others)) = paths.split_first() else { return Ok(String::new()) };
+            let mut moved = 0;
+            for config in store.gifs.values_mut() {
+                if others.contains(&canonical(&config.path)) {
+                    config.path = keep.clone();
+                    moved += 1;
+                }
+            }
+            // Only the app's own copies are deleted, never files the user keeps elsewhere
+            let mut deleted = 0;
+            for path in others.iter().filter(|p| Store::is_managed(p)) {
+                fs::remove_file(path).map_err(|e| e.to_string())?;
+                deleted += 1;
+            }
+            Ok(format!("{} entries now use {}, deleted {} copies", moved, keep.display(), deleted))
+        }
+        Issue::StaleProcess { pid, name } => {
+            processes.processes.remove(pid);
+            Ok(format!("Forgot {} ({})", name, pid))
+        }
+        Issue::InvalidAlign { name, .. } => {
+            let config = store.gifs.get_mut(name).ok_or("Entry no longer exists")?;
+            config.align = "center".to_string();
+            Ok(format!("{} is centered", name))
+        }
+        Issue::InvalidMonitor { name, .. } => {
+            let config = store.gifs.get_mut(name).ok_or("Entry no longer exists")?;
+            config.monitor = 0;
+            Ok(format!("{} uses monitor 0", name))
+        }
+    }
+}
+
+fn remove_entry(store: &mut Store, name: &str) {
+    store.gifs.remove(name);
+    for scene in store.scenes.values_mut() {
+        scene.animations.retain(|a| a != name);
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Every file in the managed folder
+fn managed_files() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(Store::gifs_dir()) else { return Vec::new() };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| canonical(&e.path())))
+        .filter(|p| p.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::process::RunningProcess;
+    use crate::app::store::Scene;
+    use crate::types::Frame;
+
+    // Files live in a per-test folder outside the managed one, so nothing here is ever deleted
+    fn folder(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gif-engine-test-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_gif(path: &Path) {
+        let frame = Frame { buffer: vec![255, 0, 0, 255], width: 1, height: 1, delay: Default::default() };
+        crate::encoder::gif::save_gif(path, &[&frame], None, &Default::default()).unwrap();
+    }
+
+    fn store_with(entries: &[(&str, &Path)]) -> Store {
+        let mut store = Store::default();
+        for (name, path) in entries {
+            store.gifs.insert(name.to_string(), GifConfig::new(name.to_string(), path.to_path_buf()));
+        }
+        store
+    }
+
+    // `check` also scans the real managed folder; keep only what concerns the test's entries
+    fn check_entries(store: &Store, processes: &ProcessStore, monitors: Option<usize>, dir: &Path) -> Vec<Issue> {
+        let dir = canonical(dir);
+        let mut issues = check(store, processes, monitors);
+        issues.retain(|issue| match issue {
+            Issue::Orphaned { .. } => false,
+            Issue::Duplicate { paths } => paths.iter().all(|p| p.starts_with(&dir)),
+            _ => true,
+        });
+        issues
+    }
+
+    fn stale_processes() -> ProcessStore {
+        let mut processes = ProcessStore::default();
+        let pid = u32::MAX - 1; // Never a live process
+        processes.processes.insert(pid, RunningProcess { pid, name: "gone".into(), start_time: 0, animations: Vec::new(), control: true });
+        processes
+    }
+
+    #[test]
+    fn check_finds_entry_problems() {
+        let dir = folder("check");
+        let good = dir.join("good.gif");
+        let broken = dir.join("broken.gif");
+        write_gif(&good);
+        fs::write(&broken, b"not a gif").unwrap();
+
+        let mut store = store_with(&[("good", &good), ("broken", &broken), ("missing", &dir.join("missing.gif"))]);
+        store.gifs.get_mut("good").unwrap().align = "middle".into();
+        store.gifs.get_mut("good").unwrap().monitor = 2;
+
+        let issues = check_entries(&store, &stale_processes(), Some(2), &dir);
+        let _ = fs::remove_dir_all(&dir);
+        let found: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+
+        assert!(matches!(&issues[0], Issue::Undecodable { name, .. } if name == "broken"), "{found:?}");
+        assert!(matches!(&issues[1], Issue::InvalidAlign { name, align } if name == "good" && align == "middle"));
+        assert!(matches!(&issues[2], Issue::InvalidMonitor { monitor: 2, available: 2, .. }));
+        assert!(matches!(&issues[3], Issue::MissingFile { name, .. } if name == "missing"));
+        assert!(matches!(issues.last(), Some(Issue::StaleProcess { name, .. }) if name == "gone"));
+        assert_eq!(issues.len(), 5, "{found:?}");
+    }
+
+    #[test]
+    fn check_groups_files_with_the_same_content() {
+        let dir = folder("duplicates");
+        let (a, b) = (dir.join("a.gif"), dir.join("b.gif"));
+        write_gif(&a);
+        fs::copy(&a, &b).unwrap();
+
+        let store = store_with(&[("first", &a), ("second", &b), ("again", &a)]);
+        let issues = check_entries(&store, &ProcessStore::default(), None, &dir);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(issues.len(), 1);
+        let Issue::Duplicate { paths } = &issues[0] else { panic!("{}", issues[0]) };
+        assert_eq!(paths, &[canonical(&a), canonical(&b)]);
+    }
+
+    #[test]
+    fn repairs_fix_entries_in_memory() {
+        let mut store = store_with(&[("a", Path::new("a.gif")), ("b", Path::new("b.gif"))]);
+        store.scenes.insert("desk".into(), Scene { animations: vec!["a".into(), "b".into()], ..Default::default() });
+        store.gifs.get_mut("b").unwrap().align = "middle".into();
+        store.gifs.get_mut("b").unwrap().monitor = 3;
+        let mut processes = stale_processes();
+
+        let issues = [
+            Issue::MissingFile { name: "a".into(), path: "a.gif".into() },
+            Issue::InvalidAlign { name: "b".into(), align: "middle".into() },
+            Issue::InvalidMonitor { name: "b".into(), monitor: 3, available: 1 },
+            Issue::StaleProcess { pid: u32::MAX - 1, name: "gone".into() },
+        ];
+        for issue in &issues {
+            repair(issue, &mut store, &mut processes).unwrap();
+        }
+
+        assert!(!store.gifs.contains_key("a"));
+        assert_eq!(store.scenes["desk"].animations, ["b"]);
+        assert_eq!((store.gifs["b"].align.as_str(), store.gifs["b"].monitor), ("center", 0));
+        assert!(processes.processes.is_empty());
+
+        let gone = Issue::InvalidAlign { name: "a".into(), align: "middle".into() };
+        assert!(repair(&gone, &mut store, &mut processes).is_err());
+    }
+
+    #[test]
+    fn orphans_are_added_under_a_free_name() {
+        let mut store = store_with(&[("cat", Path::new("elsewhere/cat.gif"))]);
+        let message = repair(&Issue::Orphaned { path: "gifs/cat.gif".into() }, &mut store, &mut ProcessStore::default()).unwrap();
+
+        assert_eq!(message, "Added cat 2");
+        assert_eq!(store.gifs["cat 2"].path, Path::new("gifs/cat.gif"));
+    }
+
+    #[test]
+    fn repairs_never_delete_files_outside_the_library() {
+        let dir = folder("unmanaged");
+        let (a, b, broken) = (dir.join("a.gif"), dir.join("b.gif"), dir.join("broken.gif"));
+        write_gif(&a);
+        fs::copy(&a, &b).unwrap();
+        fs::write(&broken, b"not a gif").unwrap();
+        let (a, b) = (canonical(&a), canonical(&b));
+
+        let mut store = store_with(&[("first", &a), ("second", &b), ("broken", &broken)]);
+        let mut processes = ProcessStore::default();
+        let duplicate = repair(&Issue::Duplicate { paths: vec![a.clone(), b.clone()] }, &mut store, &mut processes);
+        let undecodable = repair(
+            &Issue::Undecodable { name: "broken".into(), path: broken.clone(), error: String::new() },
+            &mut store,
+            &mut processes,
+        );
+        let kept = (b.exists(), broken.exists());
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(duplicate.unwrap(), format!("1 entries now use {}, deleted 0 copies", a.display()));
+        assert_eq!(store.gifs["second"].path, a);
+        assert!(undecodable.is_ok());
+        assert!(!store.gifs.contains_key("broken"));
+        assert_eq!(kept, (true, true));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::app::process::ProcessStore;
+use crate::app::store::GifConfig;
+use crate::control;
+use crate::playback::sequence::PlaybackMode;
+use crate::processing::{self, background::BackgroundRemoval};
+use std::process::Command;
+
+// Starting player processes, shared by the manager and the library subcommands
+
+/// The `play` command line for a library entry
+pub fn play_command(config: &GifConfig, click_through: bool) -> Result<Command, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to get executable path: {}", e))?;
+    let mut cmd = Command::new(exe);
+    cmd.arg("play").arg(&config.path);
+    cmd.arg("--name").arg(&config.name);
+
+    if let Some(fps) = config.fps {
+        cmd.arg("--fps").arg(fps.to_string());
+    } else if (config.speed - 1.0).abs() > f32::EPSILON {
+        cmd.arg("--speed").arg(config.speed.to_string());
+    }
+    if config.mode != PlaybackMode::Forward {
+        cmd.arg("--mode").arg(config.mode.as_str());
+    }
+    if let Some(range) = config.frame_range {
+        cmd.arg("--range").arg(range.to_string());
+    }
+    if let Some(interpolation) = &config.interpolation {
+        cmd.arg("--interpolate").arg(interpolation.target_fps.to_string());
+        cmd.arg("--interpolation").arg(interpolation.method.as_str());
+    }
+    if let Some(scale) = config.scale {
+        cmd.arg("--scale").arg(scale.to_string());
+    }
+    if config.opacity < 1.0 {
+        cmd.arg("--opacity").arg(config.opacity.to_string());
+    }
+    if let Some(rect) = config.crop {
+        cmd.arg("--crop").arg(rect.to_string());
+    }
+    if config.trim {
+        cmd.arg("--trim");
+    }
+    for swap in &config.palette {
+        cmd.arg("--palette-swap").arg(swap.to_string());
+    }
+    match &config.background {
+        Some(BackgroundRemoval::ChromaKey(key)) => {
+            cmd.arg("--chroma-key").arg(key_color_arg(key.color));
+            cmd.arg("--chroma-tolerance").arg(key.tolerance.to_string());
+            cmd.arg("--chroma-softness").arg(key.softness.to_string());
+        }
+        Some(BackgroundRemoval::EdgeFill(fill)) => {
+            cmd.arg("--edge-fill").arg(key_color_arg(fill.color));
+            cmd.arg("--edge-tolerance").arg(fill.tolerance.to_string());
+        }
+        None => {}
+    }
+    if let Some(defringe) = &config.defringe {
+        cmd.arg("--defringe").arg(processing::format_hex_color(defringe.matte));
+        cmd.arg("--defringe-erode").arg(defringe.erode.to_string());
+        cmd.arg("--defringe-feather").arg(defringe.feather.to_string());
+    }
+    for filter in &config.filters {
+        cmd.arg("--filter").arg(filter.to_string());
+    }
+    if let Some(outline) = &config.outline {
+        cmd.arg("--outline").arg(processing::format_hex_color(outline.color));
+        cmd.arg("--outline-width").arg(outline.thickness.to_string());
+    }
+    if let Some(shadow) = &config.shadow {
+        cmd.arg("--shadow").arg(processing::format_hex_color(shadow.color));
+        cmd.arg("--shadow-x").arg(shadow.offset.0.to_string());
+        cmd.arg("--shadow-y").arg(shadow.offset.1.to_string());
+        cmd.arg("--shadow-blur").arg(shadow.blur.to_string());
+        cmd.arg("--shadow-opacity").arg(shadow.opacity.to_string());
+    }
+    if let Some((x, y)) = config.position {
+        cmd.arg("--x").arg(x.to_string());
+        cmd.arg("--y").arg(y.to_string());
+    }
+
+    cmd.arg("--align").arg(&config.align);
+    cmd.arg("--monitor").arg(config.monitor.to_string());
+
+    if config.overlay {
+        cmd.arg("--overlay");
+    }
+    if click_through {
+        cmd.arg("--click-through");
+    }
+
+    Ok(cmd)
+}
+
+/// Start a player for `config` and register it in running.json. Returns its pid.
+pub fn launch(config: &GifConfig, click_through: bool, processes: &mut ProcessStore) -> Result<u32, String> {
+    let child = play_command(config, click_through)?
+        .spawn()
+        .map_err(|e| format!("Failed to start: {}", e))?;
+    processes.add_process(child.id(), config.name.clone());
+    Ok(child.id())
+}
+
+/// Apply `config` to the running players of that animation in place. Its own player is
+/// restarted if it doesn't answer; processes shared with other animations are never killed
+/// for it, and a compositor scene (which can't reload one animation) is left alone with a
+/// note to restart the scene. Every player is tried; the returned report says what happened
+/// to each, and is an error if any of them wasn't updated.
+pub fn update(config: &GifConfig, click_through: bool, processes: &mut ProcessStore) -> Result<String, String> {
+    let running: Vec<(u32, String, bool)> = processes
+        .processes
+        .values()
+        .filter(|info| info.hosts(&config.name))
+        .map(|info| (info.pid, info.name.clone(), info.control))
+        .collect();
+    if running.is_empty() {
+        return Err(format!("{} isn't playing", config.name));
+    }
+
+    let mut updated = Vec::new();
+    let mut failed = Vec::new();
+    for (pid, host, control) in running {
+        if !control {
+            failed.push(format!("{} plays in {}, which can't be updated in place; restart the scene", config.name, host));
+            continue;
+        }
+        let reload = control::Command::Reload { config: Some(Box::new(config.clone())) };
+        match control::send(pid, Some(&config.name), reload) {
+            Ok(response) => match response.into_result() {
+                Ok(_) => updated.push(format!("Updated {}", config.name)),
+                Err(e) => failed.push(format!("{} (pid {}) rejected the update: {}", host, pid, e)),
+            },
+            Err(_) if host == config.name => {
+                processes.kill_process(pid);
+                match launch(config, click_through, processes) {
+                    Ok(pid) => updated.push(format!("Restarted {} (pid {})", config.name, pid)),
+                    Err(e) => failed.push(format!("{} couldn't be restarted: {}", config.name, e)),
+                }
+            }
+            Err(e) => failed.push(format!("{} isn't responding (pid {}): {}", host, pid, e)),
+        }
+    }
+    let report = updated.iter().chain(&failed).cloned().collect::<Vec<_>>().join("\n");
+    if failed.is_empty() { Ok(report) } else { Err(report) }
+}
+
+/// Stop `name` wherever it plays. Its own players (or the process registered under that name,
+/// like a scene) are killed; in a process shared with other animations only its window is
+/// closed, since killing the process would stop them all. A compositor scene can't drop a
+/// single animation, so that is refused with an error naming the scene.
+/// Returns how many processes stopped playing it.
+pub fn stop(name: &str, processes: &mut ProcessStore) -> Result<usize, String> {
+    let hosts: Vec<(u32, String, bool)> = processes
+        .processes
+        .values()
+        .filter(|info| info.hosts(name))
+        .map(|info| (info.pid, info.name.clone(), info.control))
+        .collect();
+
+    let mut stopped = 0;
+    let mut errors = Vec::new();
+    for (pid, host, control) in hosts {
+        if host == name {
+            processes.kill_process(pid);
+            stopped += 1;
+        } else if !control {
+            errors.push(format!("{} is part of {}, which can only be stopped as a whole", name, host));
+        } else {
+            match control::send(pid, Some(name), control::Command::Quit).and_then(|r| r.into_result()) {
+                Ok(_) => {
+                    processes.remove_hosted(pid, name);
+                    stopped += 1;
+                }
+                Err(e) => errors.push(format!("Can't close {} in {}: {}", name, host, e)),
+            }
+        }
+    }
+
+    if errors.is_empty() { Ok(stopped) } else { Err(errors.join("\n")) }
+}
+
+fn key_color_arg(color: Option<[u8; 3]>) -> String {
+    color.map(processing::format_hex_color).unwrap_or_else(|| "auto".to_string())
+}
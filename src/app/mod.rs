@@ -1,5 +1,7 @@
 pub mod store;
 pub mod process;
 pub mod dirs;
+pub mod doctor;
+pub mod launch;
 
 
@@ -11,9 +11,31 @@ pub struct RunningProcess {
     pub pid: u32,
     pub name: String,
     pub start_time: u64,
+    /// Animations hosted by this process when it plays several at once
+    #[serde(default)]
+    pub animations: Vec<String>,
+    /// Whether the process listens for control requests (players do, the compositor doesn't)
+    #[serde(default)]
+    pub control: bool,
 }
 
-#[derive(Serialize, Deserialize, Default)]
+impl RunningProcess {
+    /// Whether this process is playing the animation called `name`
+    pub fn hosts(&self, name: &str) -> bool {
+        self.name == name || self.animations.iter().any(|a| a == name)
+    }
+}
+
+/// Whether `pid` is still one of our own processes. The check is by executable name, since
+/// pids in running.json may have been reused by unrelated programs since they were recorded.
+pub fn is_player(system: &System, pid: u32) -> bool {
+    let exe_name = std::env::current_exe().ok().and_then(|p| p.file_name().map(|n| n.to_os_string()));
+    system
+        .process(Pid::from_u32(pid))
+        .is_some_and(|p| exe_name.as_deref().is_none_or(|exe| p.name() == exe))
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
 pub struct ProcessStore {
     pub processes: HashMap<u32, RunningProcess>,
 }
@@ -42,14 +64,23 @@ impl ProcessStore {
             fs::create_dir_all(&app_dir)?;
         }
         
+        // Write then rename, so the manager and the command line never read a half-written file
         let store_path = app_dir.join("running.json");
+        let temp_path = store_path.with_extension("json.tmp");
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(store_path, content)?;
+        fs::write(&temp_path, content)?;
+        fs::rename(temp_path, store_path)?;
         
         Ok(())
     }
 
+    /// Register a single-animation player
     pub fn add_process(&mut self, pid: u32, name: String) {
+        self.add_host_process(pid, name, Vec::new(), true);
+    }
+
+    /// Register a process that plays several animations under one `name`
+    pub fn add_host_process(&mut self, pid: u32, name: String, animations: Vec<String>, control: bool) {
         let start_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -59,6 +90,8 @@ impl ProcessStore {
             pid,
             name,
             start_time,
+            animations,
+            control,
         });
         let _ = self.save();
     }
@@ -68,12 +101,37 @@ impl ProcessStore {
         let _ = self.save();
     }
 
+    /// Drop one animation from a process hosting several. The process stays registered
+    /// until its last one goes, since it exits then.
+    pub fn remove_hosted(&mut self, pid: u32, name: &str) {
+        if let Some(process) = self.processes.get_mut(&pid) {
+            process.animations.retain(|a| a != name);
+            if process.animations.is_empty() {
+                self.processes.remove(&pid);
+            }
+            let _ = self.save();
+        }
+    }
+
+    /// Follow a library rename so running players are still found under the new name
+    pub fn rename(&mut self, old: &str, new: &str) {
+        for process in self.processes.values_mut() {
+            if process.name == old {
+                process.name = new.to_string();
+            }
+            for animation in process.animations.iter_mut().filter(|a| *a == old) {
+                *animation = new.to_string();
+            }
+        }
+        let _ = self.save();
+    }
+
     pub fn cleanup_dead_processes(&mut self) {
         let mut sys = System::new_all();
         sys.refresh_all();
         
         let dead_pids: Vec<u32> = self.processes.keys()
-            .filter(|&&pid| sys.process(Pid::from_u32(pid)).is_none())
+            .filter(|&&pid| !is_player(&sys, pid))
             .cloned()
             .collect();
             
@@ -85,7 +143,8 @@ impl ProcessStore {
     
     pub fn kill_process(&mut self, pid: u32) -> bool {
         let sys = System::new_all();
-        if let Some(process) = sys.process(Pid::from_u32(pid)) {
+        // A pid reused by another program (after a reboot, say) is only forgotten, never killed
+        if let Some(process) = sys.process(Pid::from_u32(pid)).filter(|_| is_player(&sys, pid)) {
             process.kill();
             self.remove_process(pid);
             return true;
@@ -1,14 +1,50 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use crate::app::dirs;
+use crate::decoder::palette::PaletteSwap;
+use crate::playback::sequence::{FrameRange, PlaybackMode};
+use crate::processing::{ProcessOptions, parse_hex_color};
+use crate::processing::background::{BackgroundRemoval, EdgeFill};
+use crate::processing::chroma::ChromaKey;
+use crate::processing::defringe::Defringe;
+use crate::processing::effects::{Outline, Shadow};
+use crate::processing::filters::ColorFilter;
+use crate::processing::interpolate::{Interpolation, InterpolationMethod};
+use crate::processing::trim::CropRect;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Values `GifConfig::align` understands
+pub const ALIGNMENTS: [&str; 6] = ["top-left", "top-right", "bottom-left", "bottom-right", "center", "custom"];
+
+/// Keys `GifConfig::set` understands
+pub const SETTINGS: [&str; 32] = [
+    "fps", "speed", "mode", "range", "interpolate", "interpolation", "scale", "x", "y", "align", "monitor",
+    "overlay", "opacity", "crop", "trim", "palette", "filters", "chroma-key", "chroma-tolerance",
+    "chroma-softness", "edge-fill", "edge-tolerance", "defringe", "defringe-erode", "defringe-feather",
+    "outline", "outline-width", "shadow", "shadow-x", "shadow-y", "shadow-blur", "shadow-opacity",
+];
+
+/// Opacities an animation can be given; a fully transparent window would still catch clicks
+pub const OPACITY_RANGE: std::ops::RangeInclusive<f32> = 0.05..=1.0;
+
+/// Scale factors an animation can be given
+pub const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.1..=2.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GifConfig {
     pub path: PathBuf,
     pub name: String,
-    pub fps: Option<u32>,
+    pub fps: Option<u32>, // Constant FPS, takes precedence over speed
+    #[serde(default = "default_speed")]
+    pub speed: f32, // Multiplier on the original frame delays
+    #[serde(default)]
+    pub mode: PlaybackMode,
+    #[serde(default)]
+    pub frame_range: Option<FrameRange>,
+    #[serde(default)]
+    pub interpolation: Option<Interpolation>,
     pub scale: Option<f32>,
     pub position: Option<(i32, i32)>,
     #[serde(default)]
@@ -16,9 +52,216 @@ pub struct GifConfig {
     #[serde(default)]
     pub monitor: usize,
     pub overlay: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f32, // 0.0 (invisible) to 1.0 (opaque)
+    #[serde(default)]
+    pub palette: Vec<PaletteSwap>,
+    #[serde(default)]
+    pub crop: Option<CropRect>,
+    #[serde(default)]
+    pub trim: bool,
+    #[serde(default)]
+    pub background: Option<BackgroundRemoval>,
+    #[serde(default)]
+    pub defringe: Option<Defringe>,
+    #[serde(default)]
+    pub filters: Vec<ColorFilter>,
+    #[serde(default)]
+    pub outline: Option<Outline>,
+    #[serde(default)]
+    pub shadow: Option<Shadow>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+impl GifConfig {
+    /// A library entry with default settings
+    pub fn new(name: String, path: PathBuf) -> Self {
+        Self {
+            path,
+            name,
+            fps: None,
+            speed: default_speed(),
+            mode: PlaybackMode::Forward,
+            frame_range: None,
+            interpolation: None,
+            scale: None,
+            position: None,
+            align: "center".to_string(),
+            monitor: 0,
+            overlay: true,
+            opacity: default_opacity(),
+            palette: Vec::new(),
+            crop: None,
+            trim: false,
+            background: None,
+            defringe: None,
+            filters: Vec::new(),
+            outline: None,
+            shadow: None,
+        }
+    }
+
+    /// Change one setting from its text form, as in `gif-engine set <name> key=value`.
+    /// `none` clears optional settings; lists (`palette`, `filters`) are comma separated.
+    /// Background, defringe and effect keys are named after the `play` flags; setting a color
+    /// turns the feature on, and the detail keys change the feature that is already on.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let none = value.eq_ignore_ascii_case("none");
+        match key {
+            "fps" => self.fps = if none { None } else { Some(parse_in(key, value, 1..=240)?) },
+            "speed" => self.speed = parse_in(key, value, 0.1..=10.0)?,
+            "mode" => self.mode = value.parse()?,
+            "range" => self.frame_range = if none { None } else { Some(value.parse()?) },
+            "interpolate" => {
+                self.interpolation = if none {
+                    None
+                } else {
+                    let target_fps = parse_in(key, value, 1..=240)?;
+                    Some(Interpolation { target_fps, ..self.interpolation.clone().unwrap_or_default() })
+                }
+            }
+            "interpolation" => {
+                let method: InterpolationMethod = value.parse()?;
+                self.interpolation.get_or_insert_with(Interpolation::default).method = method;
+            }
+            "scale" => self.scale = if none { None } else { Some(parse_in(key, value, SCALE_RANGE)?) },
+            "x" | "y" => {
+                if none {
+                    self.position = None;
+                } else {
+                    let v: i32 = value.parse().map_err(|_| format!("Invalid {} '{}'", key, value))?;
+                    let (x, y) = self.position.unwrap_or((0, 0));
+                    self.position = Some(if key == "x" { (v, y) } else { (x, v) });
+                    self.align = "custom".to_string();
+                }
+            }
+            "align" => {
+                if !ALIGNMENTS.contains(&value) {
+                    return Err(format!("Unknown alignment '{}' ({})", value, ALIGNMENTS.join(", ")));
+                }
+                self.align = value.to_string();
+            }
+            "monitor" => self.monitor = value.parse().map_err(|_| format!("Invalid monitor '{}'", value))?,
+            "overlay" => self.overlay = parse_bool(key, value)?,
+            "opacity" => self.opacity = parse_in(key, value, OPACITY_RANGE)?,
+            "crop" => self.crop = if none { None } else { Some(value.parse()?) },
+            "trim" => self.trim = parse_bool(key, value)?,
+            "palette" => self.palette = parse_list(value)?,
+            "filters" => self.filters = parse_list(value)?,
+            "chroma-key" => {
+                self.background = if none {
+                    None
+                } else {
+                    let color = parse_key_color(value)?;
+                    let key = match self.background.take() {
+                        Some(BackgroundRemoval::ChromaKey(key)) => key,
+                        _ => ChromaKey::default(),
+                    };
+                    Some(BackgroundRemoval::ChromaKey(ChromaKey { color, ..key }))
+                }
+            }
+            "chroma-tolerance" | "chroma-softness" => {
+                let Some(BackgroundRemoval::ChromaKey(chroma)) = &mut self.background else {
+                    return Err(format!("{} needs chroma-key to be set", key));
+                };
+                let v = parse_in(key, value, 0.0..=1.0)?;
+                if key == "chroma-tolerance" { chroma.tolerance = v } else { chroma.softness = v }
+            }
+            "edge-fill" => {
+                self.background = if none {
+                    None
+                } else {
+                    let color = parse_key_color(value)?;
+                    let fill = match self.background.take() {
+                        Some(BackgroundRemoval::EdgeFill(fill)) => fill,
+                        _ => EdgeFill::default(),
+                    };
+                    Some(BackgroundRemoval::EdgeFill(EdgeFill { color, ..fill }))
+                }
+            }
+            "edge-tolerance" => {
+                let Some(BackgroundRemoval::EdgeFill(fill)) = &mut self.background else {
+                    return Err(format!("{} needs edge-fill to be set", key));
+                };
+                fill.tolerance = parse_in(key, value, 0.0..=1.0)?;
+            }
+            "defringe" => {
+                self.defringe = if none {
+                    None
+                } else {
+                    let matte = parse_hex_color(value)?;
+                    Some(Defringe { matte, ..self.defringe.clone().unwrap_or_default() })
+                }
+            }
+            "defringe-erode" | "defringe-feather" => {
+                let defringe = self.defringe.as_mut().ok_or_else(|| format!("{} needs defringe to be set", key))?;
+                let v = parse_in(key, value, 0..=8)?;
+                if key == "defringe-erode" { defringe.erode = v } else { defringe.feather = v }
+            }
+            "outline" => {
+                self.outline = if none {
+                    None
+                } else {
+                    let color = parse_hex_color(value)?;
+                    Some(Outline { color, ..self.outline.clone().unwrap_or_default() })
+                }
+            }
+            "outline-width" => {
+                let outline = self.outline.as_mut().ok_or_else(|| format!("{} needs outline to be set", key))?;
+                outline.thickness = parse_in(key, value, 1..=16)?;
+            }
+            "shadow" => {
+                self.shadow = if none {
+                    None
+                } else {
+                    let color = parse_hex_color(value)?;
+                    Some(Shadow { color, ..self.shadow.clone().unwrap_or_default() })
+                }
+            }
+            "shadow-x" | "shadow-y" | "shadow-blur" | "shadow-opacity" => {
+                let shadow = self.shadow.as_mut().ok_or_else(|| format!("{} needs shadow to be set", key))?;
+                match key {
+                    "shadow-x" => shadow.offset.0 = parse_in(key, value, -32..=32)?,
+                    "shadow-y" => shadow.offset.1 = parse_in(key, value, -32..=32)?,
+                    "shadow-blur" => shadow.blur = parse_in(key, value, 0..=32)?,
+                    _ => shadow.opacity = parse_in(key, value, 0.0..=1.0)?,
+                }
+            }
+            _ => return Err(format!("Unknown setting '{}' ({})", key, SETTINGS.join(", "))),
+        }
+        Ok(())
+    }
+
+    /// Take the processing settings from `options`, the inverse of `process_options`
+    pub fn set_process_options(&mut self, options: ProcessOptions) {
+        let ProcessOptions { palette, crop, trim, scale, background, defringe, filters, outline, shadow } = options;
+        self.palette = palette;
+        self.crop = crop;
+        self.trim = trim;
+        self.scale = scale;
+        self.background = background;
+        self.defringe = defringe;
+        self.filters = filters;
+        self.outline = outline;
+        self.shadow = shadow;
+    }
+
+    pub fn process_options(&self) -> ProcessOptions {
+        ProcessOptions {
+            palette: self.palette.clone(),
+            crop: self.crop,
+            trim: self.trim,
+            scale: self.scale,
+            background: self.background.clone(),
+            defringe: self.defringe.clone(),
+            filters: self.filters.clone(),
+            outline: self.outline.clone(),
+            shadow: self.shadow.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct AppSettings {
     #[serde(default = "default_theme")]
     pub theme: String, // "dark", "light"
@@ -28,6 +271,50 @@ pub struct AppSettings {
     pub click_through: bool,
 }
 
+fn parse_in<T>(key: &str, value: &str, range: std::ops::RangeInclusive<T>) -> Result<T, String>
+where
+    T: std::str::FromStr + PartialOrd + std::fmt::Display,
+{
+    let v: T = value.parse().map_err(|_| format!("Invalid {} '{}'", key, value))?;
+    if !range.contains(&v) {
+        return Err(format!("{} must be between {} and {}", key, range.start(), range.end()));
+    }
+    Ok(v)
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Ok(true),
+        "false" | "off" | "no" | "0" => Ok(false),
+        _ => Err(format!("Invalid {} '{}', expected true or false", key, value)),
+    }
+}
+
+// A `#rrggbb` color, or `auto` to detect it from the frame corners
+fn parse_key_color(value: &str) -> Result<Option<[u8; 3]>, String> {
+    if value.eq_ignore_ascii_case("auto") {
+        Ok(None)
+    } else {
+        parse_hex_color(value).map(Some)
+    }
+}
+
+// Comma separated; empty or `none` gives an empty list
+fn parse_list<T: std::str::FromStr<Err = String>>(value: &str) -> Result<Vec<T>, String> {
+    if value.is_empty() || value.eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    value.split(',').map(|item| item.trim().parse()).collect()
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
 fn default_theme() -> String {
     "dark".to_string()
 }
@@ -50,28 +337,99 @@ impl Default for AppSettings {
     }
 }
 
-#[derive(Serialize, Deserialize, Default)]
+/// How a scene's animations are put on screen
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SceneMode {
+    /// One player process and window per animation; a crash only takes down that animation
+    #[default]
+    Isolated,
+    /// One process with a window per animation, decoding shared files once
+    Shared,
+    /// One process with a single click-through window per monitor compositing every animation
+    Compositor,
+}
+
+impl SceneMode {
+    pub const ALL: [SceneMode; 3] = [SceneMode::Isolated, SceneMode::Shared, SceneMode::Compositor];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SceneMode::Isolated => "Isolated",
+            SceneMode::Shared => "Shared",
+            SceneMode::Compositor => "Compositor",
+        }
+    }
+}
+
+/// A named set of animations that are started and stopped together
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Scene {
+    /// Library names, bottom to top (the compositor's z-order)
+    pub animations: Vec<String>,
+    #[serde(default)]
+    pub mode: SceneMode,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
 pub struct Store {
     pub gifs: HashMap<String, GifConfig>,
     #[serde(default)]
+    pub scenes: HashMap<String, Scene>,
+    #[serde(default)]
     pub settings: AppSettings,
+    /// store.json as this copy last read or wrote it. There is no file locking, so `save`
+    /// compares against it to keep what other processes (the command line) changed meanwhile.
+    #[serde(skip)]
+    base: Option<Box<Store>>,
+}
+
+// Three-way merge of one map: keys changed here since `base` take this copy's value (or are
+// removed), the rest follow `on_disk`
+fn merge<V: Clone + PartialEq>(mine: &HashMap<String, V>, base: &HashMap<String, V>, mut on_disk: HashMap<String, V>) -> HashMap<String, V> {
+    for key in mine.keys().chain(base.keys()) {
+        match (mine.get(key), base.get(key)) {
+            (mine, base) if mine == base => {}
+            (Some(value), _) => {
+                on_disk.insert(key.clone(), value.clone());
+            }
+            (None, _) => {
+                on_disk.remove(key);
+            }
+        }
+    }
+    on_disk
 }
 
 impl Store {
-    fn gifs_dir() -> PathBuf {
+    /// Folder holding the managed copies of library files
+    pub fn gifs_dir() -> PathBuf {
         let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
         config_dir.join("gif-engine").join("gifs")
     }
 
+    /// Whether `path` is one of the managed copies (as opposed to a file the user keeps elsewhere)
+    pub fn is_managed(path: &Path) -> bool {
+        let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+        canonical(path).starts_with(canonical(&Self::gifs_dir()))
+    }
+
+    /// When store.json was last written, by this process or another one
+    pub fn modified() -> Option<SystemTime> {
+        let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+        fs::metadata(config_dir.join("gif-engine").join("store.json")).and_then(|m| m.modified()).ok()
+    }
+
     pub fn load() -> Self {
         let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
         let store_path = config_dir.join("gif-engine").join("store.json");
 
+        let mut store = Self::default();
         if store_path.exists() {
             match fs::read_to_string(&store_path) {
                 Ok(content) => {
                     match serde_json::from_str::<Store>(&content) {
-                        Ok(store) => return store,
+                        Ok(loaded) => store = loaded,
                         Err(e) => {
                             eprintln!("Error parsing store.json: {}", e);
                             // If parsing fails, backup the corrupted file and return default
@@ -82,11 +440,15 @@ impl Store {
                 Err(e) => eprintln!("Error reading store.json: {}", e),
             }
         }
-        
-        Self::default()
+
+        store.base = Some(Box::new(store.clone()));
+        store
     }
 
-    pub fn save(&self) -> Result<(), std::io::Error> {
+    /// Write the library. Entries this copy changed since it was loaded (or last saved) are
+    /// written as they are here; every other entry keeps whatever store.json holds now, so
+    /// changes another process saved in the meantime aren't lost. Those are picked up here too.
+    pub fn save(&mut self) -> Result<(), std::io::Error> {
         let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
         let app_dir = config_dir.join("gif-engine");
         
@@ -95,9 +457,22 @@ impl Store {
         }
         
         let store_path = app_dir.join("store.json");
+        let on_disk = fs::read_to_string(&store_path).ok().and_then(|content| serde_json::from_str::<Store>(&content).ok());
+        if let (Some(base), Some(on_disk)) = (self.base.take(), on_disk) {
+            self.gifs = merge(&self.gifs, &base.gifs, on_disk.gifs);
+            self.scenes = merge(&self.scenes, &base.scenes, on_disk.scenes);
+            if self.settings == base.settings {
+                self.settings = on_disk.settings;
+            }
+        }
+
+        // Write then rename, so the manager and the command line never read a half-written file
+        let temp_path = store_path.with_extension("json.tmp");
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(store_path, content)?;
-        
+        fs::write(&temp_path, content)?;
+        fs::rename(temp_path, store_path)?;
+
+        self.base = Some(Box::new(Store { base: None, ..self.clone() }));
         Ok(())
     }
 
@@ -122,24 +497,203 @@ impl Store {
         // Use the copied file's absolute path
         let abs_path = fs::canonicalize(&dest_path).unwrap_or(dest_path);
         
-        let config = GifConfig {
-            path: abs_path,
-            name: name.clone(),
-            fps: None,
-            scale: None,
-            position: None,
-            align: "center".to_string(),
-            monitor: 0,
-            overlay: true,
-        };
+        let config = GifConfig::new(name.clone(), abs_path);
         self.gifs.insert(name, config);
         
         Ok(())
     }
     
+    /// Remove an entry and its place in scenes. The managed copy is deleted unless another entry uses it.
+    pub fn remove_gif(&mut self, name: &str) -> Result<GifConfig, String> {
+        let config = self.gifs.remove(name).ok_or_else(|| format!("No animation named '{}'", name))?;
+        for scene in self.scenes.values_mut() {
+            scene.animations.retain(|a| a != name);
+        }
+        let shared = self.gifs.values().any(|c| c.path == config.path);
+        if !shared && Self::is_managed(&config.path) {
+            let _ = fs::remove_file(&config.path);
+        }
+        Ok(config)
+    }
+
+    /// Rename an entry, its scene references and its managed copy (so a later import under
+    /// the old name can't overwrite it)
+    pub fn rename_gif(&mut self, old: &str, new: &str) -> Result<(), String> {
+        if new.trim().is_empty() {
+            return Err("Name can't be empty".to_string());
+        }
+        if self.gifs.contains_key(new) {
+            return Err(format!("'{}' already exists", new));
+        }
+        let mut config = self.gifs.remove(old).ok_or_else(|| format!("No animation named '{}'", old))?;
+
+        if Self::is_managed(&config.path) {
+            let extension = config.path.extension().and_then(|e| e.to_str()).unwrap_or("gif");
+            let dest = Self::gifs_dir().join(format!("{}.{}", new, extension));
+            if !dest.exists() && fs::rename(&config.path, &dest).is_ok() {
+                let dest = fs::canonicalize(&dest).unwrap_or(dest);
+                for other in self.gifs.values_mut().filter(|c| c.path == config.path) {
+                    other.path = dest.clone();
+                }
+                config.path = dest;
+            }
+        }
+
+        config.name = new.to_string();
+        self.gifs.insert(new.to_string(), config);
+        for scene in self.scenes.values_mut() {
+            for animation in scene.animations.iter_mut().filter(|a| *a == old) {
+                *animation = new.to_string();
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_gif(&self, name: &str) -> Option<&GifConfig> {
         self.gifs.get(name)
     }
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> GifConfig {
+        GifConfig::new("cat".into(), PathBuf::from("cat.gif"))
+    }
+
+    fn map(entries: &[(&str, u32)]) -> HashMap<String, u32> {
+        entries.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn every_listed_setting_is_known() {
+        for key in SETTINGS {
+            let result = config().set(key, "nonsense");
+            assert!(!result.is_err_and(|e| e.starts_with("Unknown setting")), "{key}");
+        }
+        assert!(config().set("colour", "red").unwrap_err().starts_with("Unknown setting"));
+    }
+
+    #[test]
+    fn set_parses_plain_values() {
+        let mut c = config();
+        c.set("fps", "30").unwrap();
+        c.set("overlay", "off").unwrap();
+        c.set("palette", "#ff0000=#00ff00, #000000=#ffffff").unwrap();
+        assert_eq!((c.fps, c.overlay, c.palette.len()), (Some(30), false, 2));
+
+        c.set("fps", "none").unwrap();
+        c.set("palette", "none").unwrap();
+        assert_eq!((c.fps, c.palette.len()), (None, 0));
+
+        assert!(c.set("fps", "0").is_err());
+        assert!(c.set("overlay", "maybe").is_err());
+        assert!(c.set("align", "middle").is_err());
+        assert_eq!(c.align, "center");
+    }
+
+    #[test]
+    fn positions_switch_to_custom_alignment() {
+        let mut c = config();
+        c.set("y", "-20").unwrap();
+        assert_eq!((c.position, c.align.as_str()), (Some((0, -20)), "custom"));
+        c.set("x", "15").unwrap();
+        assert_eq!(c.position, Some((15, -20)));
+        assert!(c.set("x", "left").is_err());
+    }
+
+    #[test]
+    fn opacity_and_scale_stay_in_range() {
+        let mut c = config();
+        c.set("opacity", &OPACITY_RANGE.start().to_string()).unwrap();
+        assert_eq!(c.opacity, *OPACITY_RANGE.start());
+        assert_eq!(c.set("opacity", "0").unwrap_err(), "opacity must be between 0.05 and 1");
+        assert!(c.set("opacity", "1.5").is_err());
+        assert_eq!(c.opacity, *OPACITY_RANGE.start());
+
+        c.set("scale", "2").unwrap();
+        assert_eq!(c.scale, Some(*SCALE_RANGE.end()));
+        assert!(c.set("scale", "0.05").is_err());
+        assert!(c.set("scale", "2.5").is_err());
+        c.set("scale", "none").unwrap();
+        assert_eq!(c.scale, None);
+    }
+
+    #[test]
+    fn background_keys_replace_each_other() {
+        let mut c = config();
+        assert_eq!(c.set("chroma-tolerance", "0.3").unwrap_err(), "chroma-tolerance needs chroma-key to be set");
+
+        c.set("chroma-key", "#00ff00").unwrap();
+        c.set("chroma-tolerance", "0.3").unwrap();
+        c.set("chroma-key", "auto").unwrap();
+        let Some(BackgroundRemoval::ChromaKey(key)) = &c.background else { panic!("{:?}", c.background) };
+        assert_eq!((key.color, key.tolerance), (None, 0.3));
+        assert!(c.set("chroma-softness", "2").is_err());
+        assert!(c.set("edge-tolerance", "0.2").is_err());
+
+        c.set("edge-fill", "#ffffff").unwrap();
+        c.set("edge-tolerance", "0.2").unwrap();
+        let Some(BackgroundRemoval::EdgeFill(fill)) = &c.background else { panic!("{:?}", c.background) };
+        assert_eq!((fill.color, fill.tolerance), (Some([255, 255, 255]), 0.2));
+
+        c.set("edge-fill", "none").unwrap();
+        assert_eq!(c.background, None);
+    }
+
+    #[test]
+    fn effect_keys_keep_the_other_details() {
+        let mut c = config();
+        assert!(c.set("outline-width", "2").is_err());
+        assert!(c.set("shadow-x", "2").is_err());
+        assert!(c.set("defringe-erode", "2").is_err());
+
+        c.set("outline", "#ff0000").unwrap();
+        c.set("outline-width", "3").unwrap();
+        c.set("outline", "#0000ff").unwrap();
+        assert_eq!(c.outline, Some(Outline { color: [0, 0, 255], thickness: 3 }));
+        assert!(c.set("outline-width", "0").is_err());
+
+        c.set("shadow", "#000000").unwrap();
+        c.set("shadow-x", "-4").unwrap();
+        c.set("shadow-y", "6").unwrap();
+        c.set("shadow-blur", "5").unwrap();
+        c.set("shadow-opacity", "0.25").unwrap();
+        let shadow = c.shadow.clone().unwrap();
+        assert_eq!((shadow.offset, shadow.blur, shadow.opacity), ((-4, 6), 5, 0.25));
+        assert!(c.set("shadow-x", "40").is_err());
+
+        c.set("defringe", "#ffffff").unwrap();
+        c.set("defringe-feather", "2").unwrap();
+        assert_eq!(c.defringe.as_ref().map(|d| (d.matte, d.feather)), Some(([255, 255, 255], 2)));
+        assert!(c.set("defringe-erode", "9").is_err());
+
+        for key in ["outline", "shadow", "defringe"] {
+            c.set(key, "none").unwrap();
+        }
+        assert_eq!((c.outline, c.shadow, c.defringe), (None, None, None));
+    }
+
+    #[test]
+    fn merge_keeps_changes_from_both_sides() {
+        let base = map(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]);
+        // Here: a changed, b removed, e added. On disk: c changed, d removed, f added.
+        let mine = map(&[("a", 2), ("c", 1), ("d", 1), ("e", 1)]);
+        let on_disk = map(&[("a", 1), ("b", 1), ("c", 3), ("f", 1)]);
+
+        assert_eq!(merge(&mine, &base, on_disk), map(&[("a", 2), ("c", 3), ("e", 1), ("f", 1)]));
+    }
+
+    #[test]
+    fn merge_prefers_this_copy_when_both_changed() {
+        // Removing b here wins over changing it on disk too
+        let base = map(&[("a", 1), ("b", 1)]);
+        let mine = map(&[("a", 2)]);
+        let on_disk = map(&[("a", 3), ("b", 4)]);
+
+        assert_eq!(merge(&mine, &base, on_disk), map(&[("a", 2)]));
+    }
+}
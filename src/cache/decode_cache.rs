@@ -0,0 +1,54 @@
+use crate::decoder::{self, palette::PaletteSwap};
+use crate::processing::{self, Padding, ProcessOptions};
+use crate::types::{AnimationInfo, Frame};
+use std::path::{Path, PathBuf};
+
+// Decoded frames shared between animations played by one process.
+// Several library entries often point at the same file with different settings,
+// so the decode is done once and only the processing runs per entry.
+
+struct Entry {
+    path: PathBuf,
+    palette: Vec<PaletteSwap>, // Swaps are applied while decoding, so they are part of the key
+    info: AnimationInfo,
+    frames: Vec<Frame>,
+}
+
+#[derive(Default)]
+pub struct DecodeCache {
+    entries: Vec<Entry>,
+    hits: usize,
+}
+
+impl DecodeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same as `processing::load`, but reuses an earlier decode of the same file and palette
+    pub fn load(&mut self, path: &Path, options: &ProcessOptions) -> Result<(AnimationInfo, Vec<Frame>, Padding), Box<dyn std::error::Error>> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let index = match self.entries.iter().position(|e| e.path == key && e.palette == options.palette) {
+            Some(index) => {
+                self.hits += 1;
+                index
+            }
+            None => {
+                let (info, frames) = decoder::load_animation_with_palette(path, &options.palette)?;
+                self.entries.push(Entry { path: key, palette: options.palette.clone(), info, frames });
+                self.entries.len() - 1
+            }
+        };
+
+        let entry = &self.entries[index];
+        let mut info = entry.info.clone();
+        let mut frames = entry.frames.clone();
+        let padding = processing::process(&mut info, &mut frames, options, Some(path))?;
+        Ok((info, frames, padding))
+    }
+
+    /// Number of loads served without decoding
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
@@ -1,16 +1,25 @@
+use crate::playback::sequence::{FrameRange, FrameSequence, PlaybackMode};
+use crate::processing::interpolate::{self, Interpolation};
+use crate::renderer::pixels;
 use crate::types::Frame;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::time::Duration;
 
 pub struct FrameBuffer {
     frames: Vec<Frame>,
-    current_index: usize,
+    sequence: FrameSequence,
+    premultiplied: Vec<Vec<u32>>, // Paint-ready pixels, filled by `premultiply`
+    hashes: Vec<u64>, // Content hash of each premultiplied frame
 }
 
 impl FrameBuffer {
     pub fn new(frames: Vec<Frame>) -> Self {
+        let sequence = FrameSequence::new(frames.len());
         Self {
             frames,
-            current_index: 0,
+            sequence,
+            premultiplied: Vec::new(),
+            hashes: Vec::new(),
         }
     }
 
@@ -18,17 +27,107 @@ impl FrameBuffer {
         if self.frames.is_empty() {
             panic!("FrameBuffer is empty");
         }
-        let frame = &self.frames[self.current_index];
-        self.current_index = (self.current_index + 1) % self.frames.len();
-        frame
+        let index = self.sequence.advance();
+        &self.frames[index]
     }
 
+    /// Index of the frame most recently returned by `next`, used to repaint without advancing.
+    pub fn current_index(&self) -> usize {
+        self.sequence.current()
+    }
+
+    /// Frame indices of one full cycle in the order the player shows them.
+    /// Playback starts over from the beginning afterwards.
+    pub fn cycle(&mut self) -> Vec<usize> {
+        self.sequence.reset();
+        let order = (0..self.sequence.cycle_len()).map(|_| self.sequence.advance()).collect();
+        self.sequence.reset();
+        order
+    }
+
+    pub fn get(&self, index: usize) -> &Frame {
+        &self.frames[index]
+    }
+
+    pub fn set_mode(&mut self, mode: PlaybackMode) {
+        self.sequence.set_mode(mode);
+    }
+
+    /// Loop over a sub-range of frames only (`None` plays them all)
+    pub fn set_range(&mut self, range: Option<FrameRange>) {
+        self.sequence.set_range(range);
+    }
+
+    /// Apply timing settings: a constant FPS if given, otherwise the speed multiplier
+    pub fn set_timing(&mut self, fps: Option<u32>, speed: f32) {
+        if let Some(fps) = fps.filter(|&fps| fps > 0) {
+            self.override_delay(Duration::from_secs_f64(1.0 / fps as f64));
+        } else if (speed - 1.0).abs() > f32::EPSILON {
+            self.scale_speed(speed);
+        }
+    }
+
+    /// Scale every frame's own delay, keeping the relative timing (2.0 plays twice as fast)
+    pub fn scale_speed(&mut self, multiplier: f32) {
+        let multiplier = multiplier.clamp(0.1, 10.0);
+        for frame in &mut self.frames {
+            frame.delay = frame.delay.div_f32(multiplier);
+        }
+    }
+
+    /// Replace every frame's delay with one constant (fixed FPS)
     pub fn override_delay(&mut self, delay: Duration) {
         for frame in &mut self.frames {
             frame.delay = delay;
         }
     }
 
+    /// Generate in-between frames for the active range, which then becomes the whole buffer.
+    /// Call after any delay changes. Does nothing in random and shuffle modes (see
+    /// `PlaybackMode::can_interpolate`). Returns the extra memory used, in bytes.
+    pub fn interpolate(&mut self, settings: &Interpolation) -> usize {
+        let range = self.sequence.range();
+        let mode = self.sequence.mode();
+        if !mode.can_interpolate() {
+            return 0;
+        }
+        let frames: Vec<Frame> = self.frames.drain(range.start..range.end).collect();
+        let original = frames.iter().map(|f| f.buffer.len()).sum::<usize>();
+
+        self.frames = interpolate::apply(frames, settings, mode != PlaybackMode::PingPong);
+        self.sequence = FrameSequence::new(self.frames.len());
+        self.sequence.set_mode(mode);
+
+        self.frames.iter().map(|f| f.buffer.len()).sum::<usize>().saturating_sub(original)
+    }
+
+    /// Convert every frame to the painter's premultiplied format once, so painting is a copy.
+    /// The RGBA data is released afterwards, so call this after any other changes to the frames.
+    pub fn premultiply(&mut self) {
+        self.premultiplied = self.frames
+            .iter_mut()
+            .map(|frame| pixels::premultiply(&std::mem::take(&mut frame.buffer)))
+            .collect();
+        self.hashes = self.premultiplied
+            .iter()
+            .map(|pixels| {
+                let mut hasher = DefaultHasher::new();
+                pixels.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect();
+    }
+
+    /// Identifies a frame's content, so identical frames can be recognized when painting
+    pub fn content_hash(&self, index: usize) -> u64 {
+        self.hashes.get(index).copied().unwrap_or(index as u64)
+    }
+
+    /// Premultiplied pixels of a frame; empty unless `premultiply` was called
+    pub fn pixels(&self, index: usize) -> &[u32] {
+        self.premultiplied.get(index).map(Vec::as_slice).unwrap_or(&[])
+    }
+
     pub fn len(&self) -> usize {
         self.frames.len()
     }
@@ -0,0 +1,95 @@
+use crate::app::dirs;
+use crate::types::Frame;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// On-disk cache for per-frame background masks, so expensive fills survive restarts.
+// One file per source animation; the header records what the masks were computed from.
+
+const MAGIC: &[u8; 8] = b"GEMASK01";
+
+pub struct MaskKey {
+    file_name: String,
+    fingerprint: u64,
+}
+
+impl MaskKey {
+    /// Build a key from the source file's identity (path, size, mtime) and the settings
+    /// that produced the masks. Returns `None` if the file can't be inspected.
+    pub fn new<P: AsRef<Path>, S: Hash>(source: P, settings: &S) -> Option<Self> {
+        let path = fs::canonicalize(source.as_ref()).ok()?;
+        let meta = fs::metadata(&path).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        let mut name_hasher = DefaultHasher::new();
+        path.hash(&mut name_hasher);
+
+        let mut hasher = DefaultHasher::new();
+        meta.len().hash(&mut hasher);
+        modified.as_nanos().hash(&mut hasher);
+        settings.hash(&mut hasher);
+
+        Some(Self {
+            file_name: format!("{:016x}.mask", name_hasher.finish()),
+            fingerprint: hasher.finish(),
+        })
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    config_dir.join("gif-engine").join("cache")
+}
+
+/// Load cached masks, if they exist and match both the key and the frame layout
+pub fn load(key: &MaskKey, frames: &[Frame]) -> Option<Vec<Vec<bool>>> {
+    let data = fs::read(cache_dir().join(&key.file_name)).ok()?;
+    let header = header(key, frames);
+    if data.len() < header.len() || data[..header.len()] != header[..] {
+        return None;
+    }
+
+    let mut masks = Vec::with_capacity(frames.len());
+    let mut offset = header.len();
+    for frame in frames {
+        let pixels = (frame.width * frame.height) as usize;
+        let bytes = pixels.div_ceil(8);
+        let packed = data.get(offset..offset + bytes)?;
+        masks.push((0..pixels).map(|i| packed[i / 8] & (1 << (i % 8)) != 0).collect());
+        offset += bytes;
+    }
+
+    Some(masks)
+}
+
+pub fn save(key: &MaskKey, frames: &[Frame], masks: &[Vec<bool>]) -> Result<(), std::io::Error> {
+    let dir = cache_dir();
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    let mut data = header(key, frames);
+    for mask in masks {
+        let mut packed = vec![0u8; mask.len().div_ceil(8)];
+        for (i, _) in mask.iter().enumerate().filter(|(_, set)| **set) {
+            packed[i / 8] |= 1 << (i % 8);
+        }
+        data.extend_from_slice(&packed);
+    }
+
+    fs::write(dir.join(&key.file_name), data)
+}
+
+fn header(key: &MaskKey, frames: &[Frame]) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&key.fingerprint.to_le_bytes());
+    header.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+    if let Some(first) = frames.first() {
+        header.extend_from_slice(&first.width.to_le_bytes());
+        header.extend_from_slice(&first.height.to_le_bytes());
+    }
+    header
+}
@@ -1,2 +1,4 @@
+pub mod decode_cache;
 pub mod frame_buffer;
 pub mod memory_pool;
+pub mod mask_cache;
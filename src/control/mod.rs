@@ -0,0 +1,172 @@
+pub mod server;
+#[cfg(target_os = "windows")]
+mod pipe;
+#[cfg(unix)]
+mod socket;
+
+use crate::app::store::GifConfig;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+
+// Control channel between the manager (or the CLI) and running players.
+// Each player process listens on a local endpoint named after its pid: a named pipe on
+// Windows, a Unix domain socket elsewhere. One connection carries one request and one
+// response, each a single line of JSON.
+
+/// Bumped whenever a message changes incompatibly; players refuse other versions
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What a player should do
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Command {
+    Pause,
+    Resume,
+    /// Multiplier on the file's own frame delays, like the `speed` setting
+    SetSpeed { speed: f32 },
+    SetOpacity { opacity: f32 },
+    /// Move the window's top-left corner to desktop coordinates
+    Move { x: i32, y: i32 },
+    /// Decode the file again and apply `config` (or the current settings) in place,
+    /// keeping the window
+    Reload {
+        #[serde(default)]
+        config: Option<Box<GifConfig>>,
+    },
+    /// Close the window, and the process with its last one
+    Quit,
+    /// Report the state of each window
+    Status,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Request {
+    pub version: u32,
+    /// Animation to address in a process playing several; every window when unset
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(flatten)]
+    pub command: Command,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Response {
+    pub version: u32,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Filled in for `status`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub players: Vec<PlayerStatus>,
+}
+
+impl Response {
+    pub fn ok(players: Vec<PlayerStatus>) -> Self {
+        Self { version: PROTOCOL_VERSION, ok: true, error: None, players }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self { version: PROTOCOL_VERSION, ok: false, error: Some(message.into()), players: Vec::new() }
+    }
+
+    /// The player's error as an `Err`
+    pub fn into_result(self) -> Result<Self, String> {
+        if self.ok {
+            Ok(self)
+        } else {
+            Err(self.error.unwrap_or_else(|| "Request failed".to_string()))
+        }
+    }
+}
+
+/// One window's state, as reported by `status`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerStatus {
+    pub name: String,
+    pub paused: bool,
+    pub speed: f32,
+    pub opacity: f32,
+    pub frame: usize,
+    pub frames: usize,
+    pub position: Option<(i32, i32)>,
+}
+
+/// Send `command` to the player with process id `pid` and wait for its answer.
+/// `Err` means the player couldn't be reached; a player refusing the request gives
+/// a response with `ok` unset (see `Response::into_result`).
+pub fn send(pid: u32, target: Option<&str>, command: Command) -> Result<Response, String> {
+    let request = Request { version: PROTOCOL_VERSION, target: target.map(str::to_string), command };
+    let mut line = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+    line.push('\n');
+
+    #[cfg(target_os = "windows")]
+    let stream = pipe::connect(pid);
+    #[cfg(unix)]
+    let stream = socket::connect(pid);
+    let mut stream = stream.map_err(|e| format!("Can't reach player {}: {}", pid, e))?;
+
+    stream.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).map_err(|e| e.to_string())?;
+
+    let response: Response = serde_json::from_str(&reply).map_err(|e| format!("Bad reply from player {}: {}", pid, e))?;
+    if response.version != PROTOCOL_VERSION {
+        return Err(format!("Player {} speaks protocol version {}, expected {}", pid, response.version, PROTOCOL_VERSION));
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn round_trip(request: &Request) -> Request {
+        serde_json::from_str(&serde_json::to_string(request).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn requests_are_flat_json_lines() {
+        let request = Request { version: PROTOCOL_VERSION, target: Some("cat".into()), command: Command::SetSpeed { speed: 1.5 } };
+        let text = serde_json::to_string(&request).unwrap();
+        assert!(!text.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+            serde_json::json!({ "version": PROTOCOL_VERSION, "target": "cat", "command": "set-speed", "speed": 1.5 })
+        );
+    }
+
+    #[test]
+    fn requests_survive_a_round_trip() {
+        let moved = round_trip(&Request { version: PROTOCOL_VERSION, target: None, command: Command::Move { x: -10, y: 20 } });
+        assert!(moved.target.is_none());
+        assert!(matches!(moved.command, Command::Move { x: -10, y: 20 }));
+
+        let config = GifConfig::new("cat".into(), PathBuf::from("cat.gif"));
+        let reload = Command::Reload { config: Some(Box::new(config.clone())) };
+        let reload = round_trip(&Request { version: PROTOCOL_VERSION, target: None, command: reload });
+        assert!(matches!(reload.command, Command::Reload { config: Some(c) } if *c == config));
+
+        // Optional fields may be left out
+        let request: Request = serde_json::from_str(r#"{"version":1,"command":"reload"}"#).unwrap();
+        assert!(matches!(request.command, Command::Reload { config: None }));
+    }
+
+    #[test]
+    fn responses_leave_out_empty_fields() {
+        assert_eq!(serde_json::to_string(&Response::ok(Vec::new())).unwrap(), format!(r#"{{"version":{},"ok":true}}"#, PROTOCOL_VERSION));
+
+        let status = PlayerStatus { name: "cat".into(), paused: true, speed: 2.0, opacity: 0.5, frame: 3, frames: 8, position: Some((1, 2)) };
+        let text = serde_json::to_string(&Response::ok(vec![status])).unwrap();
+        let response: Response = serde_json::from_str(&text).unwrap();
+        assert_eq!(response.players.len(), 1);
+        assert_eq!((response.players[0].frame, response.players[0].position), (3, Some((1, 2))));
+    }
+
+    #[test]
+    fn into_result_turns_refusals_into_errors() {
+        assert!(Response::ok(Vec::new()).into_result().is_ok());
+        assert_eq!(Response::error("No such animation").into_result().unwrap_err(), "No such animation");
+        assert_eq!(Response { ok: false, ..Default::default() }.into_result().unwrap_err(), "Request failed");
+    }
+}
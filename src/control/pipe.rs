@@ -0,0 +1,83 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::windows::io::FromRawHandle;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{CloseHandle, ERROR_FILE_NOT_FOUND, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE};
+use windows::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+    PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+use windows::core::HSTRING;
+
+// Named pipe transport, one pipe name per player process.
+// The default pipe security only lets the creating user (and administrators) write to it.
+
+pub type Stream = File;
+
+// How long a client keeps retrying while the player is between connections. Every instance
+// can be busy, or none may exist at all while the player hasn't created the next one yet.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn name(pid: u32) -> String {
+    format!(r"\\.\pipe\gif-engine-{}", pid)
+}
+
+fn is_between_connections(e: &io::Error) -> bool {
+    [ERROR_PIPE_BUSY, ERROR_FILE_NOT_FOUND].iter().any(|code| e.raw_os_error() == Some(code.0 as i32))
+}
+
+pub fn connect(pid: u32) -> io::Result<Stream> {
+    let start = Instant::now();
+    loop {
+        match OpenOptions::new().read(true).write(true).open(name(pid)) {
+            Err(e) if is_between_connections(&e) && start.elapsed() < BUSY_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// The pipe this process listens on; each accepted connection uses a fresh pipe instance
+pub struct Listener {
+    name: HSTRING,
+}
+
+impl Listener {
+    pub fn bind(pid: u32) -> io::Result<Self> {
+        Ok(Self { name: HSTRING::from(name(pid)) })
+    }
+
+    pub fn accept(&self) -> io::Result<Stream> {
+        unsafe {
+            let handle = CreateNamedPipeW(
+                &self.name,
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                None,
+            );
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+            // A client that connected between creation and this call is fine too
+            if let Err(e) = ConnectNamedPipe(handle, None).or_else(|e| {
+                if e.code() == ERROR_PIPE_CONNECTED.to_hresult() { Ok(()) } else { Err(e) }
+            }) {
+                let _ = CloseHandle(handle);
+                return Err(io::Error::other(e));
+            }
+            Ok(File::from_raw_handle(handle.0 as _))
+        }
+    }
+
+    /// Called once the reply is written: wait until the client has read it, since closing
+    /// the pipe would otherwise throw it away
+    pub fn finish(stream: Stream) {
+        let _ = stream.sync_all();
+    }
+}
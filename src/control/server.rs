@@ -0,0 +1,166 @@
+use super::{PROTOCOL_VERSION, Request, Response};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+use super::pipe::{Listener, Stream};
+#[cfg(unix)]
+use super::socket::{self as transport, Listener, Stream};
+
+// The player side: a thread accepts connections and hands each request to the event loop,
+// which carries it out between frames and sends back the response.
+
+// How long a client is kept waiting for the event loop
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A request waiting to be carried out on the player's thread
+pub struct Pending {
+    pub request: Request,
+    reply: mpsc::Sender<Response>,
+}
+
+impl Pending {
+    pub fn reply(self, response: Response) {
+        let _ = self.reply.send(response);
+    }
+}
+
+/// This process's control endpoint
+pub struct Server {
+    requests: mpsc::Receiver<Pending>,
+}
+
+impl Server {
+    /// Start listening. `wake` is called after each request is queued so a sleeping event loop
+    /// picks it up; it returns false once there's no loop left to wake.
+    pub fn start(wake: impl Fn() -> bool + Send + 'static) -> io::Result<Self> {
+        let listener = Listener::bind(std::process::id())?;
+        let (sender, requests) = mpsc::channel();
+
+        thread::spawn(move || {
+            loop {
+                let stream = match listener.accept() {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        eprintln!("Control endpoint error: {}", e);
+                        thread::sleep(Duration::from_millis(100));
+                        continue;
+                    }
+                };
+                if !serve(stream, &sender, &wake) {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self { requests })
+    }
+
+    /// Next request waiting, if any
+    pub fn try_recv(&self) -> Option<Pending> {
+        self.requests.try_recv().ok()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        transport::remove(std::process::id());
+    }
+}
+
+// Answer one connection. Returns false when the event loop is gone.
+fn serve(stream: Stream, queue: &mpsc::Sender<Pending>, wake: &impl Fn() -> bool) -> bool {
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line).is_err() {
+        return true;
+    }
+
+    let mut alive = true;
+    let response = match parse(&line) {
+        Ok(request) => {
+            let (reply, answer) = mpsc::channel();
+            if queue.send(Pending { request, reply }).is_err() || !wake() {
+                alive = false;
+                Response::error("Player is shutting down")
+            } else {
+                answer.recv_timeout(REPLY_TIMEOUT).unwrap_or_else(|_| Response::error("Player didn't answer"))
+            }
+        }
+        Err(e) => Response::error(e),
+    };
+
+    if let Ok(mut text) = serde_json::to_string(&response) {
+        text.push('\n');
+        let _ = (&stream).write_all(text.as_bytes());
+    }
+    Listener::finish(stream);
+    alive
+}
+
+// The version is checked before the rest, so a newer client gets a clear error
+// rather than a parse failure
+fn parse(line: &str) -> Result<Request, String> {
+    let value: serde_json::Value = serde_json::from_str(line).map_err(|e| format!("Invalid request: {}", e))?;
+    let version = value.get("version").and_then(|v| v.as_u64());
+    if version != Some(PROTOCOL_VERSION as u64) {
+        return Err(format!(
+            "Unsupported protocol version {}, this player speaks {}",
+            version.map(|v| v.to_string()).unwrap_or_else(|| "(none)".to_string()),
+            PROTOCOL_VERSION
+        ));
+    }
+    serde_json::from_value(value).map_err(|e| format!("Invalid request: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::{Command, send};
+
+    #[test]
+    fn parse_accepts_the_current_version() {
+        let request = parse(&format!(r#"{{"version":{},"target":"cat","command":"pause"}}"#, PROTOCOL_VERSION)).unwrap();
+        assert_eq!(request.target.as_deref(), Some("cat"));
+        assert!(matches!(request.command, Command::Pause));
+    }
+
+    #[test]
+    fn parse_checks_the_version_first() {
+        // Even with a command this player doesn't know, the version is what gets reported
+        let error = parse(r#"{"version":99,"command":"teleport"}"#).unwrap_err();
+        assert_eq!(error, format!("Unsupported protocol version 99, this player speaks {}", PROTOCOL_VERSION));
+
+        let error = parse(r#"{"command":"pause"}"#).unwrap_err();
+        assert!(error.contains("version (none)"), "{error}");
+        assert!(parse(r#"{"version":"1","command":"pause"}"#).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_malformed_requests() {
+        assert!(parse("pause").unwrap_err().starts_with("Invalid request"));
+        let error = parse(&format!(r#"{{"version":{},"command":"teleport"}}"#, PROTOCOL_VERSION)).unwrap_err();
+        assert!(error.starts_with("Invalid request"), "{error}");
+    }
+
+    #[test]
+    fn requests_reach_the_event_loop_and_back() {
+        let server = Server::start(|| true).unwrap();
+        let client = thread::spawn(|| send(std::process::id(), Some("cat"), Command::SetOpacity { opacity: 0.5 }));
+
+        let pending = loop {
+            if let Some(pending) = server.try_recv() {
+                break pending;
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+        assert_eq!(pending.request.target.as_deref(), Some("cat"));
+        assert!(matches!(pending.request.command, Command::SetOpacity { opacity } if opacity == 0.5));
+        pending.reply(Response::error("Not now"));
+
+        let response = client.join().unwrap().unwrap();
+        assert_eq!(response.into_result().unwrap_err(), "Not now");
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Unix domain socket transport, one socket file per player process
+
+pub type Stream = UnixStream;
+
+// Long enough for a reload of a large file
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn path(pid: u32) -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(std::env::temp_dir);
+    dir.join(format!("gif-engine-{}.sock", pid))
+}
+
+pub fn connect(pid: u32) -> io::Result<Stream> {
+    let stream = UnixStream::connect(path(pid))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    Ok(stream)
+}
+
+/// The socket this process listens on
+pub struct Listener {
+    listener: UnixListener,
+}
+
+impl Listener {
+    pub fn bind(pid: u32) -> io::Result<Self> {
+        let path = path(pid);
+        // Left behind by a crashed process that had the same pid
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+        // Only the user running the player may control it
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        Ok(Self { listener })
+    }
+
+    pub fn accept(&self) -> io::Result<Stream> {
+        let (stream, _) = self.listener.accept()?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        Ok(stream)
+    }
+
+    /// Called once the reply is written
+    pub fn finish(_stream: Stream) {}
+}
+
+/// Delete the socket file once the process stops listening
+pub fn remove(pid: u32) {
+    let _ = fs::remove_file(path(pid));
+}
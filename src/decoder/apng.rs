@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::path::Path;
-use crate::types::{Frame, AnimationInfo};
+use crate::types::{Frame, AnimationInfo, FrameInfo};
 use std::time::Duration;
 use png::{DisposeOp, BlendOp};
 
@@ -97,11 +97,45 @@ pub fn load_apng<P: AsRef<Path>>(path: P) -> Result<(AnimationInfo, Vec<Frame>),
         height: height as u16,
         frame_count: frames.len(),
         duration: total_duration,
+        palette: Vec::new(),
+        loops: info.animation_control.map(|ac| ac.num_plays).filter(|&plays| plays > 0),
+        format: "apng",
     };
 
     Ok((anim_info, frames))
 }
 
+/// Frame rectangles, delays, disposal and blending as stored in the file
+pub fn read_frame_info<P: AsRef<Path>>(path: P) -> Result<Vec<FrameInfo>, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
+    let mut reader = png::Decoder::new(file).read_info()?;
+    let num_frames = reader.info().animation_control.ok_or("Not an APNG file")?.num_frames as usize;
+
+    let mut frames = Vec::new();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    while frames.len() < num_frames && reader.next_frame(&mut buf).is_ok() {
+        let Some(fc) = reader.info().frame_control else { break };
+        let delay_den = if fc.delay_den == 0 { 100 } else { fc.delay_den };
+        frames.push(FrameInfo {
+            x: fc.x_offset,
+            y: fc.y_offset,
+            width: fc.width,
+            height: fc.height,
+            delay: Duration::from_secs_f64(fc.delay_num as f64 / delay_den as f64),
+            disposal: match fc.dispose_op {
+                DisposeOp::None => "none",
+                DisposeOp::Background => "background",
+                DisposeOp::Previous => "previous",
+            },
+            blend: match fc.blend_op {
+                BlendOp::Source => "source",
+                BlendOp::Over => "over",
+            },
+        });
+    }
+    Ok(frames)
+}
+
 fn clear_region(canvas: &mut [u8], stride: u32, x: u32, y: u32, w: u32, h: u32) {
     for row in 0..h {
         let start = ((y + row) * stride + x) as usize * 4;
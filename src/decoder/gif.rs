@@ -1,40 +1,111 @@
-use crate::types::{AnimationInfo, Frame};
-use image::AnimationDecoder;
+use crate::decoder::palette::{self, PaletteSwap};
+use crate::types::{AnimationInfo, Frame, FrameInfo};
+use gif::DisposalMethod;
 use std::fs::File;
 use std::path::Path;
 
+/// Decode a GIF through its color tables and composite the frames ourselves, which lets
+/// palette swaps be applied to the tables before any pixel is expanded.
 pub fn load_gif<P: AsRef<Path>>(
     path: P,
+    swaps: &[PaletteSwap],
 ) -> Result<(AnimationInfo, Vec<Frame>), Box<dyn std::error::Error>> {
+    let loops = read_loops(path.as_ref());
     let file = File::open(path)?;
-    let decoder = image::codecs::gif::GifDecoder::new(file)?;
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(file)?;
 
-    // Get info before consuming frames?
-    // GifDecoder doesn't expose frame count easily without iterating.
+    // Index maths is done in usize, since the sizes come straight from the file
+    let width = decoder.width() as usize;
+    let height = decoder.height() as usize;
+    let canvas_len = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or("GIF canvas is too large")?;
 
-    let frames = decoder.into_frames().collect_frames()?;
+    let original_palette = decoder.global_palette().map(palette::to_colors).unwrap_or_default();
+    let global_palette = decoder.global_palette().map(|p| {
+        let mut p = p.to_vec();
+        palette::remap(&mut p, swaps);
+        p
+    });
 
-    if frames.is_empty() {
-        return Err("No frames found in GIF".into());
-    }
-
-    let width = frames[0].buffer().width();
-    let height = frames[0].buffer().height();
+    // Full canvas (RGBA), plus a snapshot for DisposalMethod::Previous
+    let mut canvas = vec![0u8; canvas_len];
+    let mut previous_canvas: Option<Vec<u8>> = None;
 
     let mut result_frames = Vec::new();
     let mut total_duration = std::time::Duration::from_secs(0);
 
-    for f in frames {
-        let buffer = f.buffer();
-        let delay: std::time::Duration = f.delay().into();
+    while let Some(frame) = decoder.read_next_frame()? {
+        let local_palette = frame.palette.as_ref().map(|p| {
+            let mut p = p.clone();
+            palette::remap(&mut p, swaps);
+            p
+        });
+        let colors = local_palette.as_deref()
+            .or(global_palette.as_deref())
+            .ok_or("GIF frame has no color table")?;
+
+        if frame.dispose == DisposalMethod::Previous {
+            previous_canvas = Some(canvas.clone());
+        }
+
+        // Draw the frame's indices onto the canvas, skipping the transparent index
+        for row in 0..frame.height as usize {
+            let y = frame.top as usize + row;
+            if y >= height { break; }
+
+            for col in 0..frame.width as usize {
+                let x = frame.left as usize + col;
+                if x >= width { break; }
+
+                let index = frame.buffer[row * frame.width as usize + col];
+                if Some(index) == frame.transparent {
+                    continue;
+                }
+                let Some(rgb) = colors.get(index as usize * 3..index as usize * 3 + 3) else {
+                    continue;
+                };
+
+                let c = (y * width + x) * 4;
+                canvas[c..c + 3].copy_from_slice(rgb);
+                canvas[c + 3] = 255;
+            }
+        }
+
+        let delay = std::time::Duration::from_millis(frame.delay as u64 * 10);
         total_duration += delay;
 
         result_frames.push(Frame {
-            buffer: buffer.to_vec(), // Rgba8 buffer
-            width: buffer.width(),
-            height: buffer.height(),
+            buffer: canvas.clone(),
+            width: width as u32,
+            height: height as u32,
             delay,
         });
+
+        // Dispose this frame before the next one is drawn
+        match frame.dispose {
+            DisposalMethod::Background => {
+                // Background is treated as transparent, like browsers do
+                let left = (frame.left as usize).min(width);
+                let right = (frame.left as usize + frame.width as usize).min(width);
+                for row in frame.top as usize..(frame.top as usize + frame.height as usize).min(height) {
+                    canvas[(row * width + left) * 4..(row * width + right) * 4].fill(0);
+                }
+            }
+            DisposalMethod::Previous => {
+                if let Some(prev) = previous_canvas.take() {
+                    canvas = prev;
+                }
+            }
+            DisposalMethod::Keep | DisposalMethod::Any => {}
+        }
+    }
+
+    if result_frames.is_empty() {
+        return Err("No frames found in GIF".into());
     }
 
     let info = AnimationInfo {
@@ -42,7 +113,198 @@ pub fn load_gif<P: AsRef<Path>>(
         height: height as u16,
         frame_count: result_frames.len(),
         duration: total_duration,
+        palette: original_palette,
+        loops,
+        format: "gif",
     };
 
     Ok((info, result_frames))
 }
+
+/// Frame rectangles, delays and disposal as stored in the file
+pub fn read_frame_info<P: AsRef<Path>>(path: P) -> Result<Vec<FrameInfo>, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(file)?;
+
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame()? {
+        frames.push(FrameInfo {
+            x: frame.left as u32,
+            y: frame.top as u32,
+            width: frame.width as u32,
+            height: frame.height as u32,
+            delay: std::time::Duration::from_millis(frame.delay as u64 * 10),
+            disposal: match frame.dispose {
+                DisposalMethod::Any => "unspecified",
+                DisposalMethod::Keep => "keep",
+                DisposalMethod::Background => "background",
+                DisposalMethod::Previous => "previous",
+            },
+            // Without a transparent index every pixel of the rectangle is replaced
+            blend: if frame.transparent.is_some() { "over" } else { "source" },
+        });
+    }
+    Ok(frames)
+}
+
+// The decoder skips application extensions, so look for the looping block ourselves.
+// Without one a GIF plays once; a count of 0 loops forever, otherwise it repeats that many times.
+fn read_loops(path: &Path) -> Option<u32> {
+    let Ok(data) = std::fs::read(path) else { return None };
+    for id in [&b"NETSCAPE2.0"[..], &b"ANIMEXTS1.0"[..]] {
+        let Some(pos) = data.windows(id.len()).position(|w| w == id) else { continue };
+        // Sub-block of 3 bytes: 1, then the count as u16 little-endian
+        if let Some(&[3, 1, lo, hi]) = data.get(pos + id.len()..pos + id.len() + 4) {
+            return match u16::from_le_bytes([lo, hi]) {
+                0 => None,
+                repeats => Some(repeats as u32 + 1),
+            };
+        }
+    }
+    Some(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::path::PathBuf;
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const GREEN: [u8; 3] = [0, 255, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+
+    // Global table: 0 red, 1 green, 2 blue, 3 unused (the transparent index in these tests)
+    fn write_gif(name: &str, frames: &[gif::Frame]) -> PathBuf {
+        let palette = [RED, GREEN, BLUE, [0, 0, 0]].concat();
+        let mut data = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut data, 4, 4, &palette).unwrap();
+            for frame in frames {
+                encoder.write_frame(frame).unwrap();
+            }
+        }
+        let path = std::env::temp_dir().join(format!("gif-engine-test-{}-{}.gif", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn frame(left: u16, top: u16, width: u16, height: u16, index: u8, dispose: DisposalMethod) -> gif::Frame<'static> {
+        gif::Frame {
+            left,
+            top,
+            width,
+            height,
+            dispose,
+            transparent: Some(3),
+            buffer: Cow::Owned(vec![index; width as usize * height as usize]),
+            ..Default::default()
+        }
+    }
+
+    fn load(name: &str, frames: &[gif::Frame], swaps: &[PaletteSwap]) -> Vec<Frame> {
+        let path = write_gif(name, frames);
+        let result = load_gif(&path, swaps);
+        let _ = std::fs::remove_file(&path);
+        result.unwrap().1
+    }
+
+    fn pixel(frame: &Frame, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * frame.width + x) * 4) as usize;
+        frame.buffer[i..i + 4].try_into().unwrap()
+    }
+
+    fn opaque(rgb: [u8; 3]) -> [u8; 4] {
+        [rgb[0], rgb[1], rgb[2], 255]
+    }
+
+    #[test]
+    fn swaps_recolor_table_entries_not_transparency() {
+        // Swapping onto the transparent index's color must not make pixels transparent,
+        // and the transparent index stays transparent whatever its entry becomes
+        let swaps = [
+            PaletteSwap { from: RED, to: [0, 0, 0] },
+            PaletteSwap { from: [0, 0, 0], to: BLUE },
+        ];
+        let frames = load("swap", &[frame(0, 0, 4, 4, 0, DisposalMethod::Keep), frame(1, 1, 2, 2, 3, DisposalMethod::Keep)], &swaps);
+
+        assert_eq!(pixel(&frames[0], 0, 0), opaque([0, 0, 0]));
+        assert_eq!(pixel(&frames[1], 1, 1), opaque([0, 0, 0]));
+    }
+
+    #[test]
+    fn swaps_apply_to_local_tables() {
+        let mut local = frame(0, 0, 4, 4, 0, DisposalMethod::Keep);
+        local.palette = Some(GREEN.to_vec());
+        let frames = load("local", &[local], &[PaletteSwap { from: GREEN, to: BLUE }]);
+
+        assert_eq!(pixel(&frames[0], 2, 2), opaque(BLUE));
+    }
+
+    #[test]
+    fn keep_leaves_previous_pixels_under_transparency() {
+        let frames = load(
+            "keep",
+            &[frame(0, 0, 4, 4, 0, DisposalMethod::Keep), frame(1, 1, 2, 2, 3, DisposalMethod::Keep)],
+            &[],
+        );
+
+        assert_eq!(pixel(&frames[1], 1, 1), opaque(RED));
+    }
+
+    #[test]
+    fn background_disposal_clears_only_the_frame_rectangle() {
+        let frames = load(
+            "background",
+            &[
+                frame(0, 0, 4, 4, 0, DisposalMethod::Keep),
+                frame(1, 1, 2, 2, 1, DisposalMethod::Background),
+                frame(0, 0, 1, 1, 2, DisposalMethod::Keep),
+            ],
+            &[],
+        );
+
+        assert_eq!(pixel(&frames[1], 1, 1), opaque(GREEN));
+        assert_eq!(pixel(&frames[2], 1, 1), [0, 0, 0, 0]);
+        assert_eq!(pixel(&frames[2], 3, 3), opaque(RED));
+        assert_eq!(pixel(&frames[2], 0, 0), opaque(BLUE));
+    }
+
+    #[test]
+    fn previous_disposal_restores_the_canvas_before_the_frame() {
+        let frames = load(
+            "previous",
+            &[
+                frame(0, 0, 4, 4, 0, DisposalMethod::Keep),
+                frame(1, 1, 2, 2, 1, DisposalMethod::Previous),
+                frame(0, 0, 1, 1, 2, DisposalMethod::Keep),
+            ],
+            &[],
+        );
+
+        assert_eq!(pixel(&frames[1], 2, 2), opaque(GREEN));
+        assert_eq!(pixel(&frames[2], 2, 2), opaque(RED));
+        assert_eq!(pixel(&frames[2], 0, 0), opaque(BLUE));
+    }
+
+    #[test]
+    fn frames_outside_the_canvas_are_clipped() {
+        // A frame hanging off the right edge and one starting past it, both cleared afterwards
+        let frames = load(
+            "clipped",
+            &[
+                frame(2, 2, 4, 4, 1, DisposalMethod::Background),
+                frame(6, 0, 2, 2, 2, DisposalMethod::Background),
+                frame(0, 0, 1, 1, 0, DisposalMethod::Keep),
+            ],
+            &[],
+        );
+
+        assert_eq!(pixel(&frames[0], 3, 3), opaque(GREEN));
+        assert_eq!(pixel(&frames[0], 1, 1), [0, 0, 0, 0]);
+        assert_eq!(pixel(&frames[2], 3, 3), [0, 0, 0, 0]);
+        assert_eq!(pixel(&frames[2], 0, 0), opaque(RED));
+    }
+}
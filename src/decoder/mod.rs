@@ -1,16 +1,35 @@
 pub mod gif;
 pub mod apng;
+pub mod palette;
 
 use std::path::Path;
-use crate::types::{Frame, AnimationInfo};
+use crate::types::{Frame, AnimationInfo, FrameInfo};
+use palette::PaletteSwap;
 
 pub fn load_animation<P: AsRef<Path>>(path: P) -> Result<(AnimationInfo, Vec<Frame>), Box<dyn std::error::Error>> {
+    load_animation_with_palette(path, &[])
+}
+
+/// Load an animation with palette swaps applied while decoding (GIF only, ignored for APNG)
+pub fn load_animation_with_palette<P: AsRef<Path>>(path: P, swaps: &[PaletteSwap]) -> Result<(AnimationInfo, Vec<Frame>), Box<dyn std::error::Error>> {
     let path = path.as_ref();
     let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
 
     match ext.as_str() {
-        "gif" => gif::load_gif(path),
+        "gif" => gif::load_gif(path, swaps),
         "png" | "apng" => apng::load_apng(path),
         _ => Err("Unsupported file format".into()),
     }
 }
+
+/// Per-frame metadata as stored in the file, without compositing the frames
+pub fn read_frame_info<P: AsRef<Path>>(path: P) -> Result<Vec<FrameInfo>, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+
+    match ext.as_str() {
+        "gif" => gif::read_frame_info(path),
+        "png" | "apng" => apng::read_frame_info(path),
+        _ => Err("Unsupported file format".into()),
+    }
+}
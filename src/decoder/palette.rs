@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Replace one palette color with another. Swaps are applied to the GIF color tables
+/// before frames are composited, so recoloring costs nothing per pixel.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
+pub struct PaletteSwap {
+    pub from: [u8; 3],
+    pub to: [u8; 3],
+}
+
+/// CLI form: `#rrggbb=#rrggbb`
+impl FromStr for PaletteSwap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s.split_once('=').ok_or_else(|| format!("Invalid palette swap '{}', expected #rrggbb=#rrggbb", s))?;
+        Ok(PaletteSwap {
+            from: crate::processing::parse_hex_color(from)?,
+            to: crate::processing::parse_hex_color(to)?,
+        })
+    }
+}
+
+impl fmt::Display for PaletteSwap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = crate::processing::format_hex_color;
+        write!(f, "{}={}", hex(self.from), hex(self.to))
+    }
+}
+
+/// Apply the swaps to a flat `rgbrgb...` color table in place
+pub fn remap(palette: &mut [u8], swaps: &[PaletteSwap]) {
+    if swaps.is_empty() {
+        return;
+    }
+
+    for entry in palette.chunks_exact_mut(3) {
+        if let Some(swap) = swaps.iter().find(|s| s.from[..] == entry[..]) {
+            entry.copy_from_slice(&swap.to);
+        }
+    }
+}
+
+pub fn to_colors(palette: &[u8]) -> Vec<[u8; 3]> {
+    palette.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect()
+}
+
+/// Map each source color to the target color at the same index, skipping no-ops
+pub fn swaps_by_index(source: &[[u8; 3]], target: &[[u8; 3]]) -> Vec<PaletteSwap> {
+    let mut swaps: Vec<PaletteSwap> = Vec::new();
+    for (from, to) in source.iter().zip(target) {
+        if from != to && !swaps.iter().any(|s| s.from == *from) {
+            swaps.push(PaletteSwap { from: *from, to: *to });
+        }
+    }
+    swaps
+}
+
+/// Read a palette file: GIMP `.gpl`, JASC or RIFF `.pal`, or `.hex` (one color per line)
+pub fn read_palette_file<P: AsRef<Path>>(path: P) -> Result<Vec<[u8; 3]>, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+    let data = fs::read(path)?;
+
+    let colors = match ext.as_str() {
+        "gpl" => parse_gpl(&String::from_utf8_lossy(&data))?,
+        "pal" if data.starts_with(b"RIFF") => parse_riff_pal(&data)?,
+        "pal" => parse_jasc_pal(&String::from_utf8_lossy(&data))?,
+        "hex" => parse_hex(&String::from_utf8_lossy(&data))?,
+        _ => return Err("Unsupported palette format (expected .gpl, .pal or .hex)".into()),
+    };
+
+    if colors.is_empty() {
+        return Err("Palette file contains no colors".into());
+    }
+    Ok(colors)
+}
+
+fn parse_rgb_triplet(line: &str) -> Option<[u8; 3]> {
+    let mut parts = line.split_whitespace().map(|p| p.parse::<u8>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Some([r, g, b]),
+        _ => None,
+    }
+}
+
+fn parse_gpl(text: &str) -> Result<Vec<[u8; 3]>, Box<dyn std::error::Error>> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err("Missing 'GIMP Palette' header".into());
+    }
+
+    // Header fields like "Name:" and "Columns:" and comments simply fail to parse as colors
+    Ok(lines
+        .map(str::trim)
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(parse_rgb_triplet)
+        .collect())
+}
+
+fn parse_jasc_pal(text: &str) -> Result<Vec<[u8; 3]>, Box<dyn std::error::Error>> {
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some("JASC-PAL") {
+        return Err("Missing 'JASC-PAL' header".into());
+    }
+    let _version = lines.next();
+    let count: usize = lines.next().and_then(|l| l.parse().ok()).ok_or("Missing JASC-PAL color count")?;
+
+    Ok(lines.filter_map(parse_rgb_triplet).take(count).collect())
+}
+
+fn parse_riff_pal(data: &[u8]) -> Result<Vec<[u8; 3]>, Box<dyn std::error::Error>> {
+    // RIFF <size> PAL  data <size> <version:u16> <count:u16> [r g b flags]*
+    if data.len() < 24 || &data[8..12] != b"PAL " || &data[12..16] != b"data" {
+        return Err("Not a RIFF palette".into());
+    }
+    let count = u16::from_le_bytes([data[22], data[23]]) as usize;
+    let entries = data.get(24..24 + count * 4).ok_or("Truncated RIFF palette")?;
+
+    Ok(entries.chunks_exact(4).map(|e| [e[0], e[1], e[2]]).collect())
+}
+
+fn parse_hex(text: &str) -> Result<Vec<[u8; 3]>, Box<dyn std::error::Error>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| crate::processing::parse_hex_color(l).map_err(|e| e.into()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gimp_palettes_skip_header_fields_and_comments() {
+        let text = "GIMP Palette\nName: Test\nColumns: 2\n# comment\n255   0   0\tRed\n  0 128 255 Sky\n";
+        assert_eq!(parse_gpl(text).unwrap(), [[255, 0, 0], [0, 128, 255]]);
+        assert!(parse_gpl("255 0 0\n").is_err());
+    }
+
+    #[test]
+    fn jasc_palettes_read_up_to_the_count() {
+        let text = "JASC-PAL\r\n0100\r\n2\r\n1 2 3\r\n4 5 6\r\n7 8 9\r\n";
+        assert_eq!(parse_jasc_pal(text).unwrap(), [[1, 2, 3], [4, 5, 6]]);
+        assert!(parse_jasc_pal("JASC-PAL\n0100\n").is_err());
+    }
+
+    #[test]
+    fn riff_palettes_drop_the_flags_byte() {
+        let mut data = b"RIFF\0\0\0\0PAL data\0\0\0\0".to_vec();
+        data.extend_from_slice(&[0x00, 0x03, 2, 0]);
+        data.extend_from_slice(&[10, 20, 30, 0, 40, 50, 60, 0]);
+        assert_eq!(parse_riff_pal(&data).unwrap(), [[10, 20, 30], [40, 50, 60]]);
+
+        data.truncate(data.len() - 1);
+        assert!(parse_riff_pal(&data).is_err());
+    }
+
+    #[test]
+    fn hex_palettes_take_one_color_per_line() {
+        assert_eq!(parse_hex("#ff0000\n\n00ff00\n").unwrap(), [[255, 0, 0], [0, 255, 0]]);
+        assert!(parse_hex("#ff0000\nnot a color\n").is_err());
+    }
+
+    #[test]
+    fn files_are_read_by_extension() {
+        let path = std::env::temp_dir().join(format!("gif-engine-test-{}.hex", std::process::id()));
+        std::fs::write(&path, "#010203\n").unwrap();
+        let colors = read_palette_file(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(colors.unwrap(), [[1, 2, 3]]);
+
+        assert!(read_palette_file("palette.act").is_err());
+    }
+
+    #[test]
+    fn swaps_by_index_skip_unchanged_and_repeated_colors() {
+        let source = [[1, 1, 1], [2, 2, 2], [1, 1, 1]];
+        let target = [[9, 9, 9], [2, 2, 2], [8, 8, 8]];
+        assert_eq!(swaps_by_index(&source, &target), [PaletteSwap { from: [1, 1, 1], to: [9, 9, 9] }]);
+    }
+
+    #[test]
+    fn swaps_parse_and_display() {
+        let swap: PaletteSwap = "#ff0000=#00ff00".parse().unwrap();
+        assert_eq!(swap, PaletteSwap { from: [255, 0, 0], to: [0, 255, 0] });
+        assert_eq!(swap.to_string(), "#ff0000=#00ff00");
+        assert!("#ff0000".parse::<PaletteSwap>().is_err());
+    }
+}
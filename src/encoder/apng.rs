@@ -0,0 +1,26 @@
+use super::spread_delays;
+use crate::types::Frame;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+/// Encode full-canvas frames as a lossless animated PNG, with millisecond delays
+pub fn save_apng<P: AsRef<Path>>(path: P, frames: &[&Frame], loops: Option<u32>) -> Result<(), Box<dyn std::error::Error>> {
+    let first = frames.first().ok_or("No frames to export")?;
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, first.width, first.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, loops.unwrap_or(0))?;
+    let mut writer = encoder.write_header()?;
+
+    let delays: Vec<Duration> = frames.iter().map(|f| f.delay).collect();
+    for (frame, delay) in frames.iter().zip(spread_delays(&delays, Duration::from_millis(1))) {
+        writer.set_frame_delay(u16::try_from(delay).unwrap_or(u16::MAX), 1000)?;
+        writer.write_image_data(&frame.buffer)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
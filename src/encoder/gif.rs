@@ -0,0 +1,303 @@
+use super::quantize::{Dither, Palette};
+use super::spread_delays;
+use crate::types::Frame;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Where GIF colors come from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PaletteMode {
+    /// One color table for the whole animation; smallest files, no color flicker between frames
+    #[default]
+    Global,
+    /// A color table per frame; better colors when the scenes change a lot
+    PerFrame,
+}
+
+impl PaletteMode {
+    pub const ALL: [PaletteMode; 2] = [PaletteMode::Global, PaletteMode::PerFrame];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaletteMode::Global => "global",
+            PaletteMode::PerFrame => "per-frame",
+        }
+    }
+}
+
+impl FromStr for PaletteMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PaletteMode::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .ok_or_else(|| format!("Unknown palette mode '{}' (global, per-frame)", s))
+    }
+}
+
+impl fmt::Display for PaletteMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// GIF quantization settings
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GifOptions {
+    #[serde(default = "default_colors")]
+    pub colors: usize, // 2-256, transparency included
+    #[serde(default)]
+    pub dither: Dither,
+    #[serde(default)]
+    pub palette: PaletteMode,
+    /// Merge repeated frames and store only what changed from the previous frame
+    #[serde(default)]
+    pub optimize: bool,
+}
+
+pub fn default_colors() -> usize {
+    256
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            colors: default_colors(),
+            dither: Dither::default(),
+            palette: PaletteMode::default(),
+            optimize: false,
+        }
+    }
+}
+
+/// Encode full-canvas frames as a GIF. Delays are stored in hundredths of a second,
+/// with rounding spread so the total length is kept.
+pub fn save_gif<P: AsRef<Path>>(path: P, frames: &[&Frame], loops: Option<u32>, options: &GifOptions) -> Result<(), Box<dyn std::error::Error>> {
+    if options.optimize {
+        return save_gif_optimized(path, frames, loops, options);
+    }
+
+    let first = frames.first().ok_or("No frames to export")?;
+    let (width, height) = (u16::try_from(first.width)?, u16::try_from(first.height)?);
+
+    let mut global = match options.palette {
+        PaletteMode::Global => Some(Palette::new(frames, options.colors, false)),
+        PaletteMode::PerFrame => None,
+    };
+    let global_rgb = global.as_ref().map(Palette::to_rgb).unwrap_or_default();
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(file, width, height, &global_rgb)?;
+    write_loops(&mut encoder, loops)?;
+
+    let delays: Vec<Duration> = frames.iter().map(|f| f.delay).collect();
+    let delays = spread_delays(&delays, Duration::from_millis(10));
+
+    for (frame, delay) in frames.iter().zip(delays) {
+        let mut local = None;
+        let palette = match &mut global {
+            Some(palette) => palette,
+            None => local.insert(Palette::new(&[frame], options.colors, false)),
+        };
+
+        let indices = palette.index(frame, options.dither);
+        encoder.write_frame(&gif::Frame {
+            width: frame.width as u16,
+            height: frame.height as u16,
+            delay: u16::try_from(delay).unwrap_or(u16::MAX),
+            // Every frame covers the canvas, so clear it for the transparent areas of the next
+            dispose: gif::DisposalMethod::Background,
+            transparent: palette.transparent.then_some(0),
+            palette: local.as_ref().map(Palette::to_rgb),
+            buffer: Cow::Owned(indices),
+            ..gif::Frame::default()
+        })?;
+    }
+    Ok(())
+}
+
+fn write_loops<W: std::io::Write>(encoder: &mut gif::Encoder<W>, loops: Option<u32>) -> Result<(), gif::EncodingError> {
+    match loops {
+        None => encoder.set_repeat(gif::Repeat::Infinite),
+        Some(1) => Ok(()), // Playing once is what a GIF without a loop block does
+        Some(n) => encoder.set_repeat(gif::Repeat::Finite(u16::try_from(n - 1).unwrap_or(u16::MAX))),
+    }
+}
+
+// One frame of an optimized GIF: a sub-rectangle drawn over what is already on screen
+struct Patch {
+    rect: (usize, usize, usize, usize), // x, y, width, height
+    delay: Duration,
+    clear: bool, // Dispose to transparent before the next frame
+}
+
+/// Optimized GIF: one shared palette with index 0 kept transparent, consecutive identical
+/// frames merged, and each frame cropped to the area that changed. Inside that area, pixels
+/// that are already on screen are written as transparent, which compresses much better.
+/// When pixels turn transparent the previous frame covers the canvas and is cleared instead.
+fn save_gif_optimized<P: AsRef<Path>>(path: P, frames: &[&Frame], loops: Option<u32>, options: &GifOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let first = frames.first().ok_or("No frames to export")?;
+    // Checked before any work; rectangles inside the canvas then fit in u16 too
+    let (canvas_width, canvas_height) = (u16::try_from(first.width)?, u16::try_from(first.height)?);
+    let (width, height) = (canvas_width as usize, canvas_height as usize);
+    let mut palette = Palette::new(frames, options.colors, true);
+
+    // Quantize, merging frames that come out identical into one longer frame
+    let mut indexed: Vec<(Vec<u8>, Duration)> = Vec::new();
+    for frame in frames {
+        let indices = palette.index(frame, options.dither);
+        match indexed.last_mut() {
+            Some((last, delay)) if *last == indices => *delay += frame.delay,
+            _ => indexed.push((indices, frame.delay)),
+        }
+    }
+
+    // Work out each frame's rectangle against the canvas the decoder will be showing
+    let full = (0, 0, width, height);
+    let mut patches: Vec<Patch> = Vec::with_capacity(indexed.len());
+    let mut shown = vec![0u8; width * height];
+    let mut bases = Vec::with_capacity(indexed.len());
+    for (indices, delay) in &indexed {
+        let uncovers = shown.iter().zip(indices).any(|(&s, &i)| s != 0 && i == 0);
+        if let Some(previous) = patches.last_mut().filter(|_| uncovers) {
+            previous.rect = full;
+            previous.clear = true;
+            shown.fill(0);
+        }
+
+        let rect = changed_rect(&shown, indices, width, height).unwrap_or((0, 0, 1, 1));
+        patches.push(Patch { rect, delay: *delay, clear: false });
+        bases.push(std::mem::replace(&mut shown, indices.clone()));
+    }
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(file, canvas_width, canvas_height, &palette.to_rgb())?;
+    write_loops(&mut encoder, loops)?;
+
+    let delays: Vec<Duration> = patches.iter().map(|p| p.delay).collect();
+    let delays = spread_delays(&delays, Duration::from_millis(10));
+    for (((patch, (indices, _)), base), delay) in patches.iter().zip(&indexed).zip(&bases).zip(delays) {
+        let (x0, y0, w, h) = patch.rect;
+        let mut buffer = Vec::with_capacity(w * h);
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                let i = y * width + x;
+                // Leave pixels that are already showing the right color untouched
+                buffer.push(if indices[i] == base[i] { 0 } else { indices[i] });
+            }
+        }
+
+        encoder.write_frame(&gif::Frame {
+            left: x0 as u16,
+            top: y0 as u16,
+            width: w as u16,
+            height: h as u16,
+            delay: u16::try_from(delay).unwrap_or(u16::MAX),
+            dispose: if patch.clear { gif::DisposalMethod::Background } else { gif::DisposalMethod::Keep },
+            transparent: Some(0),
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        })?;
+    }
+    Ok(())
+}
+
+// Bounding box of the pixels that differ between two index buffers
+fn changed_rect(a: &[u8], b: &[u8], width: usize, height: usize) -> Option<(usize, usize, usize, usize)> {
+    let (mut x0, mut y0, mut x1, mut y1) = (width, height, 0, 0);
+    for y in 0..height {
+        let row = y * width..(y + 1) * width;
+        let (ra, rb) = (&a[row.clone()], &b[row]);
+        let Some(left) = ra.iter().zip(rb).position(|(p, q)| p != q) else { continue };
+        let right = ra.iter().zip(rb).rposition(|(p, q)| p != q).unwrap_or(left);
+        x0 = x0.min(left);
+        x1 = x1.max(right + 1);
+        y0 = y0.min(y);
+        y1 = y + 1;
+    }
+    (x0 < x1).then(|| (x0, y0, x1 - x0, y1 - y0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(pixels: &[[u8; 4]], delay: u64) -> Frame {
+        Frame { buffer: pixels.concat(), width: 2, height: 2, delay: Duration::from_millis(delay) }
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    // Save optimized, then read back both the composited frames and the stored rectangles
+    fn save_optimized(name: &str, frames: &[Frame]) -> (Vec<Frame>, Vec<crate::types::FrameInfo>) {
+        let refs: Vec<&Frame> = frames.iter().collect();
+        let path = std::env::temp_dir().join(format!("gif-engine-test-{}-{}.gif", std::process::id(), name));
+        let options = GifOptions { optimize: true, ..GifOptions::default() };
+        let result = save_gif(&path, &refs, None, &options);
+        let loaded = crate::decoder::load_animation(&path).map(|(_, frames)| frames);
+        let info = crate::decoder::read_frame_info(&path);
+        let _ = std::fs::remove_file(&path);
+        result.unwrap();
+        (loaded.unwrap(), info.unwrap())
+    }
+
+    #[test]
+    fn changed_rect_bounds_the_differences() {
+        let a = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let b = [0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 2, 0];
+        assert_eq!(changed_rect(&a, &b, 4, 3), Some((1, 1, 2, 2)));
+        assert_eq!(changed_rect(&a, &a, 4, 3), None);
+    }
+
+    #[test]
+    fn identical_frames_are_merged() {
+        let frames = [frame(&[RED; 4], 100), frame(&[RED; 4], 150), frame(&[BLUE; 4], 100)];
+        let (loaded, info) = save_optimized("merged", &frames);
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].delay, Duration::from_millis(250));
+        assert_eq!(loaded[1].buffer, [BLUE; 4].concat());
+        assert_eq!(info.len(), 2);
+    }
+
+    #[test]
+    fn later_frames_store_only_what_changed() {
+        let frames = [frame(&[RED; 4], 100), frame(&[RED, RED, RED, BLUE], 100)];
+        let (loaded, info) = save_optimized("cropped", &frames);
+
+        assert_eq!(loaded[1].buffer, [RED, RED, RED, BLUE].concat());
+        assert_eq!((info[1].x, info[1].y, info[1].width, info[1].height), (1, 1, 1, 1));
+        assert_eq!(info[1].disposal, "keep");
+    }
+
+    #[test]
+    fn uncovered_pixels_clear_the_previous_frame() {
+        let frames = [frame(&[RED; 4], 100), frame(&[RED, CLEAR, RED, RED], 100)];
+        let (loaded, info) = save_optimized("uncovered", &frames);
+
+        assert_eq!(loaded[1].buffer, [RED, CLEAR, RED, RED].concat());
+        assert_eq!((info[0].width, info[0].height), (2, 2));
+        assert_eq!(info[0].disposal, "background");
+    }
+
+    #[test]
+    fn canvases_wider_than_a_gif_allows_are_rejected() {
+        let wide = Frame { buffer: vec![0; 70_000 * 4], width: 70_000, height: 1, delay: Duration::from_millis(100) };
+        let path = std::env::temp_dir().join(format!("gif-engine-test-{}-wide.gif", std::process::id()));
+        for optimize in [false, true] {
+            let options = GifOptions { optimize, ..GifOptions::default() };
+            assert!(save_gif(&path, &[&wide], None, &options).is_err());
+            assert!(!path.exists());
+        }
+    }
+}
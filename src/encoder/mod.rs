@@ -0,0 +1,337 @@
+pub mod apng;
+pub mod gif;
+pub mod png;
+pub mod quantize;
+pub mod webp;
+
+use crate::types::Frame;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Animated formats processed frames can be exported to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Gif,
+    Apng,
+    WebP,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Gif, ExportFormat::Apng, ExportFormat::WebP];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Gif => "gif",
+            ExportFormat::Apng => "apng",
+            ExportFormat::WebP => "webp",
+        }
+    }
+
+    /// File extensions, the usual one first
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ExportFormat::Gif => &["gif"],
+            ExportFormat::Apng => &["png", "apng"],
+            ExportFormat::WebP => &["webp"],
+        }
+    }
+
+    /// Pick the format from a file name's extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        ExportFormat::ALL.into_iter().find(|f| f.extensions().contains(&ext.as_str()))
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ExportFormat::ALL
+            .into_iter()
+            .find(|f| f.as_str() == s)
+            .ok_or_else(|| format!("Unknown format '{}' (gif, apng, webp)", s))
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Encode `frames` (all the same size) to `path`. GIF settings only apply to GIF output.
+/// Returns the size of the written file in bytes.
+pub fn export(path: &Path, format: ExportFormat, frames: &[&Frame], loops: Option<u32>, gif_options: &gif::GifOptions) -> Result<u64, Box<dyn std::error::Error>> {
+    if frames.iter().any(|f| f.width != frames[0].width || f.height != frames[0].height) {
+        return Err("All frames must be the same size".into());
+    }
+
+    match format {
+        ExportFormat::Gif => gif::save_gif(path, frames, loops, gif_options)?,
+        ExportFormat::Apng => apng::save_apng(path, frames, loops)?,
+        ExportFormat::WebP => webp::save_webp(path, frames, loops)?,
+    }
+    Ok(std::fs::metadata(path)?.len())
+}
+
+/// Re-encode a GIF as an optimized GIF (see `GifOptions::optimize`) and replace the file only if
+/// the result is smaller and shows exactly the same frames for the same total time.
+/// Returns the file size before and after; they are equal if the file was kept.
+pub fn optimize_in_place(path: &Path, options: &gif::GifOptions) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    let before = std::fs::metadata(path)?.len();
+    let (info, frames) = crate::decoder::load_animation(path)?;
+    let refs: Vec<&Frame> = frames.iter().collect();
+
+    let temp = path.with_extension("tmp.gif");
+    let options = gif::GifOptions { optimize: true, ..options.clone() };
+    let result = gif::save_gif(&temp, &refs, info.loops, &options)
+        .and_then(|_| {
+            let (_, optimized) = crate::decoder::load_animation(&temp)?;
+            Ok(same_animation(&frames, &optimized))
+        });
+
+    let after = std::fs::metadata(&temp).map(|m| m.len()).unwrap_or(before);
+    match result {
+        Ok(true) if after < before => {
+            std::fs::rename(&temp, path)?;
+            Ok((before, after))
+        }
+        Ok(true) => {
+            let _ = std::fs::remove_file(&temp);
+            Ok((before, before))
+        }
+        Ok(false) => {
+            let _ = std::fs::remove_file(&temp);
+            Err("Optimizing would change colors (too many for one palette)".into())
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+// Whether `optimized` shows the same pictures as `original` over the same time, allowing for
+// runs of identical frames having been merged and for delays rounded to GIF precision
+fn same_animation(original: &[Frame], optimized: &[Frame]) -> bool {
+    let same = |a: &Frame, b: &Frame| {
+        a.buffer.len() == b.buffer.len()
+            && a.buffer.chunks_exact(4).zip(b.buffer.chunks_exact(4)).all(|(p, q)| p == q || (p[3] == 0 && q[3] == 0))
+    };
+
+    let mut j = 0;
+    for (i, frame) in original.iter().enumerate() {
+        let Some(current) = optimized.get(j) else { return false };
+        if same(frame, current) {
+            continue;
+        }
+        // A changed picture has to be the next optimized frame (and never the first original)
+        j += 1;
+        match optimized.get(j) {
+            Some(next) if i > 0 && same(frame, next) => {}
+            _ => return false,
+        }
+    }
+
+    let total = |frames: &[Frame]| frames.iter().map(|f| f.delay).sum::<Duration>();
+    j + 1 == optimized.len() && total(original).abs_diff(total(optimized)) < Duration::from_millis(10)
+}
+
+// Convert delays to whole `unit`s, carrying the rounding so the total length is kept
+fn spread_delays(delays: &[Duration], unit: Duration) -> Vec<u64> {
+    let unit = unit.as_secs_f64();
+    let mut elapsed = 0.0;
+    let mut written = 0u64;
+    delays
+        .iter()
+        .map(|delay| {
+            elapsed += delay.as_secs_f64();
+            let end = (elapsed / unit).round() as u64;
+            let units = end.saturating_sub(written);
+            written += units;
+            units
+        })
+        .collect()
+}
+
+/// Lay `frames` out on a grid, left to right and top to bottom, `columns` per row.
+/// Cells are as large as the largest frame; smaller frames sit in the top-left of their cell.
+pub fn contact_sheet(frames: &[&Frame], columns: usize, gap: u32) -> Frame {
+    let columns = columns.clamp(1, frames.len().max(1));
+    let rows = frames.len().div_ceil(columns);
+    let cell_w = frames.iter().map(|f| f.width).max().unwrap_or(0);
+    let cell_h = frames.iter().map(|f| f.height).max().unwrap_or(0);
+
+    let width = columns as u32 * cell_w + (columns as u32).saturating_sub(1) * gap;
+    let height = rows as u32 * cell_h + (rows as u32).saturating_sub(1) * gap;
+    let mut buffer = vec![0u8; (width * height * 4) as usize];
+
+    for (i, frame) in frames.iter().enumerate() {
+        let x0 = (i % columns) as u32 * (cell_w + gap);
+        let y0 = (i / columns) as u32 * (cell_h + gap);
+        let row_len = frame.width as usize * 4;
+        for y in 0..frame.height {
+            let src = y as usize * row_len;
+            let dst = (((y0 + y) * width + x0) * 4) as usize;
+            buffer[dst..dst + row_len].copy_from_slice(&frame.buffer[src..src + row_len]);
+        }
+    }
+
+    Frame {
+        buffer,
+        width,
+        height,
+        delay: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn frame(width: u32, height: u32, pixels: &[[u8; 4]], delay: u64) -> Frame {
+        Frame { buffer: pixels.concat(), width, height, delay: Duration::from_millis(delay) }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gif-engine-test-{}-{}", std::process::id(), name))
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    fn animation() -> Vec<Frame> {
+        vec![
+            frame(2, 2, &[RED, BLUE, CLEAR, RED], 100),
+            frame(2, 2, &[BLUE, CLEAR, RED, BLUE], 250),
+        ]
+    }
+
+    // Export, decode again, and clean up
+    fn round_trip(name: &str, format: ExportFormat, loops: Option<u32>) -> (crate::types::AnimationInfo, Vec<Frame>) {
+        let frames = animation();
+        let refs: Vec<&Frame> = frames.iter().collect();
+        let path = temp_path(name);
+        let size = export(&path, format, &refs, loops, &gif::GifOptions::default());
+        let loaded = crate::decoder::load_animation(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(size.unwrap() > 0);
+        loaded.unwrap()
+    }
+
+    #[test]
+    fn formats_come_from_names_and_extensions() {
+        assert_eq!(ExportFormat::from_path(Path::new("a.GIF")), Some(ExportFormat::Gif));
+        assert_eq!(ExportFormat::from_path(Path::new("a.png")), Some(ExportFormat::Apng));
+        assert_eq!(ExportFormat::from_path(Path::new("a.apng")), Some(ExportFormat::Apng));
+        assert_eq!(ExportFormat::from_path(Path::new("a.webp")), Some(ExportFormat::WebP));
+        assert_eq!(ExportFormat::from_path(Path::new("a.bmp")), None);
+        assert_eq!(ExportFormat::from_path(Path::new("gif")), None);
+
+        for format in ExportFormat::ALL {
+            assert_eq!(format.to_string().parse::<ExportFormat>(), Ok(format));
+        }
+        assert!("png".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn spread_delays_keeps_the_total_length() {
+        let delays = [Duration::from_millis(33); 3];
+        assert_eq!(spread_delays(&delays, Duration::from_millis(10)), [3, 4, 3]);
+        assert_eq!(spread_delays(&[Duration::from_millis(4); 5], Duration::from_millis(10)), [0, 1, 0, 1, 0]);
+        assert_eq!(spread_delays(&delays, Duration::from_millis(1)), [33, 33, 33]);
+    }
+
+    #[test]
+    fn contact_sheet_lays_frames_out_in_rows() {
+        let small = frame(1, 1, &[BLUE], 0);
+        let big = frame(2, 1, &[RED, RED], 0);
+        let sheet = contact_sheet(&[&big, &small, &small], 2, 1);
+
+        assert_eq!((sheet.width, sheet.height), (5, 3));
+        let pixel = |x: u32, y: u32| -> [u8; 4] {
+            let i = ((y * sheet.width + x) * 4) as usize;
+            sheet.buffer[i..i + 4].try_into().unwrap()
+        };
+        assert_eq!(pixel(1, 0), RED);
+        assert_eq!(pixel(2, 0), CLEAR);
+        assert_eq!(pixel(3, 0), BLUE);
+        assert_eq!(pixel(4, 0), CLEAR);
+        assert_eq!(pixel(0, 2), BLUE);
+        assert_eq!(pixel(0, 1), CLEAR);
+    }
+
+    #[test]
+    fn export_rejects_frames_of_different_sizes() {
+        let frames = [frame(1, 1, &[RED], 100), frame(2, 1, &[RED, RED], 100)];
+        let path = temp_path("mixed.gif");
+        assert!(export(&path, ExportFormat::Gif, &[&frames[0], &frames[1]], None, &gif::GifOptions::default()).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn gif_round_trip_keeps_pixels_delays_and_loops() {
+        let (info, frames) = round_trip("round.gif", ExportFormat::Gif, Some(3));
+        assert_eq!(info.loops, Some(3));
+        assert_eq!(frames.len(), 2);
+        for (original, loaded) in animation().iter().zip(&frames) {
+            assert_eq!(loaded.buffer, original.buffer);
+            assert_eq!(loaded.delay, original.delay);
+        }
+
+        let (info, _) = round_trip("forever.gif", ExportFormat::Gif, None);
+        assert_eq!(info.loops, None);
+    }
+
+    #[test]
+    fn apng_round_trip_keeps_pixels_delays_and_loops() {
+        let (info, frames) = round_trip("round.png", ExportFormat::Apng, Some(2));
+        assert_eq!(info.loops, Some(2));
+        assert_eq!(frames.len(), 2);
+        for (original, loaded) in animation().iter().zip(&frames) {
+            assert_eq!(loaded.buffer, original.buffer);
+            assert_eq!(loaded.delay, original.delay);
+        }
+    }
+
+    #[test]
+    fn same_animation_allows_merged_frames_and_rounded_delays() {
+        let original = [frame(1, 1, &[RED], 40), frame(1, 1, &[RED], 64), frame(1, 1, &[BLUE], 100)];
+        let merged = [frame(1, 1, &[RED], 100), frame(1, 1, &[BLUE], 100)];
+        assert!(same_animation(&original, &merged));
+
+        // Transparent pixels match whatever their color channels hold
+        assert!(same_animation(&[frame(1, 1, &[CLEAR], 100)], &[frame(1, 1, &[[9, 9, 9, 0]], 100)]));
+
+        assert!(!same_animation(&original, &[frame(1, 1, &[RED], 100), frame(1, 1, &[RED], 100)]));
+        assert!(!same_animation(&original, &[frame(1, 1, &[RED], 100), frame(1, 1, &[BLUE], 200)]));
+        assert!(!same_animation(&original, &merged[..1]));
+    }
+
+    #[test]
+    fn optimize_in_place_shrinks_without_changing_the_animation() {
+        // A large still picture with one pixel changing, saved unoptimized first
+        let mut frames: Vec<Frame> = (0..6).map(|_| frame(32, 32, &[RED; 32 * 32], 100)).collect();
+        for (i, f) in frames.iter_mut().enumerate() {
+            f.buffer[i * 4..i * 4 + 4].copy_from_slice(&BLUE);
+        }
+        let refs: Vec<&Frame> = frames.iter().collect();
+        let path = temp_path("optimize.gif");
+        gif::save_gif(&path, &refs, None, &gif::GifOptions::default()).unwrap();
+
+        let result = optimize_in_place(&path, &gif::GifOptions::default());
+        let loaded = crate::decoder::load_animation(&path);
+        let leftover = path.with_extension("tmp.gif").exists();
+        let _ = std::fs::remove_file(&path);
+
+        let (before, after) = result.unwrap();
+        assert!(after < before, "{after} >= {before}");
+        assert!(same_animation(&frames, &loaded.unwrap().1));
+        assert!(!leftover);
+    }
+}
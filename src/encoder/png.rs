@@ -0,0 +1,43 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use crate::types::Frame;
+
+/// Write one frame as a still RGBA PNG
+pub fn save_png<P: AsRef<Path>>(path: P, frame: &Frame) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), frame.width, frame.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&frame.buffer)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_rgba_pixels_unchanged() {
+        let frame = Frame {
+            buffer: vec![255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 0, 0, 1, 2, 3, 4],
+            width: 2,
+            height: 2,
+            delay: Default::default(),
+        };
+        let path = std::env::temp_dir().join(format!("gif-engine-test-{}-still.png", std::process::id()));
+        let result = save_png(&path, &frame);
+        let decoded = File::open(&path).map(|file| {
+            let mut reader = png::Decoder::new(file).read_info().unwrap();
+            let mut buffer = vec![0; reader.output_buffer_size()];
+            let info = reader.next_frame(&mut buffer).unwrap();
+            (info.width, info.height, info.color_type, buffer)
+        });
+        let _ = std::fs::remove_file(&path);
+        result.unwrap();
+
+        assert_eq!(decoded.unwrap(), (2, 2, png::ColorType::Rgba, frame.buffer));
+    }
+}
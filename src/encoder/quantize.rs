@@ -0,0 +1,352 @@
+use crate::types::Frame;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+// Reduces RGBA frames to an indexed palette for GIF output.
+// Pixels below half opacity become fully transparent (GIF has 1-bit alpha) and use index 0.
+
+const ALPHA_CUTOFF: u8 = 128;
+
+/// How colors that aren't in the palette are approximated
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Dither {
+    /// Nearest palette color; flat areas stay flat, gradients band
+    None,
+    /// Error diffusion; smooth gradients, but noise that changes between frames
+    #[default]
+    FloydSteinberg,
+    /// Bayer pattern; stable between frames, visible cross-hatch
+    Ordered,
+}
+
+impl Dither {
+    pub const ALL: [Dither; 3] = [Dither::None, Dither::FloydSteinberg, Dither::Ordered];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dither::None => "none",
+            Dither::FloydSteinberg => "floyd-steinberg",
+            Dither::Ordered => "ordered",
+        }
+    }
+}
+
+impl FromStr for Dither {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Dither::ALL
+            .into_iter()
+            .find(|d| d.as_str() == s)
+            .ok_or_else(|| format!("Unknown dithering '{}' (none, floyd-steinberg, ordered)", s))
+    }
+}
+
+impl fmt::Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A palette of up to 256 colors; index 0 is reserved for transparency when `transparent` is set
+pub struct Palette {
+    pub colors: Vec<[u8; 3]>,
+    pub transparent: bool,
+    lookup: HashMap<[u8; 3], u8>, // Nearest-color cache
+}
+
+impl Palette {
+    /// Build a palette of at most `max_colors` entries (transparency included) for `frames`.
+    /// Animations with no more colors than that keep them exactly.
+    /// `reserve_transparent` keeps index 0 free even if no pixel is transparent.
+    pub fn new(frames: &[&Frame], max_colors: usize, reserve_transparent: bool) -> Self {
+        let max_colors = max_colors.clamp(2, 256);
+
+        let mut histogram: HashMap<[u8; 3], u32> = HashMap::new();
+        let mut transparent = reserve_transparent;
+        // Every opaque pixel is counted (no sampling), so no color that `index` meets is
+        // missing from the palette's input
+        for frame in frames {
+            for px in frame.buffer.chunks_exact(4) {
+                if px[3] < ALPHA_CUTOFF {
+                    transparent = true;
+                } else {
+                    *histogram.entry([px[0], px[1], px[2]]).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let slots = max_colors - transparent as usize;
+        let mut colors = if transparent { vec![[0, 0, 0]] } else { Vec::new() };
+        colors.extend(median_cut(histogram.into_iter().collect(), slots));
+        if colors.is_empty() {
+            colors.push([0, 0, 0]);
+        }
+
+        Self { colors, transparent, lookup: HashMap::new() }
+    }
+
+    /// Flattened RGB triples, padded to a power of two as GIF color tables require
+    pub fn to_rgb(&self) -> Vec<u8> {
+        let size = self.colors.len().next_power_of_two().max(2);
+        let mut rgb: Vec<u8> = self.colors.iter().flatten().copied().collect();
+        rgb.resize(size * 3, 0);
+        rgb
+    }
+
+    fn nearest(&mut self, color: [u8; 3]) -> u8 {
+        if let Some(&index) = self.lookup.get(&color) {
+            return index;
+        }
+        // With nothing but the transparent entry (frames that weren't part of `new`),
+        // index 0 is the only valid answer
+        let first = (self.transparent && self.colors.len() > 1) as usize;
+        let mut best = (u32::MAX, first);
+        for (i, c) in self.colors.iter().enumerate().skip(first) {
+            let d = distance(*c, color);
+            if d < best.0 {
+                best = (d, i);
+            }
+        }
+        self.lookup.insert(color, best.1 as u8);
+        best.1 as u8
+    }
+
+    /// Map a frame to palette indices
+    pub fn index(&mut self, frame: &Frame, dither: Dither) -> Vec<u8> {
+        let (w, h) = (frame.width as usize, frame.height as usize);
+        let mut out = vec![0u8; w * h];
+
+        // Error carried to the current and next row, per channel
+        let mut errors = vec![[0f32; 3]; (w + 2) * 2];
+        for y in 0..h {
+            let (cur, next) = errors.split_at_mut(w + 2);
+            let (cur, next) = if y % 2 == 0 { (cur, next) } else { (next, cur) };
+            next.fill([0.0; 3]);
+
+            for x in 0..w {
+                let i = y * w + x;
+                let px = &frame.buffer[i * 4..i * 4 + 4];
+                if px[3] < ALPHA_CUTOFF && self.transparent {
+                    out[i] = 0;
+                    continue;
+                }
+
+                let mut color = [px[0] as f32, px[1] as f32, px[2] as f32];
+                match dither {
+                    Dither::None => {}
+                    Dither::FloydSteinberg => {
+                        for (c, e) in color.iter_mut().zip(cur[x + 1]) {
+                            *c += e;
+                        }
+                    }
+                    Dither::Ordered => {
+                        let offset = (BAYER[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5;
+                        for c in &mut color {
+                            *c += offset * ORDERED_SPREAD;
+                        }
+                    }
+                }
+
+                let target = color.map(|c| c.round().clamp(0.0, 255.0) as u8);
+                let index = self.nearest(target);
+                out[i] = index;
+
+                if dither == Dither::FloydSteinberg {
+                    let chosen = self.colors[index as usize];
+                    for c in 0..3 {
+                        let err = color[c] - chosen[c] as f32;
+                        cur[x + 2][c] += err * 7.0 / 16.0;
+                        next[x][c] += err * 3.0 / 16.0;
+                        next[x + 1][c] += err * 5.0 / 16.0;
+                        next[x + 2][c] += err / 16.0;
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+const ORDERED_SPREAD: f32 = 48.0;
+
+// Weighted so green differences count most, roughly as the eye sees them
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    let d = |i: usize| (a[i] as i32 - b[i] as i32).pow(2) as u32;
+    2 * d(0) + 4 * d(1) + 3 * d(2)
+}
+
+// Split the color box with the widest channel at its weighted median until there are
+// `count` boxes, then use each box's weighted average
+fn median_cut(colors: Vec<([u8; 3], u32)>, count: usize) -> Vec<[u8; 3]> {
+    if colors.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![colors];
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = widest_channel(b);
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range);
+        let Some((i, channel, _)) = widest else { break };
+
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by_key(|(c, _)| c[channel]);
+        let half = colors.iter().map(|(_, n)| *n as u64).sum::<u64>() / 2;
+        let mut seen = 0u64;
+        let mut split = colors.len() - 1;
+        for (k, (_, n)) in colors.iter().enumerate() {
+            seen += *n as u64;
+            if seen >= half {
+                split = k + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, colors.len() - 1);
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes
+        .iter()
+        .map(|b| {
+            let total: u64 = b.iter().map(|(_, n)| *n as u64).sum::<u64>().max(1);
+            let mean = |i: usize| (b.iter().map(|(c, n)| c[i] as u64 * *n as u64).sum::<u64>() / total) as u8;
+            [mean(0), mean(1), mean(2)]
+        })
+        .collect()
+}
+
+fn widest_channel(colors: &[([u8; 3], u32)]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let min = colors.iter().map(|(c, _)| c[channel]).min().unwrap_or(0);
+            let max = colors.iter().map(|(c, _)| c[channel]).max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn frame(width: u32, height: u32, pixels: &[[u8; 4]]) -> Frame {
+        Frame { buffer: pixels.concat(), width, height, delay: Duration::from_millis(100) }
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn median_cut_keeps_few_colors_exactly() {
+        let mut colors = median_cut(vec![([255, 0, 0], 5), ([0, 255, 0], 1), ([0, 0, 255], 2)], 8);
+        colors.sort();
+        assert_eq!(colors, [[0, 0, 255], [0, 255, 0], [255, 0, 0]]);
+        assert!(median_cut(Vec::new(), 8).is_empty());
+    }
+
+    #[test]
+    fn median_cut_averages_each_box_by_weight() {
+        // Two clusters of equal weight on red; each box is its cluster's weighted mean
+        let mut colors = median_cut(
+            vec![([0, 0, 0], 3), ([4, 0, 0], 1), ([250, 0, 0], 2), ([254, 0, 0], 2)],
+            2,
+        );
+        colors.sort();
+        assert_eq!(colors, [[1, 0, 0], [252, 0, 0]]);
+    }
+
+    #[test]
+    fn widest_channel_picks_the_largest_range() {
+        assert_eq!(widest_channel(&[([0, 10, 5], 1), ([20, 200, 6], 1)]), (1, 190));
+    }
+
+    #[test]
+    fn transparency_takes_index_zero() {
+        let f = frame(2, 2, &[RED, GREEN, BLUE, CLEAR]);
+        let mut palette = Palette::new(&[&f], 256, false);
+        assert!(palette.transparent);
+        assert_eq!(palette.colors.len(), 4);
+        assert_eq!(palette.colors[0], [0, 0, 0]);
+
+        let indices = palette.index(&f, Dither::None);
+        assert_eq!(indices[3], 0);
+        for (i, px) in [RED, GREEN, BLUE].iter().enumerate() {
+            assert_eq!(palette.colors[indices[i] as usize], [px[0], px[1], px[2]]);
+        }
+    }
+
+    #[test]
+    fn reserve_keeps_index_zero_free_without_transparency() {
+        let f = frame(1, 1, &[RED]);
+        assert!(!Palette::new(&[&f], 256, false).transparent);
+
+        let mut palette = Palette::new(&[&f], 256, true);
+        assert_eq!(palette.colors, [[0, 0, 0], [255, 0, 0]]);
+        assert_eq!(palette.index(&f, Dither::FloydSteinberg), [1]);
+    }
+
+    #[test]
+    fn colors_outside_the_palette_map_to_the_nearest() {
+        let f = frame(2, 1, &[RED, BLUE]);
+        let mut palette = Palette::new(&[&f], 256, false);
+        let other = frame(1, 1, &[[200, 10, 20, 255]]);
+        let index = palette.index(&other, Dither::None)[0];
+        assert_eq!(palette.colors[index as usize], [255, 0, 0]);
+    }
+
+    #[test]
+    fn a_transparent_only_palette_maps_everything_to_zero() {
+        let mut palette = Palette::new(&[&frame(1, 1, &[CLEAR])], 256, false);
+        assert_eq!(palette.colors.len(), 1);
+        assert_eq!(palette.index(&frame(1, 1, &[RED]), Dither::None), [0]);
+    }
+
+    #[test]
+    fn dithering_mixes_colors_for_in_between_shades() {
+        // Mid gray between black and white: plain mapping is flat, both dithers mix
+        let f = frame(4, 4, &[[0, 0, 0, 255], [255, 255, 255, 255]].repeat(8));
+        let mut palette = Palette::new(&[&f], 2, false);
+        let gray = frame(4, 4, &[[128, 128, 128, 255]; 16]);
+
+        let flat = palette.index(&gray, Dither::None);
+        assert!(flat.iter().all(|&i| i == flat[0]));
+        for dither in [Dither::FloydSteinberg, Dither::Ordered] {
+            let mixed = palette.index(&gray, dither);
+            let ones = mixed.iter().filter(|&&i| i == 1).count();
+            assert!((4..=12).contains(&ones), "{dither}: {ones}");
+        }
+    }
+
+    #[test]
+    fn to_rgb_pads_to_a_power_of_two() {
+        let f = frame(3, 1, &[RED, GREEN, BLUE]);
+        let rgb = Palette::new(&[&f], 256, false).to_rgb();
+        assert_eq!(rgb.len(), 4 * 3);
+        assert_eq!(rgb[9..], [0, 0, 0]);
+    }
+
+    #[test]
+    fn dither_names_round_trip() {
+        for dither in Dither::ALL {
+            assert_eq!(dither.to_string().parse::<Dither>(), Ok(dither));
+        }
+        assert!("bayer".parse::<Dither>().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use super::spread_delays;
+use crate::types::Frame;
+use image::codecs::webp::WebPEncoder;
+use image::ColorType;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+// Animated WebP is a RIFF container of ANMF chunks, each wrapping an ordinary still image.
+// The image crate only encodes still lossless WebP, so each frame is encoded on its own and
+// its VP8L chunk is moved into the animation.
+
+const FLAG_ALPHA: u8 = 0x10;
+const FLAG_ANIMATION: u8 = 0x02;
+const NO_BLEND: u8 = 0x02; // Each frame replaces its area instead of drawing over it
+
+/// Encode full-canvas frames as a lossless animated WebP, with millisecond delays
+pub fn save_webp<P: AsRef<Path>>(path: P, frames: &[&Frame], loops: Option<u32>) -> Result<(), Box<dyn std::error::Error>> {
+    let first = frames.first().ok_or("No frames to export")?;
+
+    let mut vp8x = vec![FLAG_ALPHA | FLAG_ANIMATION, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(first.width - 1));
+    vp8x.extend_from_slice(&u24(first.height - 1));
+
+    let mut anim = vec![0, 0, 0, 0]; // Transparent background
+    anim.extend_from_slice(&(loops.unwrap_or(0).min(u16::MAX as u32) as u16).to_le_bytes());
+
+    let mut body = b"WEBP".to_vec();
+    write_chunk(&mut body, b"VP8X", &vp8x);
+    write_chunk(&mut body, b"ANIM", &anim);
+
+    let delays: Vec<Duration> = frames.iter().map(|f| f.delay).collect();
+    for (frame, delay) in frames.iter().zip(spread_delays(&delays, Duration::from_millis(1))) {
+        let mut still = Vec::new();
+        WebPEncoder::new_lossless(&mut still).encode(&frame.buffer, frame.width, frame.height, ColorType::Rgba8)?;
+        let image = find_chunk(&still, b"VP8L").ok_or("Encoder produced no lossless image data")?;
+
+        let mut anmf = Vec::with_capacity(image.len() + 24);
+        anmf.extend_from_slice(&u24(0)); // x / 2
+        anmf.extend_from_slice(&u24(0)); // y / 2
+        anmf.extend_from_slice(&u24(frame.width - 1));
+        anmf.extend_from_slice(&u24(frame.height - 1));
+        anmf.extend_from_slice(&u24(delay.min(0xFF_FFFF) as u32));
+        anmf.push(NO_BLEND);
+        write_chunk(&mut anmf, b"VP8L", image);
+        write_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut file = Vec::with_capacity(body.len() + 8);
+    write_chunk(&mut file, b"RIFF", &body);
+    fs::write(path, file)?;
+    Ok(())
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let b = value.to_le_bytes();
+    [b[0], b[1], b[2]]
+}
+
+// RIFF chunk: tag, little-endian size, data, padded to an even length
+fn write_chunk(out: &mut Vec<u8>, tag: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(tag);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+// Data of the first chunk called `tag` inside a RIFF WEBP file
+fn find_chunk<'a>(riff: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let mut pos = 12;
+    while pos + 8 <= riff.len() {
+        let size = u32::from_le_bytes(riff[pos + 4..pos + 8].try_into().ok()?) as usize;
+        let data = riff.get(pos + 8..pos + 8 + size)?;
+        if &riff[pos..pos + 4] == tag {
+            return Some(data);
+        }
+        pos += 8 + size + size % 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_are_padded_to_an_even_length() {
+        let mut out = Vec::new();
+        write_chunk(&mut out, b"ABCD", &[1, 2, 3]);
+        assert_eq!(out, [b'A', b'B', b'C', b'D', 3, 0, 0, 0, 1, 2, 3, 0]);
+        assert_eq!(u24(0x12_3456), [0x56, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn animation_has_header_loop_count_and_frames() {
+        let frames = [
+            Frame { buffer: [255, 0, 0, 255].repeat(6), width: 3, height: 2, delay: Duration::from_millis(40) },
+            Frame { buffer: [0, 0, 255, 128].repeat(6), width: 3, height: 2, delay: Duration::from_millis(60) },
+        ];
+        let path = std::env::temp_dir().join(format!("gif-engine-test-{}-anim.webp", std::process::id()));
+        let result = save_webp(&path, &[&frames[0], &frames[1]], Some(4));
+        let data = fs::read(&path);
+        let _ = fs::remove_file(&path);
+        result.unwrap();
+        let data = data.unwrap();
+
+        assert_eq!(&data[..4], b"RIFF");
+        assert_eq!(&data[8..12], b"WEBP");
+        assert_eq!(u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize, data.len() - 8);
+
+        let vp8x = find_chunk(&data, b"VP8X").unwrap();
+        assert_eq!(vp8x[0], FLAG_ALPHA | FLAG_ANIMATION);
+        assert_eq!(vp8x[4..10], [2, 0, 0, 1, 0, 0]);
+        assert_eq!(find_chunk(&data, b"ANIM").unwrap()[4..6], [4, 0]);
+
+        // Walk the frames: canvas-sized, with their delays and an image inside
+        let mut pos = 12;
+        let mut delays = Vec::new();
+        while pos + 8 <= data.len() {
+            let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+            if &data[pos..pos + 4] == b"ANMF" {
+                let anmf = &data[pos + 8..pos + 8 + size];
+                assert_eq!(anmf[6..12], [2, 0, 0, 1, 0, 0]);
+                assert_eq!(anmf[15], NO_BLEND);
+                assert_eq!(&anmf[16..20], b"VP8L");
+                delays.push(u32::from_le_bytes([anmf[12], anmf[13], anmf[14], 0]));
+            }
+            pos += 8 + size + size % 2;
+        }
+        assert_eq!(delays, [40, 60]);
+    }
+}
@@ -2,9 +2,26 @@ use eframe::egui;
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc;
 use std::thread;
-use crate::app::store::{Store, GifConfig};
+use crate::app::store::{Store, GifConfig, Scene, SceneMode, OPACITY_RANGE, SCALE_RANGE};
 use crate::app::process::ProcessStore;
+use crate::app::doctor::{self, Issue};
+use crate::app::launch;
 use crate::types::{Frame, AnimationInfo};
+use crate::processing::{self, ProcessOptions};
+use crate::decoder::palette::{self, PaletteSwap};
+use crate::processing::background::{BackgroundRemoval, EdgeFill};
+use crate::processing::chroma::{self, ChromaKey};
+use crate::processing::defringe::Defringe;
+use crate::processing::effects::{Outline, Shadow};
+use crate::processing::filters::ColorFilter;
+use crate::processing::interpolate::{Interpolation, InterpolationMethod};
+use crate::processing::trim::CropRect;
+use crate::cache::frame_buffer::FrameBuffer;
+use crate::encoder::ExportFormat;
+use crate::encoder::gif::GifOptions;
+use crate::encoder::quantize::Dither;
+use crate::playback::scheduler::Scheduler;
+use crate::playback::sequence::{FrameRange, FrameSequence, PlaybackMode};
 use std::process::Command;
 use tray_icon::{TrayIconBuilder, menu::{Menu, MenuItem, MenuEvent}};
 use tray_icon::Icon;
@@ -158,13 +175,142 @@ pub fn run_gui() -> Result<(), eframe::Error> {
     )
 }
 
+// Export the processed animation on a background thread; the message describes the outcome
+fn spawn_export(config: GifConfig, path: std::path::PathBuf, gif_options: GifOptions) -> mpsc::Receiver<Result<String, String>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = (|| -> Result<String, Box<dyn std::error::Error>> {
+            let format = ExportFormat::from_path(&path).ok_or("Unsupported file extension (gif, png, apng, webp)")?;
+            let (info, frames, _) = processing::load(&config.path, &config.process_options())?;
+            let mut buffer = FrameBuffer::new(frames);
+            crate::playback::player::apply_timing(&mut buffer, &config);
+
+            let order = buffer.cycle();
+            let frames: Vec<_> = order.iter().map(|&index| buffer.get(index)).collect();
+            let bytes = crate::encoder::export(&path, format, &frames, info.loops, &gif_options)?;
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            Ok(format!("Exported {} ({:.1} KB)", file_name, bytes as f64 / 1024.0))
+        })();
+        let _ = tx.send(res.map_err(|e| e.to_string()));
+    });
+    rx
+}
+
+// Run the library health check on a background thread; it decodes and hashes every file
+fn spawn_doctor_check(store: Store, processes: ProcessStore) -> mpsc::Receiver<Vec<Issue>> {
+    let monitors = crate::platform::monitor_count();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(doctor::check(&store, &processes, monitors));
+    });
+    rx
+}
+
+// Decoded preview frames, along with the palette swaps they were decoded with
+type LoadResult = Result<(AnimationInfo, Vec<Frame>, Vec<PaletteSwap>), String>;
+
+// Decode an animation for the preview on a background thread
+fn spawn_load(path: std::path::PathBuf, swaps: Vec<PaletteSwap>) -> mpsc::Receiver<LoadResult> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let res = crate::decoder::load_animation_with_palette(&path, &swaps);
+        let _ = tx.send(res.map(|(info, frames)| (info, frames, swaps)).map_err(|e| e.to_string()));
+    });
+    rx
+}
+
 struct PreviewState {
+    source_frames: Vec<Frame>, // Frames as decoded, kept so settings can be re-applied live
+    palette: Vec<PaletteSwap>, // Palette swaps applied while decoding source_frames
     frames: Vec<Frame>,
+    applied: ProcessOptions,
     info: AnimationInfo,
     current_frame: usize,
-    last_update: std::time::Instant,
+    sequence: FrameSequence,
+    playback: (PlaybackMode, Option<FrameRange>), // Settings the sequence was configured with
+    scheduler: Scheduler,
     texture: Option<egui::TextureHandle>,
     override_delay: Option<std::time::Duration>,
+    speed: f32,
+}
+
+impl PreviewState {
+    fn new(info: AnimationInfo, frames: Vec<Frame>, palette: Vec<PaletteSwap>) -> Self {
+        Self {
+            sequence: FrameSequence::new(frames.len()),
+            playback: (PlaybackMode::Forward, None),
+            source_frames: frames.clone(),
+            palette,
+            frames,
+            applied: ProcessOptions::default(),
+            info,
+            current_frame: 0,
+            scheduler: Scheduler::new(),
+            texture: None,
+            override_delay: None,
+            speed: 1.0,
+        }
+    }
+
+    // Swap in re-decoded frames (after a palette change) without restarting the animation
+    fn replace_source(&mut self, frames: Vec<Frame>, palette: Vec<PaletteSwap>) {
+        self.frames = frames.clone();
+        self.source_frames = frames;
+        self.palette = palette;
+        self.applied = ProcessOptions::default();
+        self.current_frame = self.current_frame.min(self.frames.len().saturating_sub(1));
+    }
+
+    // Follow the animation's playback mode and frame range
+    fn set_playback(&mut self, mode: PlaybackMode, range: Option<FrameRange>) {
+        if self.playback == (mode, range) {
+            return;
+        }
+        self.sequence.set_range(range);
+        self.sequence.set_mode(mode);
+        self.playback = (mode, range);
+        self.current_frame = self.sequence.current();
+        self.scheduler.reset();
+    }
+
+    // Re-run the processing pipeline on the source frames if the settings changed
+    fn apply_options(&mut self, ctx: &egui::Context, options: ProcessOptions, source: &std::path::Path) {
+        if self.applied == options {
+            return;
+        }
+
+        let mut info = self.info.clone();
+        let mut frames = self.source_frames.clone();
+        let result = processing::process(&mut info, &mut frames, &options, Some(source));
+        self.applied = options;
+        if let Err(e) = result {
+            eprintln!("Preview error: {}", e);
+            return;
+        }
+        self.frames = frames;
+        self.current_frame = self.current_frame.min(self.frames.len().saturating_sub(1));
+        self.upload_texture(ctx);
+    }
+
+    fn upload_texture(&mut self, ctx: &egui::Context) {
+        let Some(frame) = self.frames.get(self.current_frame) else {
+            return;
+        };
+        let image = egui::ColorImage::from_rgba_unmultiplied(
+            [frame.width as usize, frame.height as usize],
+            &frame.buffer,
+        );
+
+        if let Some(texture) = &mut self.texture {
+            texture.set(image, egui::TextureOptions::LINEAR);
+        } else {
+            self.texture = Some(ctx.load_texture(
+                "preview_tex",
+                image,
+                egui::TextureOptions::LINEAR
+            ));
+        }
+    }
 }
 
 #[derive(PartialEq)]
@@ -181,13 +327,22 @@ struct AnimeApp {
     // Selection & Preview
     selected_name: Option<String>,
     preview: Option<PreviewState>,
-    load_rx: Option<mpsc::Receiver<Result<(AnimationInfo, Vec<Frame>), String>>>,
+    load_rx: Option<mpsc::Receiver<LoadResult>>,
     is_loading: bool,
     load_error: Option<String>, // Store error message for UI display
     
     // UI State
     refresh_timer: std::time::Instant,
+    store_modified: Option<std::time::SystemTime>, // store.json write time when last loaded
     input_path: String,
+    scene_name_input: String,
+    export_dither: Dither, // GIF dithering used by the Export button
+    export_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    update_rx: Option<mpsc::Receiver<Result<String, String>>>, // In-place update of a running animation
+    status: Option<String>, // Result of the last export or update
+    doctor_issues: Option<Vec<Issue>>, // Last scan, None until the first one
+    doctor_rx: Option<mpsc::Receiver<Vec<Issue>>>, // Scan in progress
+    doctor_log: Vec<String>, // What the last repairs did
     
     // Tray
     _tray_icon: Option<tray_icon::TrayIcon>,
@@ -237,7 +392,16 @@ impl AnimeApp {
             is_loading: false,
             load_error: None,
             refresh_timer: std::time::Instant::now(),
+            store_modified: Store::modified(),
             input_path: String::new(),
+            scene_name_input: String::new(),
+            export_dither: Dither::default(),
+            export_rx: None,
+            update_rx: None,
+            status: None,
+            doctor_issues: None,
+            doctor_rx: None,
+            doctor_log: Vec::new(),
             _tray_icon: tray_icon,
             _tray_menu: tray_menu,
             quit_item,
@@ -479,11 +643,22 @@ impl eframe::App for AnimeApp {
         }
         ctx.set_visuals(visuals);
 
-        // Auto-refresh process list every second
+        // Auto-refresh process list every second, picking up changes made from the command line
         if self.refresh_timer.elapsed().as_secs() >= 1 {
             if let Ok(mut ps) = self.process_store.lock() {
+                *ps = ProcessStore::load();
                 ps.cleanup_dead_processes();
             }
+            let modified = Store::modified();
+            if modified != self.store_modified {
+                self.store_modified = modified;
+                let mut store = Self::lock_store(&self.store);
+                *store = Store::load();
+                if self.selected_name.as_ref().is_some_and(|name| !store.gifs.contains_key(name)) {
+                    self.selected_name = None;
+                    self.preview = None;
+                }
+            }
             self.refresh_timer = std::time::Instant::now();
         }
 
@@ -493,16 +668,14 @@ impl eframe::App for AnimeApp {
                 self.is_loading = false;
                 self.load_rx = None;
                 match result {
-                    Ok((info, frames)) => {
+                    Ok((info, frames, palette)) => {
                         self.load_error = None; // Clear any previous errors
-                        self.preview = Some(PreviewState {
-                            frames,
-                            info,
-                            current_frame: 0,
-                            last_update: std::time::Instant::now(),
-                            texture: None,
-                            override_delay: None,
-                        });
+                        if let Some(preview) = &mut self.preview {
+                            // Re-decode of the current selection
+                            preview.replace_source(frames, palette);
+                        } else {
+                            self.preview = Some(PreviewState::new(info, frames, palette));
+                        }
                     }
                     Err(e) => {
                         // Store error for UI display instead of just printing
@@ -513,44 +686,37 @@ impl eframe::App for AnimeApp {
             }
         }
 
+        if let Some(result) = self.update_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.update_rx = None;
+            self.status = Some(result.unwrap_or_else(|e| format!("Update failed: {}", e)));
+        }
+        if let Some(issues) = self.doctor_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.doctor_rx = None;
+            self.doctor_issues = Some(issues);
+        }
+        if let Some(result) = self.export_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.export_rx = None;
+            self.status = Some(result.unwrap_or_else(|e| format!("Export failed: {}", e)));
+        }
+
         // Update Preview Animation
         if let Some(preview) = &mut self.preview {
             if !preview.frames.is_empty() {
-                let delay = preview.override_delay.unwrap_or(preview.frames[preview.current_frame].delay);
+                let now = std::time::Instant::now();
+                let PreviewState { frames, sequence, scheduler, override_delay, speed, .. } = preview;
+                let advanced = scheduler.tick(now, || {
+                    let index = sequence.advance();
+                    override_delay.unwrap_or_else(|| frames[index].delay.div_f32(*speed))
+                });
 
-                if preview.last_update.elapsed() >= delay {
-                    preview.current_frame = (preview.current_frame + 1) % preview.frames.len();
-                    
-                    // Accumulate time to prevent drift
-                    preview.last_update += delay;
-                    
-                    // If we've drifted too far (e.g. system sleep or heavy lag), reset
-                    if preview.last_update.elapsed() > delay {
-                        preview.last_update = std::time::Instant::now();
-                    }
-                    
-                    // Update texture
-                    let frame = &preview.frames[preview.current_frame];
-                    let image = egui::ColorImage::from_rgba_unmultiplied(
-                        [frame.width as usize, frame.height as usize],
-                        &frame.buffer,
-                    );
-                    
-                    if let Some(texture) = &mut preview.texture {
-                        texture.set(image, egui::TextureOptions::LINEAR);
-                    } else {
-                        preview.texture = Some(ctx.load_texture(
-                            "preview_tex",
-                            image,
-                            egui::TextureOptions::LINEAR
-                        ));
-                    }
-                    ctx.request_repaint(); // Ensure continuous animation
-                } else {
-                     // Request repaint for smooth timing even if we didn't update frame yet
-                     // but use delay to avoid busy loop
-                     let remaining = delay.saturating_sub(preview.last_update.elapsed());
-                     ctx.request_repaint_after(remaining);
+                if advanced {
+                    preview.current_frame = preview.sequence.current();
+                    preview.upload_texture(ctx);
+                }
+
+                // Wake up again when the next frame is due instead of repainting continuously
+                if let Some(deadline) = preview.scheduler.next_deadline() {
+                    ctx.request_repaint_after(deadline.saturating_duration_since(now));
                 }
             }
         }
@@ -562,6 +728,8 @@ impl eframe::App for AnimeApp {
             .show(ctx, |ui| {
                 ui.vertical(|ui| {
                     self.show_library(ui);
+                    ui.separator();
+                    self.show_scenes(ui);
                 });
                 
                 // Bottom Settings Button
@@ -601,6 +769,81 @@ impl eframe::App for AnimeApp {
 }
 
 impl AnimeApp {
+    // Library health check with one-click repairs, same checks as `gif-engine doctor`
+    fn show_doctor(&mut self, ui: &mut egui::Ui) {
+        let scanning = self.doctor_rx.is_some();
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Doctor").strong());
+            let label = if scanning { "Scanning..." } else { "🩺 Scan Library" };
+            if ui.add_enabled(!scanning, egui::Button::new(label)).clicked() {
+                self.start_doctor_scan();
+                self.doctor_log.clear();
+            }
+        });
+        if scanning {
+            // The scan doesn't wake the UI when it finishes
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
+        let Some(issues) = &self.doctor_issues else {
+            ui.label(egui::RichText::new("Finds missing, broken, orphaned and duplicate files, stale running entries and invalid settings.").small().weak());
+            return;
+        };
+        if issues.is_empty() {
+            ui.label("✔ No problems found");
+        }
+
+        let mut fix: Option<usize> = None;
+        let mut fix_all = false;
+        // Repairs wait for a running scan, whose results may no longer match these
+        for (i, issue) in issues.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!scanning, egui::Button::new(issue.repair_label()).small()).clicked() {
+                    fix = Some(i);
+                }
+                ui.label(issue.to_string());
+            });
+        }
+        if issues.len() > 1 && ui.add_enabled(!scanning, egui::Button::new(format!("Fix All ({})", issues.len()))).clicked() {
+            fix_all = true;
+        }
+        for line in &self.doctor_log {
+            ui.label(egui::RichText::new(line).small().weak());
+        }
+
+        let to_fix: Vec<Issue> = match fix {
+            Some(i) => vec![issues[i].clone()],
+            None if fix_all => issues.clone(),
+            None => return,
+        };
+
+        let mut store = Self::lock_store(&self.store);
+        let mut processes = Self::lock_process_store(&self.process_store);
+        self.doctor_log.clear();
+        for issue in &to_fix {
+            match doctor::repair(issue, &mut store, &mut processes) {
+                Ok(done) => self.doctor_log.push(done),
+                Err(e) => self.doctor_log.push(format!("{}: {}", issue, e)),
+            }
+        }
+        let _ = store.save();
+        let _ = processes.save();
+        if self.selected_name.as_ref().is_some_and(|name| !store.gifs.contains_key(name)) {
+            self.selected_name = None;
+            self.preview = None;
+        }
+        drop((store, processes));
+
+        // Rescan so the list shows what's left
+        self.start_doctor_scan();
+    }
+
+    fn start_doctor_scan(&mut self) {
+        let store = Self::lock_store(&self.store).clone();
+        let processes = Self::lock_process_store(&self.process_store).clone();
+        self.doctor_rx = Some(spawn_doctor_check(store, processes));
+    }
+
     fn show_settings_panel(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
             ui.heading("Settings");
@@ -665,6 +908,9 @@ impl AnimeApp {
                     ps.cleanup_dead_processes();
                 }
             }
+
+            ui.add_space(10.0);
+            self.show_doctor(ui);
             
             ui.add_space(10.0);
             ui.label(egui::RichText::new("Danger Zone").color(egui::Color32::RED));
@@ -693,12 +939,29 @@ impl AnimeApp {
         let mut store = Self::lock_store(&self.store);
         let mut to_launch = None;
         let mut to_delete = None;
+        let mut to_export = None;
+        let mut to_update = None;
+        let mut to_stop = None;
         let mut should_save = false;
 
         if let Some(config) = store.gifs.get_mut(&name) {
             // Update preview override
             if let Some(preview) = &mut self.preview {
                 preview.override_delay = config.fps.map(|f| std::time::Duration::from_secs_f64(1.0 / f as f64));
+                preview.speed = config.speed.clamp(0.1, 10.0);
+                preview.set_playback(config.mode, config.frame_range);
+
+                if preview.palette != config.palette {
+                    // Palette swaps happen in the decoder, so the preview has to be decoded again
+                    if !self.is_loading {
+                        self.load_rx = Some(spawn_load(config.path.clone(), config.palette.clone()));
+                        self.is_loading = true;
+                    }
+                } else {
+                    // The preview is shown at original size, so everything except scaling is applied
+                    let options = ProcessOptions { scale: None, ..config.process_options() };
+                    preview.apply_options(ui.ctx(), options, &config.path);
+                }
             }
             
             ui.heading(&name);
@@ -707,7 +970,7 @@ impl AnimeApp {
 
             // Preview Area
             ui.vertical_centered(|ui| {
-                if self.is_loading {
+                if self.is_loading && self.preview.is_none() {
                     ui.spinner();
                     ui.label("Loading preview...");
                 } else if let Some(error) = &self.load_error {
@@ -718,28 +981,26 @@ impl AnimeApp {
                     if ui.button("🔄 Retry").clicked() {
                         // Retry loading
                         self.load_error = None;
-                        if let Some(config) = Self::lock_store(&self.store).gifs.get(&name) {
-                            let path = config.path.clone();
-                            let (tx, rx) = mpsc::channel();
-                            self.load_rx = Some(rx);
-                            self.is_loading = true;
-                            self.preview = None;
-                            
-                            thread::spawn(move || {
-                                let res = crate::decoder::load_animation(path);
-                                let _ = tx.send(res.map_err(|e| e.to_string()));
-                            });
-                        }
+                        self.load_rx = Some(spawn_load(config.path.clone(), config.palette.clone()));
+                        self.is_loading = true;
+                        self.preview = None;
                     }
                 } else if let Some(preview) = &self.preview {
                     if let Some(texture) = &preview.texture {
-                         ui.image((texture.id(), texture.size_vec2()));
+                         let tint = egui::Color32::from_white_alpha((config.opacity.clamp(0.0, 1.0) * 255.0) as u8);
+                         ui.add(egui::Image::new((texture.id(), texture.size_vec2())).tint(tint));
                     }
                     ui.label(format!("Original: {}x{} @ {:.2} FPS", 
                         preview.info.width, 
                         preview.info.height,
                         preview.info.frame_count as f64 / preview.info.duration.as_secs_f64()
                     ));
+                    let processed_size = preview.frames.first()
+                        .map(|f| (f.width, f.height))
+                        .filter(|&size| size != (preview.info.width as u32, preview.info.height as u32));
+                    if let Some((w, h)) = processed_size {
+                        ui.label(egui::RichText::new(format!("Processed: {}x{} (before scaling)", w, h)).weak());
+                    }
                 } else {
                     ui.label("Preview unavailable");
                 }
@@ -750,29 +1011,19 @@ impl AnimeApp {
             ui.horizontal(|ui| {
                 let is_running = {
                     let ps = Self::lock_process_store(&self.process_store);
-                    ps.processes.values().any(|info| info.name == name)
+                    ps.processes.values().any(|info| info.hosts(&name))
                 };
 
                 if is_running {
-                    if ui.button("🔄 Restart").clicked() {
-                        // Kill existing
-                         let pid_opt = {
-                            let ps = Self::lock_process_store(&self.process_store);
-                            ps.processes.iter().find(|(_, info)| info.name == name).map(|(pid, _)| *pid)
-                        };
-                        if let Some(pid) = pid_opt {
-                            if let Ok(mut ps) = self.process_store.lock() { ps.kill_process(pid); }
-                        }
-                        to_launch = Some(name.clone());
+                    let updating = self.update_rx.is_some();
+                    let update = ui
+                        .add_enabled(!updating, egui::Button::new(if updating { "Updating..." } else { "🔄 Update" }))
+                        .on_hover_text("Apply the current settings to the running animation without restarting it");
+                    if update.clicked() {
+                        to_update = Some(name.clone());
                     }
                     if ui.button("⏹ Stop").clicked() {
-                        let pid_opt = {
-                            let ps = Self::lock_process_store(&self.process_store);
-                            ps.processes.iter().find(|(_, info)| info.name == name).map(|(pid, _)| *pid)
-                        };
-                        if let Some(pid) = pid_opt {
-                            if let Ok(mut ps) = self.process_store.lock() { ps.kill_process(pid); }
-                        }
+                        to_stop = Some(name.clone());
                     }
                 } else {
                     if ui.button("▶ Play").clicked() {
@@ -784,8 +1035,28 @@ impl AnimeApp {
                     if ui.button("🗑 Delete").clicked() {
                         to_delete = Some(name.clone());
                     }
+
+                    let exporting = self.export_rx.is_some();
+                    let export = ui
+                        .add_enabled(!exporting, egui::Button::new(if exporting { "Exporting..." } else { "💾 Export…" }))
+                        .on_hover_text("Save the animation with its current settings as GIF, animated PNG or animated WebP");
+                    if export.clicked() {
+                        to_export = Some(name.clone());
+                    }
+                    egui::ComboBox::from_id_source("export_dither")
+                        .selected_text(format!("Dither: {}", self.export_dither))
+                        .show_ui(ui, |ui| {
+                            for dither in Dither::ALL {
+                                ui.selectable_value(&mut self.export_dither, dither, dither.as_str());
+                            }
+                        })
+                        .response
+                        .on_hover_text("Dithering for GIF exports. APNG and WebP are lossless.");
                 });
             });
+            if let Some(status) = &self.status {
+                ui.label(egui::RichText::new(status).small().weak());
+            }
             ui.separator();
 
             // Settings
@@ -801,23 +1072,99 @@ impl AnimeApp {
                     }
                     ui.end_row();
 
-                    // FPS
+                    // Speed multiplier or constant FPS
                     let original_fps = if let Some(p) = &self.preview {
                          (p.info.frame_count as f64 / p.info.duration.as_secs_f64()) as u32
                     } else { 60 };
                     let max_fps = if original_fps == 0 { 60 } else { original_fps * 2 };
                     
                     let mut fps_val = config.fps.unwrap_or(original_fps);
-                    
-                    ui.label("Target FPS:");
+                    let mut constant_fps = config.fps.is_some();
+
+                    ui.label("Speed:");
                     ui.horizontal(|ui| {
-                        if ui.add(egui::DragValue::new(&mut fps_val).speed(1).clamp_range(0..=max_fps).suffix(" fps")).changed() {
-                            config.fps = if fps_val > 0 { Some(fps_val) } else { None };
+                        // Speed keeps each frame's own delay; constant FPS flattens them all
+                        if ui.radio_value(&mut constant_fps, false, "Multiplier").changed() {
+                            config.fps = None;
                             should_save = true;
                         }
-                        if ui.add(egui::Slider::new(&mut fps_val, 0..=max_fps).show_value(false)).changed() {
-                             config.fps = if fps_val > 0 { Some(fps_val) } else { None };
-                             should_save = true;
+                        if ui.radio_value(&mut constant_fps, true, "Constant FPS").changed() {
+                            config.fps = Some(original_fps.max(1));
+                            should_save = true;
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("");
+                    ui.horizontal(|ui| {
+                        if constant_fps {
+                            if ui.add(egui::DragValue::new(&mut fps_val).speed(1).clamp_range(1..=max_fps).suffix(" fps")).changed() {
+                                config.fps = Some(fps_val);
+                                should_save = true;
+                            }
+                            if ui.add(egui::Slider::new(&mut fps_val, 1..=max_fps).show_value(false)).changed() {
+                                config.fps = Some(fps_val);
+                                should_save = true;
+                            }
+                        } else {
+                            should_save |= ui.add(egui::DragValue::new(&mut config.speed).speed(0.01).clamp_range(0.1..=10.0).suffix("x")).changed();
+                            should_save |= ui.add(egui::Slider::new(&mut config.speed, 0.1..=10.0).logarithmic(true).show_value(false)).changed();
+                            if ui.small_button("1x").clicked() {
+                                config.speed = 1.0;
+                                should_save = true;
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    // Playback mode and frame range
+                    ui.label("Playback:");
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("playback_mode")
+                            .selected_text(config.mode.as_str())
+                            .show_ui(ui, |ui| {
+                                for mode in PlaybackMode::ALL {
+                                    should_save |= ui.selectable_value(&mut config.mode, mode, mode.as_str()).changed();
+                                }
+                            });
+
+                        let frame_count = self.preview.as_ref().map(|p| p.info.frame_count).unwrap_or(1).max(1);
+                        let mut use_range = config.frame_range.is_some();
+                        if ui.checkbox(&mut use_range, "Frames").changed() {
+                            config.frame_range = use_range.then_some(FrameRange { start: 0, end: frame_count });
+                            should_save = true;
+                        }
+                        if let Some(range) = &mut config.frame_range {
+                            should_save |= ui.add(egui::DragValue::new(&mut range.start).clamp_range(0..=frame_count - 1)).changed();
+                            ui.label("to");
+                            should_save |= ui.add(egui::DragValue::new(&mut range.end).clamp_range(1..=frame_count)).changed();
+                            range.end = range.end.max(range.start + 1);
+                        }
+                    });
+                    ui.end_row();
+
+                    // Interpolation
+                    ui.label("Interpolation:");
+                    ui.horizontal(|ui| {
+                        // Random and shuffle have no neighbouring frames to blend between
+                        ui.set_enabled(config.mode.can_interpolate());
+                        let mut enabled = config.interpolation.is_some();
+                        let checkbox = ui.checkbox(&mut enabled, "Smooth to")
+                            .on_hover_text("Generates in-between frames when playing; uses more memory")
+                            .on_disabled_hover_text("Only in forward, reverse and ping-pong modes");
+                        if checkbox.changed() {
+                            config.interpolation = enabled.then(Interpolation::default);
+                            should_save = true;
+                        }
+                        if let Some(interpolation) = &mut config.interpolation {
+                            should_save |= ui.add(egui::DragValue::new(&mut interpolation.target_fps).clamp_range(1..=240).suffix(" fps")).changed();
+                            egui::ComboBox::from_id_source("interpolation_method")
+                                .selected_text(interpolation.method.as_str())
+                                .show_ui(ui, |ui| {
+                                    for method in InterpolationMethod::ALL {
+                                        should_save |= ui.selectable_value(&mut interpolation.method, method, method.as_str()).changed();
+                                    }
+                                });
                         }
                     });
                     ui.end_row();
@@ -826,17 +1173,299 @@ impl AnimeApp {
                     let mut scale_val = config.scale.unwrap_or(1.0);
                     ui.label("Scale:");
                     ui.horizontal(|ui| {
-                        if ui.add(egui::DragValue::new(&mut scale_val).speed(0.01).clamp_range(0.1..=2.0)).changed() {
+                        if ui.add(egui::DragValue::new(&mut scale_val).speed(0.01).clamp_range(SCALE_RANGE)).changed() {
                             config.scale = if (scale_val - 1.0).abs() > f32::EPSILON { Some(scale_val) } else { None };
                             should_save = true;
                         }
-                        if ui.add(egui::Slider::new(&mut scale_val, 0.1..=2.0).show_value(false)).changed() {
+                        if ui.add(egui::Slider::new(&mut scale_val, SCALE_RANGE).show_value(false)).changed() {
                              config.scale = if (scale_val - 1.0).abs() > f32::EPSILON { Some(scale_val) } else { None };
                              should_save = true;
                         }
                     });
                     ui.end_row();
 
+                    // Opacity
+                    ui.label("Opacity:");
+                    ui.horizontal(|ui| {
+                        if ui.add(egui::DragValue::new(&mut config.opacity).speed(0.01).clamp_range(OPACITY_RANGE)).changed() {
+                            should_save = true;
+                        }
+                        if ui.add(egui::Slider::new(&mut config.opacity, OPACITY_RANGE).show_value(false)).changed() {
+                            should_save = true;
+                        }
+                    });
+                    ui.end_row();
+
+                    // Trim & crop
+                    let (canvas_w, canvas_h) = self.preview.as_ref()
+                        .map(|p| (p.info.width as u32, p.info.height as u32))
+                        .unwrap_or((u16::MAX as u32, u16::MAX as u32));
+                    ui.label("Trim:");
+                    ui.vertical(|ui| {
+                        if ui.checkbox(&mut config.trim, "Auto-trim transparent borders").on_hover_text("Shrink the window to the area any frame actually draws into").changed() {
+                            should_save = true;
+                        }
+
+                        let mut use_crop = config.crop.is_some();
+                        if ui.checkbox(&mut use_crop, "Crop rectangle").changed() {
+                            config.crop = use_crop.then_some(CropRect { x: 0, y: 0, width: canvas_w, height: canvas_h });
+                            should_save = true;
+                        }
+                        if let Some(rect) = &mut config.crop {
+                            ui.horizontal(|ui| {
+                                ui.label("X:");
+                                should_save |= ui.add(egui::DragValue::new(&mut rect.x).clamp_range(0..=canvas_w - 1)).changed();
+                                ui.label("Y:");
+                                should_save |= ui.add(egui::DragValue::new(&mut rect.y).clamp_range(0..=canvas_h - 1)).changed();
+                                ui.label("W:");
+                                should_save |= ui.add(egui::DragValue::new(&mut rect.width).clamp_range(1..=canvas_w)).changed();
+                                ui.label("H:");
+                                should_save |= ui.add(egui::DragValue::new(&mut rect.height).clamp_range(1..=canvas_h)).changed();
+                            });
+                        }
+                    });
+                    ui.end_row();
+
+                    // Palette swap (GIFs with a global color table)
+                    let source_palette = self.preview.as_ref().map(|p| p.info.palette.clone()).unwrap_or_default();
+                    if !source_palette.is_empty() {
+                        ui.label("Palette:");
+                        ui.vertical(|ui| {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+                                for (i, from) in source_palette.iter().enumerate() {
+                                    let current = config.palette.iter().find(|s| s.from == *from).map(|s| s.to);
+                                    let mut color = current.unwrap_or(*from);
+                                    let changed = ui.push_id(i, |ui| {
+                                        egui::color_picker::color_edit_button_srgb(ui, &mut color)
+                                            .on_hover_text(format!("{} → {}", processing::format_hex_color(*from), processing::format_hex_color(color)))
+                                            .changed()
+                                    }).inner;
+
+                                    if changed {
+                                        config.palette.retain(|s| s.from != *from);
+                                        if color != *from {
+                                            config.palette.push(PaletteSwap { from: *from, to: color });
+                                        }
+                                        should_save = true;
+                                    }
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                let picked = ui.button("📂 Load palette file...")
+                                    .on_hover_text("Map colors by index from a .gpl, .pal or .hex palette")
+                                    .clicked()
+                                    .then(|| rfd::FileDialog::new().add_filter("Palette", &["gpl", "pal", "hex"]).pick_file())
+                                    .flatten();
+                                if let Some(path) = picked {
+                                    match palette::read_palette_file(&path) {
+                                        Ok(colors) => {
+                                            config.palette = palette::swaps_by_index(&source_palette, &colors);
+                                            should_save = true;
+                                        }
+                                        Err(e) => eprintln!("Failed to load palette {:?}: {}", path, e),
+                                    }
+                                }
+                                if ui.add_enabled(!config.palette.is_empty(), egui::Button::new("Reset")).clicked() {
+                                    config.palette.clear();
+                                    should_save = true;
+                                }
+                            });
+                        });
+                        ui.end_row();
+                    }
+
+                    // Background removal
+                    ui.label("Background:");
+                    ui.vertical(|ui| {
+                        let mode = match &config.background {
+                            None => "Keep",
+                            Some(BackgroundRemoval::ChromaKey(_)) => "Chroma key",
+                            Some(BackgroundRemoval::EdgeFill(_)) => "Edge-connected",
+                        };
+                        egui::ComboBox::from_id_source("background_combo")
+                            .selected_text(mode)
+                            .show_ui(ui, |ui| {
+                                if ui.selectable_label(mode == "Keep", "Keep").clicked() {
+                                    config.background = None;
+                                    should_save = true;
+                                }
+                                if ui.selectable_label(mode == "Chroma key", "Chroma key").on_hover_text("Remove every pixel close to the key color").clicked() {
+                                    config.background = Some(BackgroundRemoval::ChromaKey(ChromaKey::default()));
+                                    should_save = true;
+                                }
+                                if ui.selectable_label(mode == "Edge-connected", "Edge-connected").on_hover_text("Only remove background reachable from the canvas border").clicked() {
+                                    config.background = Some(BackgroundRemoval::EdgeFill(EdgeFill::default()));
+                                    should_save = true;
+                                }
+                            });
+
+                        if let Some(removal) = &mut config.background {
+                            ui.horizontal(|ui| {
+                                let mut auto = removal.color().is_none();
+                                if ui.checkbox(&mut auto, "Auto-detect color").changed() {
+                                    *removal.color_mut() = if auto {
+                                        None
+                                    } else {
+                                        // Start the picker from the detected color
+                                        Some(self.preview.as_ref()
+                                            .and_then(|p| p.source_frames.first())
+                                            .and_then(chroma::detect_key_color)
+                                            .unwrap_or([0, 255, 0]))
+                                    };
+                                    should_save = true;
+                                }
+                                if let Some(color) = removal.color_mut() {
+                                    should_save |= egui::color_picker::color_edit_button_srgb(ui, color).changed();
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("Tolerance:");
+                                should_save |= ui.add(egui::Slider::new(removal.tolerance_mut(), 0.0..=1.0)).changed();
+                            });
+                            if let BackgroundRemoval::ChromaKey(key) = removal {
+                                ui.horizontal(|ui| {
+                                    ui.label("Softness:");
+                                    should_save |= ui.add(egui::Slider::new(&mut key.softness, 0.0..=0.5)).changed();
+                                });
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    // Defringe
+                    ui.label("Defringe:");
+                    ui.vertical(|ui| {
+                        let mut enabled = config.defringe.is_some();
+                        if ui.checkbox(&mut enabled, "Remove matte halo").on_hover_text("Clean up light or dark fringes around art exported against a solid color").changed() {
+                            config.defringe = enabled.then(Defringe::default);
+                            should_save = true;
+                        }
+
+                        if let Some(defringe) = &mut config.defringe {
+                            ui.horizontal(|ui| {
+                                ui.label("Matte:");
+                                should_save |= egui::color_picker::color_edit_button_srgb(ui, &mut defringe.matte).changed();
+                                ui.add_space(10.0);
+                                ui.label("Erode:");
+                                should_save |= ui.add(egui::DragValue::new(&mut defringe.erode).clamp_range(0..=8).suffix(" px")).changed();
+                                ui.add_space(10.0);
+                                ui.label("Feather:");
+                                should_save |= ui.add(egui::DragValue::new(&mut defringe.feather).clamp_range(0..=8).suffix(" px")).changed();
+                            });
+                        }
+                    });
+                    ui.end_row();
+
+                    // Color filters, applied top to bottom
+                    ui.label("Color Filters:");
+                    ui.vertical(|ui| {
+                        let mut move_up = None;
+                        let mut remove = None;
+                        let count = config.filters.len();
+
+                        for (i, filter) in config.filters.iter_mut().enumerate() {
+                            ui.push_id(i, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label(filter.name());
+                                    should_save |= match filter {
+                                        ColorFilter::Tint { color, amount } => {
+                                            egui::color_picker::color_edit_button_srgb(ui, color).changed()
+                                                | ui.add(egui::Slider::new(amount, 0.0..=1.0)).changed()
+                                        }
+                                        ColorFilter::Grayscale { amount } | ColorFilter::Sepia { amount } | ColorFilter::Invert { amount } => {
+                                            ui.add(egui::Slider::new(amount, 0.0..=1.0)).changed()
+                                        }
+                                        ColorFilter::HueRotate { degrees } => {
+                                            ui.add(egui::Slider::new(degrees, -180.0..=180.0).suffix("°")).changed()
+                                        }
+                                        ColorFilter::Saturation { factor } | ColorFilter::Brightness { factor } | ColorFilter::Contrast { factor } => {
+                                            ui.add(egui::Slider::new(factor, 0.0..=3.0).suffix("x")).changed()
+                                        }
+                                    };
+                                    if ui.add_enabled(i > 0, egui::Button::new("⬆").small()).clicked() {
+                                        move_up = Some(i);
+                                    }
+                                    if ui.add_enabled(i + 1 < count, egui::Button::new("⬇").small()).clicked() {
+                                        move_up = Some(i + 1);
+                                    }
+                                    if ui.small_button("✖").clicked() {
+                                        remove = Some(i);
+                                    }
+                                });
+                            });
+                        }
+
+                        if let Some(i) = move_up {
+                            config.filters.swap(i - 1, i);
+                            should_save = true;
+                        }
+                        if let Some(i) = remove {
+                            config.filters.remove(i);
+                            should_save = true;
+                        }
+
+                        egui::ComboBox::from_id_source("add_filter_combo")
+                            .selected_text("+ Add filter")
+                            .show_ui(ui, |ui| {
+                                for name in ColorFilter::NAMES {
+                                    if ui.selectable_label(false, name).clicked() {
+                                        config.filters.extend(ColorFilter::from_name(name));
+                                        should_save = true;
+                                    }
+                                }
+                            });
+                    });
+                    ui.end_row();
+
+                    // Outline
+                    ui.label("Outline:");
+                    ui.horizontal(|ui| {
+                        let mut enabled = config.outline.is_some();
+                        if ui.checkbox(&mut enabled, "").changed() {
+                            config.outline = enabled.then(Outline::default);
+                            should_save = true;
+                        }
+
+                        if let Some(outline) = &mut config.outline {
+                            should_save |= egui::color_picker::color_edit_button_srgb(ui, &mut outline.color).changed();
+                            ui.label("Thickness:");
+                            should_save |= ui.add(egui::DragValue::new(&mut outline.thickness).clamp_range(1..=16).suffix(" px")).changed();
+                        }
+                    });
+                    ui.end_row();
+
+                    // Drop shadow
+                    ui.label("Shadow:");
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            let mut enabled = config.shadow.is_some();
+                            if ui.checkbox(&mut enabled, "").changed() {
+                                config.shadow = enabled.then(Shadow::default);
+                                should_save = true;
+                            }
+
+                            if let Some(shadow) = &mut config.shadow {
+                                should_save |= egui::color_picker::color_edit_button_srgb(ui, &mut shadow.color).changed();
+                                ui.label("Opacity:");
+                                should_save |= ui.add(egui::Slider::new(&mut shadow.opacity, 0.0..=1.0)).changed();
+                            }
+                        });
+
+                        if let Some(shadow) = &mut config.shadow {
+                            ui.horizontal(|ui| {
+                                ui.label("Offset X:");
+                                should_save |= ui.add(egui::DragValue::new(&mut shadow.offset.0).clamp_range(-32..=32)).changed();
+                                ui.label("Y:");
+                                should_save |= ui.add(egui::DragValue::new(&mut shadow.offset.1).clamp_range(-32..=32)).changed();
+                                ui.add_space(10.0);
+                                ui.label("Blur:");
+                                should_save |= ui.add(egui::DragValue::new(&mut shadow.blur).clamp_range(0..=32).suffix(" px")).changed();
+                            });
+                        }
+                    });
+                    ui.end_row();
+
                     // Alignment
                     ui.label("Alignment:");
                     let mut changed_align = false;
@@ -905,8 +1534,43 @@ impl AnimeApp {
         } else {
             None
         };
+        let export_config = to_export.and_then(|n| store.gifs.get(&n).cloned());
+        let update_config = to_update.and_then(|n| store.gifs.get(&n).cloned());
+        let click_through = store.settings.click_through;
         
         drop(store);
+
+        if let Some(name) = to_stop {
+            if let Err(e) = self.stop_by_name(&name) {
+                self.status = Some(e);
+            }
+        }
+
+        if let Some(config) = update_config {
+            // Players answer once they've reloaded, which can take a moment for big files
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                let mut ps = ProcessStore::load();
+                let _ = tx.send(launch::update(&config, click_through, &mut ps));
+            });
+            self.status = None;
+            self.update_rx = Some(rx);
+        }
+
+        if let Some(config) = export_config {
+            let file_name = format!("{}.gif", config.name);
+            if let Some(path) = rfd::FileDialog::new()
+                .set_file_name(file_name)
+                .add_filter("GIF", &["gif"])
+                .add_filter("Animated PNG", &["png", "apng"])
+                .add_filter("Animated WebP", &["webp"])
+                .save_file()
+            {
+                let options = GifOptions { dither: self.export_dither, ..GifOptions::default() };
+                self.status = None;
+                self.export_rx = Some(spawn_export(config, path, options));
+            }
+        }
         
         if let Some(c) = launch_config {
             self.launch_animation(&c);
@@ -914,52 +1578,206 @@ impl AnimeApp {
     }
 
     fn launch_animation(&mut self, config: &GifConfig) {
-        let exe = match std::env::current_exe() {
-            Ok(exe) => exe,
-            Err(e) => {
-                eprintln!("Error: Failed to get executable path: {}", e);
-                return;
-            }
-        };
-        let mut cmd = Command::new(exe);
-        cmd.arg("play").arg(&config.path);
-           
-        if let Some(fps) = config.fps {
-            cmd.arg("--fps").arg(fps.to_string());
+        let click_through = Self::lock_store(&self.store).settings.click_through;
+        let mut ps = Self::lock_process_store(&self.process_store);
+        if let Err(e) = launch::launch(config, click_through, &mut ps) {
+            eprintln!("{}", e);
         }
-        if let Some(scale) = config.scale {
-            cmd.arg("--scale").arg(scale.to_string());
-        }
-        if let Some((x, y)) = config.position {
-            cmd.arg("--x").arg(x.to_string());
-            cmd.arg("--y").arg(y.to_string());
+    }
+    
+    // Name a compositor scene process is registered under in the process store
+    fn scene_process_name(scene: &str) -> String {
+        format!("Scene: {}", scene)
+    }
+
+    fn is_running(&self, name: &str) -> bool {
+        let ps = Self::lock_process_store(&self.process_store);
+        ps.processes.values().any(|info| info.hosts(name))
+    }
+
+    fn stop_by_name(&self, name: &str) -> Result<usize, String> {
+        let mut ps = Self::lock_process_store(&self.process_store);
+        launch::stop(name, &mut ps)
+    }
+
+    fn start_scene(&mut self, name: &str, scene: &Scene) {
+        match scene.mode {
+            SceneMode::Isolated => {
+                for animation in &scene.animations {
+                    let config = Self::lock_store(&self.store).gifs.get(animation).cloned();
+                    if let Some(config) = config.filter(|_| !self.is_running(animation)) {
+                        self.launch_animation(&config);
+                    }
+                }
+            }
+            SceneMode::Shared | SceneMode::Compositor => {
+                // The scene process draws these itself, so separate players would show them twice
+                for animation in &scene.animations {
+                    if let Err(e) = self.stop_by_name(animation) {
+                        eprintln!("{}", e);
+                    }
+                }
+
+                let exe = match std::env::current_exe() {
+                    Ok(exe) => exe,
+                    Err(e) => {
+                        eprintln!("Error: Failed to get executable path: {}", e);
+                        return;
+                    }
+                };
+                let command = if scene.mode == SceneMode::Shared { "play-many" } else { "compose" };
+                match Command::new(exe).arg(command).args(&scene.animations).spawn() {
+                    Ok(child) => {
+                        let mut ps = Self::lock_process_store(&self.process_store);
+                        // Shared scenes run ordinary players, which can be controlled; the compositor can't
+                        let control = scene.mode == SceneMode::Shared;
+                        ps.add_host_process(child.id(), Self::scene_process_name(name), scene.animations.clone(), control);
+                    }
+                    Err(e) => eprintln!("Failed to start: {}", e),
+                }
+            }
         }
-        
-        cmd.arg("--align").arg(&config.align);
-        cmd.arg("--monitor").arg(config.monitor.to_string());
+    }
 
-        if config.overlay {
-            cmd.arg("--overlay");
+    fn stop_scene(&self, name: &str, scene: &Scene) {
+        let mut result = self.stop_by_name(&Self::scene_process_name(name));
+        if scene.mode == SceneMode::Isolated {
+            for animation in &scene.animations {
+                result = result.and(self.stop_by_name(animation));
+            }
         }
-        
-        // Get click-through setting from store
-        let click_through = {
-            let store = Self::lock_store(&self.store);
-            store.settings.click_through
-        };
-        if click_through {
-            cmd.arg("--click-through");
+        if let Err(e) = result {
+            eprintln!("{}", e);
         }
+    }
 
-        match cmd.spawn() {
-            Ok(child) => {
-                let mut ps = Self::lock_process_store(&self.process_store);
-                ps.add_process(child.id(), config.name.clone());
-            },
-            Err(e) => eprintln!("Failed to start: {}", e),
-        }
+    fn show_scenes(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Scenes").show(ui, |ui| {
+            // Save whatever is playing now, in launch order (which becomes the z-order)
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.scene_name_input).hint_text("Scene name...").desired_width(120.0));
+                let name = self.scene_name_input.trim().to_string();
+                if ui.add_enabled(!name.is_empty(), egui::Button::new("Save running")).on_hover_text("Save the animations playing now as a scene").clicked() {
+                    let mut running: Vec<_> = {
+                        let ps = Self::lock_process_store(&self.process_store);
+                        ps.processes
+                            .values()
+                            .flat_map(|info| {
+                                // A shared process counts as each of the animations it hosts
+                                let names = if info.animations.is_empty() { vec![info.name.clone()] } else { info.animations.clone() };
+                                names.into_iter().map(move |name| (info.start_time, name))
+                            })
+                            .collect()
+                    };
+                    running.sort_by_key(|(start_time, _)| *start_time);
+
+                    let mut store = Self::lock_store(&self.store);
+                    let animations: Vec<String> = running
+                        .into_iter()
+                        .map(|(_, name)| name)
+                        .filter(|name| store.gifs.contains_key(name))
+                        .collect();
+                    let mode = store.scenes.get(&name).map(|scene| scene.mode).unwrap_or_default();
+                    store.scenes.insert(name, Scene { animations, mode });
+                    let _ = store.save();
+                    self.scene_name_input.clear();
+                }
+            });
+
+            let mut names: Vec<String> = Self::lock_store(&self.store).scenes.keys().cloned().collect();
+            names.sort();
+            if names.is_empty() {
+                ui.label(egui::RichText::new("Start some animations, then save them as a scene.").small().weak());
+            }
+
+            let mut to_start = None;
+            let mut to_stop = None;
+            let mut to_delete = None;
+            let mut should_save = false;
+
+            for name in names {
+                let running = {
+                    let store = Self::lock_store(&self.store);
+                    let scene = &store.scenes[&name];
+                    let ps = Self::lock_process_store(&self.process_store);
+                    ps.processes.values().any(|info| {
+                        info.name == Self::scene_process_name(&name)
+                            || (scene.mode == SceneMode::Isolated && scene.animations.contains(&info.name))
+                    })
+                };
+
+                let mut store = Self::lock_store(&self.store);
+                let Some(scene) = store.scenes.get_mut(&name) else { continue };
+
+                ui.push_id(&name, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(if running { format!("▶ {}", name) } else { name.clone() });
+                        egui::ComboBox::from_id_source("scene_mode")
+                            .selected_text(scene.mode.label())
+                            .width(90.0)
+                            .show_ui(ui, |ui| {
+                                for mode in SceneMode::ALL {
+                                    should_save |= ui.selectable_value(&mut scene.mode, mode, mode.label()).changed();
+                                }
+                            })
+                            .response
+                            .on_hover_text("Isolated: one process per animation. Shared: one process, a window per animation. Compositor: one click-through window per monitor.");
+                        if running {
+                            if ui.small_button("⏹").on_hover_text("Stop").clicked() {
+                                to_stop = Some((name.clone(), scene.clone()));
+                            }
+                        } else if ui.small_button("▶").on_hover_text("Start").clicked() {
+                            to_start = Some((name.clone(), scene.clone()));
+                        }
+                        if ui.small_button("✖").on_hover_text("Delete scene").clicked() {
+                            to_delete = Some(name.clone());
+                        }
+                    });
+
+                    // Members, bottom to top; order only matters in compositor mode
+                    ui.collapsing(format!("{} animations", scene.animations.len()), |ui| {
+                        let mut move_up = None;
+                        let mut remove = None;
+                        for (i, animation) in scene.animations.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(animation);
+                                if ui.add_enabled(i > 0, egui::Button::new("⬆").small()).on_hover_text("Draw below the previous one").clicked() {
+                                    move_up = Some(i);
+                                }
+                                if ui.small_button("✖").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+                        }
+                        if let Some(i) = move_up {
+                            scene.animations.swap(i - 1, i);
+                            should_save = true;
+                        }
+                        if let Some(i) = remove {
+                            scene.animations.remove(i);
+                            should_save = true;
+                        }
+                    });
+                });
+            }
+
+            if let Some(name) = to_delete {
+                let mut store = Self::lock_store(&self.store);
+                store.scenes.remove(&name);
+                should_save = true;
+            }
+            if should_save {
+                let _ = Self::lock_store(&self.store).save();
+            }
+            if let Some((name, scene)) = to_stop {
+                self.stop_scene(&name, &scene);
+            }
+            if let Some((name, scene)) = to_start {
+                self.start_scene(&name, &scene);
+            }
+        });
     }
-    
+
     fn show_library(&mut self, ui: &mut egui::Ui) {
         ui.heading("Library");
         ui.add_space(10.0);
@@ -1034,7 +1852,7 @@ impl AnimeApp {
                     // Check running status
                     let is_running = {
                          let ps = Self::lock_process_store(&self.process_store);
-                         ps.processes.values().any(|info| info.name == name)
+                         ps.processes.values().any(|info| info.hosts(&name))
                     };
 
                     let label = if is_running {
@@ -1049,16 +1867,9 @@ impl AnimeApp {
                             self.selected_name = Some(name.clone());
                             // Trigger load
                             if let Some(config) = store.gifs.get(&name) {
-                                let path = config.path.clone();
-                                let (tx, rx) = mpsc::channel();
-                                self.load_rx = Some(rx);
+                                self.load_rx = Some(spawn_load(config.path.clone(), config.palette.clone()));
                                 self.is_loading = true;
                                 self.preview = None;
-                                
-                                thread::spawn(move || {
-                                    let res = crate::decoder::load_animation(path);
-                                    let _ = tx.send(res.map_err(|e| e.to_string()));
-                                });
                             }
                         }
                     }
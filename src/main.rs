@@ -1,18 +1,37 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod cache;
+mod control;
 mod decoder;
+mod encoder;
 mod renderer;
 mod playback;
 mod platform;
 mod app;
 mod gui;
+mod processing;
 pub mod types;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
+use crate::cache::decode_cache::DecodeCache;
 use crate::cache::frame_buffer::FrameBuffer;
+use crate::decoder::palette::PaletteSwap;
+use crate::encoder::gif::{GifOptions, PaletteMode};
+use crate::encoder::quantize::Dither;
+use crate::processing::ProcessOptions;
+use crate::processing::background::{BackgroundRemoval, EdgeFill};
+use crate::processing::chroma::ChromaKey;
+use crate::processing::defringe::Defringe;
+use crate::processing::effects::{Outline, Shadow};
+use crate::processing::filters::ColorFilter;
+use crate::processing::interpolate::{Interpolation, InterpolationMethod};
+use crate::processing::trim::CropRect;
+use crate::playback::player::PlayerOptions;
+use crate::playback::sequence::{FrameRange, PlaybackMode};
 
 #[derive(Parser)]
 #[command(name = "gif-engine")]
@@ -28,19 +47,25 @@ enum Commands {
     Info {
         /// Path to GIF file
         file: PathBuf,
+
+        /// Print everything as JSON instead, for scripts
+        #[arg(long)]
+        json: bool,
+
+        /// List every frame's delay, disposal, blending and rectangle
+        #[arg(long)]
+        frames: bool,
+
+        #[command(flatten)]
+        interpolate: InterpolateArgs,
     },
     /// Play animation in a standalone window (used by the GUI)
     Play {
         /// Path to GIF file
         file: PathBuf,
-        
-        /// Target FPS (overrides GIF delay)
-        #[arg(long)]
-        fps: Option<u32>,
 
-        /// Scale factor (0.1 to 1.0)
-        #[arg(long)]
-        scale: Option<f32>,
+        #[command(flatten)]
+        timing: TimingArgs,
 
         /// X Position
         #[arg(long)]
@@ -65,10 +90,559 @@ enum Commands {
         /// Monitor Index (0, 1, 2...)
         #[arg(long, default_value_t = 0)]
         monitor: usize,
+
+        /// Opacity (0.05 to 1.0), adjustable at runtime with Ctrl + mouse wheel
+        #[arg(long, default_value_t = 1.0, value_parser = parse_opacity)]
+        opacity: f32,
+
+        /// Library name, used to address this player through `control` (defaults to the file name)
+        #[arg(long)]
+        name: Option<String>,
+
+        #[command(flatten)]
+        interpolate: InterpolateArgs,
+
+        #[command(flatten)]
+        process: Box<ProcessArgs>,
+    },
+    /// Play library animations composited into one window per monitor (used by the GUI for scenes)
+    Compose {
+        /// Library names, drawn bottom to top
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Play library animations from one process, each in its own window, sharing decoding and timing
+    PlayMany {
+        /// Library names
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Write the frames the player would paint as PNG files, without opening a window
+    Render {
+        /// Path to GIF file
+        file: PathBuf,
+
+        /// Output directory for a frame sequence, or the PNG file for --sheet and --frame
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// Combine every frame into one contact sheet image
+        #[arg(long, conflicts_with = "frame")]
+        sheet: bool,
+
+        /// Frames per row on the contact sheet
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+        columns: u32,
+
+        /// Only write this frame, counted in playback order
+        #[arg(long)]
+        frame: Option<usize>,
+
+        #[command(flatten)]
+        timing: TimingArgs,
+
+        #[command(flatten)]
+        interpolate: InterpolateArgs,
+
+        #[command(flatten)]
+        process: Box<ProcessArgs>,
+    },
+    /// Save the processed animation as a GIF, animated PNG or animated WebP
+    Export {
+        /// Path to GIF file
+        file: PathBuf,
+
+        /// File to write
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// Output format (gif, apng, webp); guessed from the output extension if left out
+        #[arg(long)]
+        format: Option<encoder::ExportFormat>,
+
+        #[command(flatten)]
+        gif: GifArgs,
+
+        /// Times to play the animation, 0 for forever (defaults to the source file's setting)
+        #[arg(long)]
+        loops: Option<u32>,
+
+        #[command(flatten)]
+        timing: TimingArgs,
+
+        #[command(flatten)]
+        interpolate: InterpolateArgs,
+
+        #[command(flatten)]
+        process: Box<ProcessArgs>,
+    },
+    /// Convert an animation to another format as it is, without processing (gif, apng, webp)
+    Convert {
+        /// Animation to read
+        input: PathBuf,
+
+        /// File to write
+        output: PathBuf,
+
+        /// Output format (gif, apng, webp); guessed from the output extension if left out
+        #[arg(long)]
+        format: Option<encoder::ExportFormat>,
+
+        #[command(flatten)]
+        gif: GifArgs,
+
+        /// Times to play the animation, 0 for forever (defaults to the input file's setting)
+        #[arg(long)]
+        loops: Option<u32>,
     },
+    /// Shrink GIFs losslessly by merging repeated frames and storing only what changes.
+    /// Files are replaced only when the result is smaller and looks identical.
+    Optimize {
+        /// GIF files to optimize in place
+        #[arg(required_unless_present = "library")]
+        files: Vec<PathBuf>,
+
+        /// Optimize every GIF in the library's managed folder
+        #[arg(long, conflicts_with = "output")]
+        library: bool,
+
+        /// Write the result here instead of replacing the file (one input only)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Palette size (2 to 256, one entry is kept for transparency)
+        #[arg(long, default_value_t = encoder::gif::default_colors() as u16, value_parser = clap::value_parser!(u16).range(2..=256))]
+        colors: u16,
+
+        /// Dithering, only matters for files with more colors than fit (none, floyd-steinberg, ordered)
+        #[arg(long, default_value = "none")]
+        dither: Dither,
+    },
+    /// Run playback headlessly against a virtual clock and report the frames shown
+    Simulate {
+        /// Path to GIF file
+        file: PathBuf,
+
+        /// Virtual time to simulate, in seconds
+        #[arg(long, default_value_t = 5.0, value_parser = parse_seconds)]
+        duration: f64,
+
+        /// Advance the clock in fixed steps of this many milliseconds (like a vsync-driven
+        /// loop) instead of waking exactly at each frame deadline
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        step: Option<u64>,
+
+        #[command(flatten)]
+        timing: TimingArgs,
+
+        #[command(flatten)]
+        interpolate: InterpolateArgs,
+    },
+    /// Check the library for missing, broken, orphaned or duplicate files, stale running
+    /// entries and invalid settings
+    Doctor {
+        /// Repair everything that was found
+        #[arg(long)]
+        fix: bool,
+    },
+    /// List the library
+    List {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Import an animation, or every GIF/APNG in a folder, into the library
+    Add {
+        /// File or folder
+        path: PathBuf,
+
+        /// Also import from subfolders
+        #[arg(long, short)]
+        recursive: bool,
+
+        /// Library name (single file only, defaults to the file name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Remove an animation from the library, stopping it first
+    Remove {
+        name: String,
+    },
+    /// Rename a library animation
+    Rename {
+        old: String,
+        new: String,
+    },
+    /// Change settings of a library animation, e.g. `set cat fps=24 align=bottom-right`.
+    /// Keys: fps, speed, mode, range, interpolate, interpolation, scale, x, y, align, monitor,
+    /// overlay, opacity, crop, trim, palette, filters, and the background, defringe, outline and
+    /// shadow flags of `play` without dashes in front (chroma-key, chroma-tolerance, edge-fill,
+    /// shadow-blur, ...); `none` clears optional ones
+    Set {
+        name: String,
+
+        /// key=value pairs
+        #[arg(required = true)]
+        settings: Vec<String>,
+
+        /// Apply the change to the animation now if it's playing
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Start library animations, each in its own player
+    Start {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Stop library animations
+    Stop {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+    /// Stop every running animation and scene
+    StopAll,
+    /// Show running players
+    Status {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Change a running animation without restarting it
+    Control {
+        /// Library name of the running animation
+        name: String,
+
+        #[command(subcommand)]
+        action: ControlAction,
+    },
+}
+
+/// Commands a running player understands
+#[derive(Subcommand)]
+enum ControlAction {
+    /// Hold the current frame
+    Pause,
+    Resume,
+    /// Play at this multiple of the file's own speed (0.1 to 10.0)
+    Speed { speed: f32 },
+    /// Window opacity (0.05 to 1.0)
+    Opacity {
+        #[arg(value_parser = parse_opacity)]
+        opacity: f32,
+    },
+    /// Move the window's top-left corner to desktop coordinates
+    Move {
+        #[arg(allow_hyphen_values = true)]
+        x: i32,
+        #[arg(allow_hyphen_values = true)]
+        y: i32,
+    },
+    /// Apply the library settings (or re-read the file) without closing the window
+    Reload,
+    /// Close the animation
+    Quit,
+    /// Show frame, speed, opacity and position
+    Status,
+}
+
+impl ControlAction {
+    fn to_command(&self, config: Option<&app::store::GifConfig>) -> control::Command {
+        match self {
+            ControlAction::Pause => control::Command::Pause,
+            ControlAction::Resume => control::Command::Resume,
+            ControlAction::Speed { speed } => control::Command::SetSpeed { speed: *speed },
+            ControlAction::Opacity { opacity } => control::Command::SetOpacity { opacity: *opacity },
+            ControlAction::Move { x, y } => control::Command::Move { x: *x, y: *y },
+            ControlAction::Reload => control::Command::Reload { config: config.cloned().map(Box::new) },
+            ControlAction::Quit => control::Command::Quit,
+            ControlAction::Status => control::Command::Status,
+        }
+    }
+}
+
+/// Frame timing and order flags
+#[derive(Args)]
+struct TimingArgs {
+    /// Constant FPS (replaces every frame delay)
+    #[arg(long)]
+    fps: Option<u32>,
+
+    /// Speed multiplier (0.1 to 10.0), scales each frame's own delay
+    #[arg(long, default_value_t = 1.0, conflicts_with = "fps")]
+    speed: f32,
+
+    /// Frame order (forward, reverse, ping-pong, random, shuffle)
+    #[arg(long, default_value = "forward")]
+    mode: PlaybackMode,
+
+    /// Only loop over frames start..end (end exclusive)
+    #[arg(long)]
+    range: Option<FrameRange>,
+}
+
+impl TimingArgs {
+    fn apply(&self, buffer: &mut FrameBuffer) {
+        buffer.set_range(self.range);
+        buffer.set_mode(self.mode);
+
+        // Optional FPS override
+        buffer.set_timing(self.fps, self.speed);
+        if let Some(target_fps) = self.fps {
+            println!("Overriding FPS to {}", target_fps);
+        } else if (self.speed - 1.0).abs() > f32::EPSILON {
+            println!("Playing at {}x speed", self.speed);
+        }
+    }
+}
+
+/// Frame interpolation flags
+#[derive(Args)]
+struct InterpolateArgs {
+    /// Generate in-between frames to play at this FPS (forward, reverse and ping-pong modes only)
+    #[arg(long, value_name = "FPS")]
+    interpolate: Option<u32>,
+
+    /// How in-between frames are made (blend, motion)
+    #[arg(long, default_value = "blend", requires = "interpolate")]
+    interpolation: InterpolationMethod,
+}
+
+impl InterpolateArgs {
+    fn to_settings(&self) -> Option<Interpolation> {
+        self.interpolate.map(|target_fps| Interpolation {
+            target_fps,
+            method: self.interpolation,
+        })
+    }
+
+    /// The settings for playing in `mode`, refusing orders that interpolation can't follow
+    fn for_mode(&self, mode: PlaybackMode) -> Result<Option<Interpolation>, String> {
+        match self.to_settings() {
+            Some(_) if !mode.can_interpolate() => Err(format!(
+                "--interpolate can't be used with --mode {} (only forward, reverse and ping-pong)",
+                mode
+            )),
+            settings => Ok(settings),
+        }
+    }
+}
+
+/// GIF export quantization flags
+#[derive(Args)]
+struct GifArgs {
+    /// Palette size for GIF output (2 to 256, transparency included)
+    #[arg(long, default_value_t = encoder::gif::default_colors() as u16, value_parser = clap::value_parser!(u16).range(2..=256))]
+    colors: u16,
+
+    /// Dithering for GIF output (none, floyd-steinberg, ordered)
+    #[arg(long, default_value = "floyd-steinberg")]
+    dither: Dither,
+
+    /// GIF color tables (global, per-frame)
+    #[arg(long, default_value = "global")]
+    palette: PaletteMode,
+
+    /// Shrink GIF output: merge repeated frames and store only the changed area of each frame
+    /// (always uses one shared palette)
+    #[arg(long)]
+    optimize: bool,
+}
+
+impl GifArgs {
+    fn to_options(&self) -> GifOptions {
+        GifOptions {
+            colors: self.colors as usize,
+            dither: self.dither,
+            palette: self.palette,
+            optimize: self.optimize,
+        }
+    }
+}
+
+/// Frame processing flags, shared by every command that runs the processing pipeline
+#[derive(Args)]
+struct ProcessArgs {
+    /// Recolor a palette entry before compositing (#rrggbb=#rrggbb, repeatable, GIF only)
+    #[arg(long = "palette-swap", value_name = "SWAP")]
+    palette_swaps: Vec<PaletteSwap>,
+
+    /// Crop to a rectangle of the original canvas (x,y,width,height)
+    #[arg(long)]
+    crop: Option<CropRect>,
+
+    /// Trim transparent borders shared by all frames
+    #[arg(long)]
+    trim: bool,
+
+    /// Scale factor (0.1 to 2.0)
+    #[arg(long, value_parser = parse_scale)]
+    scale: Option<f32>,
+
+    /// Remove a solid background color (#rrggbb, or "auto" to detect it from the corners)
+    #[arg(long)]
+    chroma_key: Option<String>,
+
+    /// Chroma-key color distance (0.0 to 1.0) treated as background
+    #[arg(long, default_value_t = processing::chroma::default_tolerance())]
+    chroma_tolerance: f32,
+
+    /// Chroma-key edge softness (0.0 to 1.0)
+    #[arg(long, default_value_t = processing::chroma::default_softness())]
+    chroma_softness: f32,
+
+    /// Remove only the background connected to the canvas border (#rrggbb, or "auto")
+    #[arg(long, conflicts_with = "chroma_key")]
+    edge_fill: Option<String>,
+
+    /// Edge-fill color distance (0.0 to 1.0) the fill may spread across
+    #[arg(long, default_value_t = processing::background::default_edge_tolerance())]
+    edge_tolerance: f32,
+
+    /// Remove the halo left by art anti-aliased against this matte color (#rrggbb)
+    #[arg(long)]
+    defringe: Option<String>,
+
+    /// Pixels to erode from the alpha edge (with --defringe)
+    #[arg(long, default_value_t = 0)]
+    defringe_erode: u32,
+
+    /// Pixels to feather the alpha edge over (with --defringe)
+    #[arg(long, default_value_t = 0)]
+    defringe_feather: u32,
+
+    /// Color filter, repeatable and applied in order: tint=#rrggbb[:amount], grayscale[=amount],
+    /// sepia[=amount], hue-rotate=degrees, saturation=factor, brightness=factor,
+    /// contrast=factor, invert[=amount]
+    #[arg(long = "filter", value_name = "FILTER")]
+    filters: Vec<ColorFilter>,
+
+    /// Draw an outline of this color (#rrggbb) around the opaque pixels
+    #[arg(long)]
+    outline: Option<String>,
+
+    /// Outline thickness in pixels
+    #[arg(long, default_value_t = processing::effects::default_outline_thickness())]
+    outline_width: u32,
+
+    /// Draw a drop shadow of this color (#rrggbb)
+    #[arg(long)]
+    shadow: Option<String>,
+
+    /// Shadow horizontal offset in pixels
+    #[arg(long, default_value_t = processing::effects::default_shadow_offset().0, allow_negative_numbers = true)]
+    shadow_x: i32,
+
+    /// Shadow vertical offset in pixels
+    #[arg(long, default_value_t = processing::effects::default_shadow_offset().1, allow_negative_numbers = true)]
+    shadow_y: i32,
+
+    /// Shadow blur radius in pixels
+    #[arg(long, default_value_t = processing::effects::default_shadow_blur())]
+    shadow_blur: u32,
+
+    /// Shadow opacity (0.0 to 1.0)
+    #[arg(long, default_value_t = processing::effects::default_shadow_opacity())]
+    shadow_opacity: f32,
+}
+
+// "auto" means detect the color from the frame corners
+fn parse_key_color(value: &str) -> Result<Option<[u8; 3]>, String> {
+    if value == "auto" {
+        Ok(None)
+    } else {
+        processing::parse_hex_color(value).map(Some)
+    }
+}
+
+// A number of seconds that fits a Duration: finite and not negative
+fn parse_seconds(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(seconds) if std::time::Duration::try_from_secs_f64(seconds).is_ok() => Ok(seconds),
+        _ => Err(format!("Invalid duration '{}', expected a number of seconds", value)),
+    }
+}
+
+fn parse_opacity(value: &str) -> Result<f32, String> {
+    parse_in_range("opacity", value, app::store::OPACITY_RANGE)
+}
+
+fn parse_scale(value: &str) -> Result<f32, String> {
+    parse_in_range("scale", value, app::store::SCALE_RANGE)
+}
+
+fn parse_in_range(what: &str, value: &str, range: std::ops::RangeInclusive<f32>) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(v) if range.contains(&v) => Ok(v),
+        _ => Err(format!("Invalid {} '{}', expected {} to {}", what, value, range.start(), range.end())),
+    }
+}
+
+impl ProcessArgs {
+    fn to_options(&self) -> Result<ProcessOptions, String> {
+        let background = if let Some(value) = &self.chroma_key {
+            Some(BackgroundRemoval::ChromaKey(ChromaKey {
+                color: parse_key_color(value)?,
+                tolerance: self.chroma_tolerance,
+                softness: self.chroma_softness,
+            }))
+        } else if let Some(value) = &self.edge_fill {
+            Some(BackgroundRemoval::EdgeFill(EdgeFill {
+                color: parse_key_color(value)?,
+                tolerance: self.edge_tolerance,
+            }))
+        } else {
+            None
+        };
+
+        let defringe = match &self.defringe {
+            Some(matte) => Some(Defringe {
+                matte: processing::parse_hex_color(matte)?,
+                erode: self.defringe_erode,
+                feather: self.defringe_feather,
+            }),
+            None => None,
+        };
+
+        let outline = match &self.outline {
+            Some(color) => Some(Outline {
+                color: processing::parse_hex_color(color)?,
+                thickness: self.outline_width,
+            }),
+            None => None,
+        };
+
+        let shadow = match &self.shadow {
+            Some(color) => Some(Shadow {
+                offset: (self.shadow_x, self.shadow_y),
+                blur: self.shadow_blur,
+                color: processing::parse_hex_color(color)?,
+                opacity: self.shadow_opacity,
+            }),
+            None => None,
+        };
+
+        Ok(ProcessOptions {
+            palette: self.palette_swaps.clone(),
+            crop: self.crop,
+            trim: self.trim,
+            scale: self.scale,
+            background,
+            defringe,
+            filters: self.filters.clone(),
+            outline,
+            shadow,
+        })
+    }
 }
 
-fn main() {
+fn main() -> ExitCode {
+    // Players and the manager run windowed; everything else is a command-line tool
+    if !matches!(
+        std::env::args().nth(1).as_deref(),
+        None | Some("play" | "play-many" | "compose")
+    ) {
+        platform::attach_console();
+    }
     let cli = Cli::parse();
 
     match &cli.command {
@@ -76,88 +650,960 @@ fn main() {
         None => {
             if let Err(e) = gui::run_gui() {
                 eprintln!("GUI Error: {}", e);
+                return ExitCode::FAILURE;
             }
         }
-        // Metadata helper (kept for debugging)
-        Some(Commands::Info { file }) => {
+        // Metadata, for people and (with --json) for scripts
+        Some(Commands::Info { file, interpolate, json, frames: show_frames }) => {
             use std::time::Instant;
 
-            println!("Loading {:?}", file);
+            if !*json {
+                println!("Loading {:?}", file);
+            }
             let start = Instant::now();
 
             match decoder::load_animation(file) {
                 Ok((info, frames)) => {
                     let duration = start.elapsed();
+                    let frame_info = decoder::read_frame_info(file).unwrap_or_else(|e| {
+                        eprintln!("Can't read frame metadata: {}", e);
+                        Vec::new()
+                    });
+                    let has_alpha = frames.iter().any(|f| f.buffer.chunks_exact(4).any(|px| px[3] < 255));
+                    let memory = frames.iter().map(|f| f.buffer.len()).sum::<usize>();
+
+                    let mut buffer = FrameBuffer::new(frames);
+                    let interpolated = interpolate.to_settings().map(|settings| {
+                        let start = Instant::now();
+                        let added = buffer.interpolate(&settings);
+                        (settings, buffer.len(), added, start.elapsed())
+                    });
+
+                    if *json {
+                        let report = InfoReport {
+                            file: file.clone(),
+                            format: info.format,
+                            width: info.width,
+                            height: info.height,
+                            frame_count: info.frame_count,
+                            duration_ms: info.duration.as_secs_f64() * 1000.0,
+                            loop_count: info.loops,
+                            has_alpha,
+                            memory_bytes: memory,
+                            decode_ms: duration.as_secs_f64() * 1000.0,
+                            interpolation: interpolated.as_ref().map(|(settings, frames, added, time)| InterpolationReport {
+                                target_fps: settings.target_fps,
+                                method: settings.method.as_str(),
+                                frame_count: *frames,
+                                added_bytes: *added,
+                                time_ms: time.as_secs_f64() * 1000.0,
+                            }),
+                            frames: frame_info.iter().enumerate().map(|(index, f)| FrameReport {
+                                index,
+                                x: f.x,
+                                y: f.y,
+                                width: f.width,
+                                height: f.height,
+                                delay_ms: f.delay.as_secs_f64() * 1000.0,
+                                disposal: f.disposal,
+                                blend: f.blend,
+                            }).collect(),
+                        };
+                        match serde_json::to_string_pretty(&report) {
+                            Ok(text) => println!("{}", text),
+                            Err(e) => {
+                                eprintln!("Error writing JSON: {}", e);
+                                return ExitCode::FAILURE;
+                            }
+                        }
+                        return ExitCode::SUCCESS;
+                    }
+
                     println!("Loaded in {:.2?}", duration);
+                    println!("Format: {}", info.format);
                     println!("Dimensions: {}x{}", info.width, info.height);
                     println!("Frame count: {}", info.frame_count);
                     println!("Total duration: {:.2?}", info.duration);
+                    match info.loops {
+                        Some(n) => println!("Plays: {} times", n),
+                        None => println!("Plays: forever"),
+                    }
+                    println!("Transparency: {}", if has_alpha { "yes" } else { "no" });
+                    println!("Memory: {:.1} MB decoded", memory as f64 / (1024.0 * 1024.0));
 
                     let fps_decode = info.frame_count as f64 / duration.as_secs_f64();
                     println!("Decode speed: {:.2} fps", fps_decode);
 
-                    let buffer = FrameBuffer::new(frames);
-                    println!("FrameBuffer initialized with {} frames", buffer.len());
+                    if let Some((settings, frames, added, time)) = interpolated {
+                        println!(
+                            "Interpolated to {} fps ({}): {} frames in {:.2?}, +{:.1} MB",
+                            settings.target_fps,
+                            settings.method,
+                            frames,
+                            time,
+                            added as f64 / (1024.0 * 1024.0)
+                        );
+                    }
+
+                    if *show_frames {
+                        println!();
+                        println!("{:>5}  {:>9}  {:<11}  {:<6}  rect", "frame", "delay", "disposal", "blend");
+                        for (index, f) in frame_info.iter().enumerate() {
+                            println!(
+                                "{:>5}  {:>6.1} ms  {:<11}  {:<6}  {}x{} at {},{}",
+                                index,
+                                f.delay.as_secs_f64() * 1000.0,
+                                f.disposal,
+                                f.blend,
+                                f.width,
+                                f.height,
+                                f.x,
+                                f.y
+                            );
+                        }
+                    }
+                }
+                Err(e) if *json => {
+                    // Scripts still get JSON to parse, alongside the failure status
+                    println!("{}", serde_json::json!({ "file": file, "error": e.to_string() }));
+                    return ExitCode::FAILURE;
                 }
                 Err(e) => {
                     eprintln!("Error loading GIF: {}", e);
+                    return ExitCode::FAILURE;
                 }
             }
         }
         // Playback entry used by the GUI when you press Play
-        Some(Commands::Play { file, fps, scale, x, y, overlay, click_through, align, monitor }) => {
+        Some(Commands::Play { file, timing, x, y, overlay, click_through, align, monitor, opacity, name, interpolate, process }) => {
             println!("Playing from CLI: {:?}", file);
-            match decoder::load_animation(file) {
-                Ok((mut info, mut frames)) => {
-                    // Optional scaling
-                    if let Some(s) = scale {
-                        if *s > 0.0 && (s.abs() - 1.0).abs() > f32::EPSILON {
-                            println!("Resizing by factor {}", s);
-                            let new_width = (info.width as f32 * s) as u32;
-                            let new_height = (info.height as f32 * s) as u32;
-                            
-                            use image::{ImageBuffer, Rgba, imageops::FilterType};
-
-                            for frame in &mut frames {
-                                let old_buffer = std::mem::take(&mut frame.buffer);
-                                if let Some(img) = ImageBuffer::<Rgba<u8>, _>::from_raw(frame.width, frame.height, old_buffer) {
-                                    let resized = image::imageops::resize(&img, new_width, new_height, FilterType::Lanczos3);
-                                    frame.width = resized.width();
-                                    frame.height = resized.height();
-                                    frame.buffer = resized.into_raw();
-                                }
-                            }
-                            info.width = new_width as u16;
-                            info.height = new_height as u16;
-                        }
-                    }
 
+            let (options, interpolation) = match process.to_options().and_then(|o| Ok((o, interpolate.for_mode(timing.mode)?))) {
+                Ok(result) => result,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            // The same settings as a library entry, so `control reload` can rebuild the animation
+            let name = name.clone().unwrap_or_else(|| file.file_stem().unwrap_or_default().to_string_lossy().to_string());
+            let mut config = app::store::GifConfig::new(name, file.clone());
+            config.fps = timing.fps;
+            config.speed = timing.speed;
+            config.mode = timing.mode;
+            config.frame_range = timing.range;
+            config.interpolation = interpolation.clone();
+            config.position = x.zip(*y);
+            config.align = align.clone();
+            config.monitor = *monitor;
+            config.overlay = *overlay;
+            config.opacity = *opacity;
+            config.set_process_options(options.clone());
+
+            match processing::load(file, &options) {
+                Ok((info, frames, padding)) => {
                     let mut buffer = FrameBuffer::new(frames);
+                    timing.apply(&mut buffer);
 
-                    // Optional FPS override
-                    if let Some(target_fps) = fps {
-                        let delay = std::time::Duration::from_secs_f64(1.0 / (*target_fps as f64));
-                        buffer.override_delay(delay);
-                        println!("Overriding FPS to {}", target_fps);
+                    // In-between frames are generated from the final delays
+                    if let Some(settings) = &interpolation {
+                        let added = buffer.interpolate(settings);
+                        println!(
+                            "Interpolated to {} fps: {} frames, +{:.1} MB",
+                            settings.target_fps,
+                            buffer.len(),
+                            added as f64 / (1024.0 * 1024.0)
+                        );
                     }
 
+                    let options = PlayerOptions {
+                        overlay: *overlay,
+                        click_through: *click_through,
+                        position: x.zip(*y),
+                        align: align.clone(),
+                        monitor_id: *monitor,
+                        opacity: *opacity,
+                        padding,
+                    };
                     if let Err(e) = playback::player::play(
+                        config,
                         buffer,
                         info.width as u32,
                         info.height as u32,
-                        *overlay,
-                        *click_through,
-                        x.zip(*y),
-                        align.clone(),
-                        *monitor,
+                        options,
                     ) {
                         eprintln!("Playback error: {}", e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error loading GIF: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        // Compositor mode: one process and one window per monitor for a whole scene
+        Some(Commands::Compose { names }) => {
+            let store = app::store::Store::load();
+            let configs: Vec<_> = names
+                .iter()
+                .filter_map(|name| {
+                    let config = store.get_gif(name).cloned();
+                    if config.is_none() {
+                        eprintln!("Unknown animation: {}", name);
+                    }
+                    config
+                })
+                .collect();
+            let missing = configs.len() < names.len();
+
+            if let Err(e) = playback::compositor::compose(configs) {
+                eprintln!("Playback error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            if missing {
+                return ExitCode::FAILURE;
+            }
+        }
+        // Shared process mode: one window per animation, decoded once per file
+        Some(Commands::PlayMany { names }) => {
+            let store = app::store::Store::load();
+            let mut cache = DecodeCache::new();
+            let mut players = Vec::new();
+            let mut failed = false;
+            for name in names {
+                let Some(config) = store.get_gif(name) else {
+                    eprintln!("Unknown animation: {}", name);
+                    failed = true;
+                    continue;
+                };
+                match playback::player::load_config(config, &mut cache) {
+                    Ok((buffer, width, height, padding)) => {
+                        let options = PlayerOptions {
+                            overlay: config.overlay,
+                            click_through: store.settings.click_through,
+                            position: config.position,
+                            align: config.align.clone(),
+                            monitor_id: config.monitor,
+                            opacity: config.opacity,
+                            padding,
+                        };
+                        players.push((config.clone(), buffer, width, height, options));
+                    }
+                    Err(e) => {
+                        eprintln!("Skipping {}: {}", name, e);
+                        failed = true;
+                    }
+                }
+            }
+            println!("Loaded {} animations ({} decodes reused)", players.len(), cache.hits());
+            drop(cache);
+
+            if players.is_empty() {
+                eprintln!("None of the animations could be loaded");
+                return ExitCode::FAILURE;
+            }
+            if let Err(e) = playback::player::play_many(players) {
+                eprintln!("Playback error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        // Headless output of the processed frames, for checking assets without a display
+        Some(Commands::Render { file, output, sheet, columns, frame, timing, interpolate, process }) => {
+            let (_, mut buffer) = match load_processed(file, timing, interpolate, process) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let order = buffer.cycle();
+            if let Err(e) = render(&buffer, &order, output, *sheet, *columns as usize, *frame) {
+                eprintln!("Render error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        // Save the processed animation as a new file
+        Some(Commands::Export { file, output, format, gif, loops, timing, interpolate, process }) => {
+            let Some(format) = format.or_else(|| encoder::ExportFormat::from_path(output)) else {
+                eprintln!("Can't tell the format from {:?}; pass --format (gif, apng, webp)", output);
+                return ExitCode::FAILURE;
+            };
+            let (info, mut buffer) = match load_processed(file, timing, interpolate, process) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            // 0 means forever on the command line, like in the files themselves
+            let loops = match loops {
+                Some(0) => None,
+                Some(n) => Some(*n),
+                None => info.loops,
+            };
+            let order = buffer.cycle();
+            let frames: Vec<_> = order.iter().map(|&index| buffer.get(index)).collect();
+            let start = std::time::Instant::now();
+            match encoder::export(output, format, &frames, loops, &gif.to_options()) {
+                Ok(bytes) => println!(
+                    "Exported {} frames as {} to {:?} ({:.1} KB) in {:.2?}",
+                    frames.len(),
+                    format,
+                    output,
+                    bytes as f64 / 1024.0,
+                    start.elapsed()
+                ),
+                Err(e) => {
+                    eprintln!("Export error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        // Straight format conversion, keeping delays and loop count
+        Some(Commands::Convert { input, output, format, gif, loops }) => {
+            let Some(format) = format.or_else(|| encoder::ExportFormat::from_path(output)) else {
+                eprintln!("Can't tell the format from {:?}; pass --format (gif, apng, webp)", output);
+                return ExitCode::FAILURE;
+            };
+            let (info, frames) = match decoder::load_animation(input) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    eprintln!("Error loading {:?}: {}", input, e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let loops = match loops {
+                Some(0) => None,
+                Some(n) => Some(*n),
+                None => info.loops,
+            };
+            let refs: Vec<_> = frames.iter().collect();
+            match encoder::export(output, format, &refs, loops, &gif.to_options()) {
+                Ok(bytes) => {
+                    let before = std::fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+                    println!("Converted {:?} to {} ({:.1} KB -> {:.1} KB)", input, format, before as f64 / 1024.0, bytes as f64 / 1024.0);
+                }
+                Err(e) => {
+                    eprintln!("Convert error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        // Lossless GIF size reduction, for single files or the whole managed library
+        Some(Commands::Optimize { files, library, output, colors, dither }) => {
+            let options = GifOptions { colors: *colors as usize, dither: *dither, optimize: true, ..GifOptions::default() };
+
+            if let Some(output) = output {
+                let [file] = files.as_slice() else {
+                    eprintln!("--output takes exactly one input file");
+                    return ExitCode::FAILURE;
+                };
+                let result = decoder::load_animation(file).and_then(|(info, frames)| {
+                    let refs: Vec<_> = frames.iter().collect();
+                    encoder::export(output, encoder::ExportFormat::Gif, &refs, info.loops, &options)
+                });
+                return match result {
+                    Ok(bytes) => {
+                        println!("Wrote {:?} ({:.1} KB)", output, bytes as f64 / 1024.0);
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => {
+                        eprintln!("Optimize error: {}", e);
+                        ExitCode::FAILURE
                     }
+                };
+            }
+
+            let mut files = files.clone();
+            let mut failed = false;
+            if *library {
+                let dir = app::store::Store::gifs_dir();
+                match std::fs::read_dir(&dir) {
+                    Ok(entries) => files.extend(
+                        entries
+                            .filter_map(|e| e.ok().map(|e| e.path()))
+                            .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))),
+                    ),
+                    Err(e) => {
+                        eprintln!("Can't read {:?}: {}", dir, e);
+                        failed = true;
+                    }
+                }
+                files.sort();
+            }
+
+            let mut saved = 0;
+            for file in &files {
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                match encoder::optimize_in_place(file, &options) {
+                    Ok((before, after)) if after < before => {
+                        saved += before - after;
+                        println!(
+                            "{}: {:.1} KB -> {:.1} KB, saved {:.1} KB ({:.0}%)",
+                            name,
+                            before as f64 / 1024.0,
+                            after as f64 / 1024.0,
+                            (before - after) as f64 / 1024.0,
+                            (before - after) as f64 * 100.0 / before as f64
+                        );
+                    }
+                    Ok(_) => println!("{}: already optimal, kept", name),
+                    Err(e) => {
+                        println!("{}: skipped ({})", name, e);
+                        failed = true;
+                    }
+                }
+            }
+            println!("Saved {:.1} KB over {} files", saved as f64 / 1024.0, files.len());
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        // Deterministic timing check without a window
+        Some(Commands::Simulate { file, duration, step, timing, interpolate }) => {
+            let interpolation = match interpolate.for_mode(timing.mode) {
+                Ok(interpolation) => interpolation,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            match decoder::load_animation(file) {
+                Ok((_, frames)) => {
+                    let mut buffer = FrameBuffer::new(frames);
+                    timing.apply(&mut buffer);
+                    if let Some(settings) = interpolation {
+                        buffer.interpolate(&settings);
+                    }
+                    let delays: Vec<_> = (0..buffer.len()).map(|i| buffer.get(i).delay).collect();
+
+                    let duration = std::time::Duration::from_secs_f64(*duration);
+                    let step = step.map(std::time::Duration::from_millis);
+                    let result = playback::engine::simulate(buffer, duration, step);
+
+                    println!("{:>10}  {:>6}  {:>10}  {:>10}", "time", "frame", "delay", "shown");
+                    for (i, (time, index)) in result.paints.iter().enumerate() {
+                        let until = result.paints.get(i + 1).map(|p| p.0).unwrap_or(duration);
+                        println!(
+                            "{:>9.3}s  {:>6}  {:>7.1} ms  {:>7.1} ms",
+                            time.as_secs_f64(),
+                            index,
+                            delays[*index].as_secs_f64() * 1000.0,
+                            (until - *time).as_secs_f64() * 1000.0
+                        );
+                    }
+
+                    let painted = result.paints.len();
+                    let mut distinct: Vec<usize> = result.paints.iter().map(|p| p.1).collect();
+                    distinct.sort_unstable();
+                    distinct.dedup();
+                    println!(
+                        "Painted {} frames ({} distinct) in {:.2?} virtual time, {:.2} fps, {} skipped",
+                        painted,
+                        distinct.len(),
+                        duration,
+                        painted as f64 / duration.as_secs_f64().max(f64::EPSILON),
+                        result.skipped
+                    );
                 }
                 Err(e) => {
                     eprintln!("Error loading GIF: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        // Library health check, same as Settings > Doctor in the manager
+        Some(Commands::Doctor { fix }) => {
+            let mut store = app::store::Store::load();
+            let mut processes = app::process::ProcessStore::load();
+            let issues = app::doctor::check(&store, &processes, platform::monitor_count());
+            if issues.is_empty() {
+                println!("No problems found");
+                return ExitCode::SUCCESS;
+            }
+
+            if !*fix {
+                for issue in &issues {
+                    println!("{}", issue);
+                }
+                println!("{} problems found, run with --fix to repair them", issues.len());
+                return ExitCode::FAILURE;
+            }
+
+            let mut unresolved = 0;
+            for issue in &issues {
+                match app::doctor::repair(issue, &mut store, &mut processes) {
+                    Ok(done) => println!("{}\n  fixed: {}", issue, done),
+                    Err(e) => {
+                        println!("{}\n  not fixed: {}", issue, e);
+                        unresolved += 1;
+                    }
+                }
+            }
+            if let Err(e) = store.save() {
+                eprintln!("Error saving library: {}", e);
+                return ExitCode::FAILURE;
+            }
+            if let Err(e) = processes.save() {
+                eprintln!("Error saving running processes: {}", e);
+                return ExitCode::FAILURE;
+            }
+            if unresolved > 0 {
+                println!("{} problems could not be fixed", unresolved);
+                return ExitCode::FAILURE;
+            }
+        }
+        // Library management without the manager window; reads and writes the same files
+        Some(Commands::List { json }) => {
+            let store = app::store::Store::load();
+            let mut processes = app::process::ProcessStore::load();
+            processes.cleanup_dead_processes();
+
+            let mut entries: Vec<_> = store.gifs.values().collect();
+            entries.sort_by(|a, b| a.name.cmp(&b.name));
+            let running = |name: &str| processes.processes.values().any(|p| p.hosts(name));
+
+            if *json {
+                let report: Vec<_> = entries
+                    .iter()
+                    .map(|config| ListEntry { config, running: running(&config.name) })
+                    .collect();
+                match serde_json::to_string_pretty(&report) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        return ExitCode::FAILURE;
+                    }
                 }
+                return ExitCode::SUCCESS;
+            }
+
+            if entries.is_empty() {
+                println!("The library is empty, add animations with `gif-engine add <file>`");
+                return ExitCode::SUCCESS;
+            }
+            println!("{:<24} {:<8} {:<10} {:<8} {:<13} {:>3}  path", "name", "state", "timing", "mode", "align", "mon");
+            for config in entries {
+                let timing = match config.fps {
+                    Some(fps) => format!("{} fps", fps),
+                    None => format!("{}x", config.speed),
+                };
+                println!(
+                    "{:<24} {:<8} {:<10} {:<8} {:<13} {:>3}  {}",
+                    config.name,
+                    if running(&config.name) { "running" } else { "stopped" },
+                    timing,
+                    config.mode.as_str(),
+                    config.align,
+                    config.monitor,
+                    config.path.display()
+                );
             }
         }
+        Some(Commands::Add { path, recursive, name }) => {
+            let mut store = app::store::Store::load();
+            let mut failed = false;
+            if path.is_dir() {
+                if name.is_some() {
+                    eprintln!("--name only works when adding a single file");
+                    return ExitCode::FAILURE;
+                }
+                let mut files = Vec::new();
+                collect_animations(path, *recursive, &mut files);
+                files.sort();
+
+                let mut added = 0;
+                for file in files {
+                    let name = file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                    if name.is_empty() || store.gifs.contains_key(&name) {
+                        println!("Skipped {:?}: '{}' is already in the library", file, name);
+                        continue;
+                    }
+                    match store.add_gif(name.clone(), file.clone()) {
+                        Ok(()) => {
+                            println!("Added {}", name);
+                            added += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("Error adding {:?}: {}", file, e);
+                            failed = true;
+                        }
+                    }
+                }
+                println!("Added {} animations", added);
+            } else if path.is_file() {
+                let name = name.clone().unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().to_string());
+                if name.is_empty() || store.gifs.contains_key(&name) {
+                    eprintln!("'{}' is already in the library, pick another with --name", name);
+                    return ExitCode::FAILURE;
+                }
+                if let Err(e) = store.add_gif(name.clone(), path.clone()) {
+                    eprintln!("Error adding {:?}: {}", path, e);
+                    return ExitCode::FAILURE;
+                }
+                println!("Added {}", name);
+            } else {
+                eprintln!("{:?} doesn't exist", path);
+                return ExitCode::FAILURE;
+            }
+            if let Err(e) = store.save() {
+                eprintln!("Error saving library: {}", e);
+                return ExitCode::FAILURE;
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        Some(Commands::Remove { name }) => {
+            let mut store = app::store::Store::load();
+            if !store.gifs.contains_key(name) {
+                eprintln!("No animation named '{}'", name);
+                return ExitCode::FAILURE;
+            }
+            // A running player keeps the file open
+            if let Err(e) = app::launch::stop(name, &mut app::process::ProcessStore::load()) {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+            match store.remove_gif(name).and_then(|_| store.save().map_err(|e| e.to_string())) {
+                Ok(()) => println!("Removed {}", name),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Some(Commands::Rename { old, new }) => {
+            let mut store = app::store::Store::load();
+            match store.rename_gif(old, new).and_then(|_| store.save().map_err(|e| e.to_string())) {
+                Ok(()) => {
+                    app::process::ProcessStore::load().rename(old, new);
+                    println!("Renamed {} to {}", old, new);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        Some(Commands::Set { name, settings, apply }) => {
+            let mut store = app::store::Store::load();
+            let click_through = store.settings.click_through;
+            let Some(config) = store.gifs.get_mut(name) else {
+                eprintln!("No animation named '{}'", name);
+                return ExitCode::FAILURE;
+            };
+            for setting in settings {
+                let Some((key, value)) = setting.split_once('=') else {
+                    eprintln!("Expected key=value, got '{}'", setting);
+                    return ExitCode::FAILURE;
+                };
+                if let Err(e) = config.set(key.trim(), value) {
+                    eprintln!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            let config = config.clone();
+            if let Err(e) = store.save() {
+                eprintln!("Error saving library: {}", e);
+                return ExitCode::FAILURE;
+            }
+            println!("Updated {}", name);
+
+            let mut processes = app::process::ProcessStore::load();
+            processes.cleanup_dead_processes();
+            if !processes.processes.values().any(|p| p.hosts(name)) {
+                return ExitCode::SUCCESS;
+            }
+            if *apply {
+                match app::launch::update(&config, click_through, &mut processes) {
+                    Ok(done) => println!("{}", done),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            } else {
+                println!("{} is playing; run `gif-engine control {} reload` to apply the changes", name, name);
+            }
+        }
+        Some(Commands::Start { names }) => {
+            let store = app::store::Store::load();
+            let mut processes = app::process::ProcessStore::load();
+            processes.cleanup_dead_processes();
+            let mut failed = false;
+            for name in names {
+                let Some(config) = store.gifs.get(name) else {
+                    eprintln!("No animation named '{}'", name);
+                    failed = true;
+                    continue;
+                };
+                if processes.processes.values().any(|p| p.hosts(name)) {
+                    println!("{} is already playing", name);
+                    continue;
+                }
+                match app::launch::launch(config, store.settings.click_through, &mut processes) {
+                    Ok(pid) => println!("Started {} (pid {})", name, pid),
+                    Err(e) => {
+                        eprintln!("{}: {}", name, e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        Some(Commands::Stop { names }) => {
+            let mut processes = app::process::ProcessStore::load();
+            let mut failed = false;
+            for name in names {
+                match app::launch::stop(name, &mut processes) {
+                    Ok(0) => println!("{} isn't playing", name),
+                    Ok(_) => println!("Stopped {}", name),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        Some(Commands::StopAll) => {
+            let mut processes = app::process::ProcessStore::load();
+            let pids: Vec<u32> = processes.processes.keys().copied().collect();
+            // Entries whose process is gone (or whose pid now belongs to something else) are only forgotten
+            let stopped = pids.iter().filter(|pid| processes.kill_process(**pid)).count();
+            println!("Stopped {} processes", stopped);
+            if let Err(e) = processes.save() {
+                eprintln!("Error saving running processes: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        Some(Commands::Status { json }) => {
+            let mut processes = app::process::ProcessStore::load();
+            processes.cleanup_dead_processes();
+            let mut running: Vec<_> = processes.processes.values().collect();
+            running.sort_by_key(|p| p.start_time);
+
+            if *json {
+                match serde_json::to_string_pretty(&running) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                return ExitCode::SUCCESS;
+            }
+
+            if running.is_empty() {
+                println!("Nothing is playing");
+                return ExitCode::SUCCESS;
+            }
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            println!("{:>7}  {:>9}  name", "pid", "uptime");
+            for process in running {
+                let uptime = now.saturating_sub(process.start_time);
+                let mut line = format!(
+                    "{:>7}  {:>3}:{:02}:{:02}  {}",
+                    process.pid,
+                    uptime / 3600,
+                    uptime / 60 % 60,
+                    uptime % 60,
+                    process.name
+                );
+                if !process.animations.is_empty() {
+                    line.push_str(&format!(" ({})", process.animations.join(", ")));
+                }
+                println!("{}", line);
+            }
+        }
+        // Talk to running players over their control endpoint
+        Some(Commands::Control { name, action }) => {
+            let store = app::store::Store::load();
+            let mut processes = app::process::ProcessStore::load();
+            processes.cleanup_dead_processes();
+            let pids: Vec<(u32, bool)> = processes
+                .processes
+                .values()
+                .filter(|p| p.hosts(name))
+                .map(|p| (p.pid, p.control))
+                .collect();
+            if pids.is_empty() {
+                eprintln!("{} isn't playing", name);
+                return ExitCode::FAILURE;
+            }
+
+            let command = action.to_command(store.get_gif(name));
+            let mut failed = false;
+            for (pid, controllable) in pids {
+                if !controllable {
+                    eprintln!("{} plays in a compositor scene (pid {}), which can't be controlled", name, pid);
+                    failed = true;
+                    continue;
+                }
+                match control::send(pid, Some(name), command.clone()).and_then(|r| r.into_result()) {
+                    Ok(response) => {
+                        for player in &response.players {
+                            println!(
+                                "{} (pid {}): frame {}/{}, {}, {:.2}x speed, {:.0}% opacity{}",
+                                player.name,
+                                pid,
+                                player.frame + 1,
+                                player.frames,
+                                if player.paused { "paused" } else { "playing" },
+                                player.speed,
+                                player.opacity * 100.0,
+                                player.position.map(|(x, y)| format!(", at {},{}", x, y)).unwrap_or_default()
+                            );
+                        }
+                        if response.players.is_empty() {
+                            println!("OK (pid {})", pid);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{} (pid {}): {}", name, pid, e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// GIF and APNG files in `dir`, optionally searching subfolders
+fn collect_animations(dir: &std::path::Path, recursive: bool, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            if recursive {
+                collect_animations(&path, recursive, files);
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gif") || ext.eq_ignore_ascii_case("apng"))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// One `list --json` entry: the stored settings plus whether it's playing
+#[derive(Serialize)]
+struct ListEntry<'a> {
+    #[serde(flatten)]
+    config: &'a app::store::GifConfig,
+    running: bool,
+}
+
+/// `info --json` output
+#[derive(Serialize)]
+struct InfoReport {
+    file: PathBuf,
+    format: &'static str,
+    width: u16,
+    height: u16,
+    frame_count: usize,
+    duration_ms: f64,
+    loop_count: Option<u32>, // Total plays, null for forever
+    has_alpha: bool,
+    memory_bytes: usize, // Decoded frames, which is also what playback keeps in memory
+    decode_ms: f64,
+    interpolation: Option<InterpolationReport>,
+    frames: Vec<FrameReport>,
+}
+
+#[derive(Serialize)]
+struct InterpolationReport {
+    target_fps: u32,
+    method: &'static str,
+    frame_count: usize,
+    added_bytes: usize,
+    time_ms: f64,
+}
+
+#[derive(Serialize)]
+struct FrameReport {
+    index: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    delay_ms: f64,
+    disposal: &'static str,
+    blend: &'static str,
+}
+
+// Decode and process a file, then apply the timing and interpolation flags, as `play` does
+fn load_processed(
+    file: &std::path::Path,
+    timing: &TimingArgs,
+    interpolate: &InterpolateArgs,
+    process: &ProcessArgs,
+) -> Result<(types::AnimationInfo, FrameBuffer), String> {
+    let options = process.to_options()?;
+    let interpolation = interpolate.for_mode(timing.mode)?;
+    let (info, frames, _) = processing::load(file, &options).map_err(|e| format!("Error loading GIF: {}", e))?;
+    let mut buffer = FrameBuffer::new(frames);
+    timing.apply(&mut buffer);
+    if let Some(settings) = interpolation {
+        buffer.interpolate(&settings);
+    }
+    Ok((info, buffer))
+}
+
+// Write the frames of one playback cycle as a sequence, a contact sheet or a single image
+fn render(
+    buffer: &FrameBuffer,
+    order: &[usize],
+    output: &std::path::Path,
+    sheet: bool,
+    columns: usize,
+    frame: Option<usize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(n) = frame {
+        let index = *order
+            .get(n)
+            .ok_or_else(|| format!("Frame {} is out of range ({} frames)", n, order.len()))?;
+        encoder::png::save_png(output, buffer.get(index))?;
+        println!("Wrote frame {} to {:?}", n, output);
+        return Ok(());
+    }
+
+    if sheet {
+        let frames: Vec<_> = order.iter().map(|&index| buffer.get(index)).collect();
+        let image = encoder::contact_sheet(&frames, columns, 1);
+        encoder::png::save_png(output, &image)?;
+        println!("Wrote {} frames as a {}x{} sheet to {:?}", frames.len(), image.width, image.height, output);
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(output)?;
+    for (n, &index) in order.iter().enumerate() {
+        let frame = buffer.get(index);
+        let path = output.join(format!("frame_{:04}.png", n));
+        encoder::png::save_png(&path, frame)?;
+        println!("{:?}\tsource {}\t{} ms", path, index, frame.delay.as_millis());
     }
+    println!("Wrote {} frames to {:?}", order.len(), output);
+    Ok(())
 }
@@ -23,3 +23,21 @@ pub fn is_ctrl_pressed() -> bool {
         false
     }
 }
+
+/// Number of connected monitors, if the platform can tell without a window
+pub fn monitor_count() -> Option<usize> {
+    #[cfg(target_os = "windows")]
+    {
+        Some(windows::monitor_count())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+/// Send command-line output to the terminal that started us, if any
+pub fn attach_console() {
+    #[cfg(target_os = "windows")]
+    windows::attach_console();
+}
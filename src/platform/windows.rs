@@ -2,7 +2,9 @@ use windows::Win32::Foundation::HWND;
 use windows::Win32::UI::WindowsAndMessaging::{
     GWL_EXSTYLE, GetWindowLongPtrW, SetWindowLongPtrW, WS_EX_LAYERED, WS_EX_TOOLWINDOW,
     WS_EX_TOPMOST, WS_EX_APPWINDOW, WS_EX_TRANSPARENT, SetWindowPos, HWND_TOPMOST, SWP_NOMOVE, SWP_NOSIZE, SWP_NOACTIVATE,
+    GetSystemMetrics, SM_CMONITORS,
 };
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
 use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
 use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
 use winit::window::Window;
@@ -68,3 +70,15 @@ pub fn is_ctrl_pressed() -> bool {
         (state as u16 & 0x8000) != 0
     }
 }
+
+pub fn monitor_count() -> usize {
+    unsafe { GetSystemMetrics(SM_CMONITORS) as usize }
+}
+
+/// Print to the console the program was started from. Release builds use the GUI subsystem,
+/// so they get no console of their own; this fails harmlessly when there is none to attach to.
+pub fn attach_console() {
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::app::process::ProcessStore;
+use crate::app::store::GifConfig;
+use crate::cache::decode_cache::DecodeCache;
+use crate::platform;
+use crate::playback::engine::{Action, Playback, SystemClock};
+use crate::playback::player::{load_config, place};
+use crate::renderer::pixels::{self, DirtyRect};
+use crate::renderer::window::create_window;
+use softbuffer::{Context, Rect, Surface};
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use std::time::Instant;
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::window::Window;
+
+/// One animation placed on a compositor window
+struct Item {
+    playback: Playback<SystemClock>,
+    x: i32, // Relative to the window
+    y: i32,
+    width: u32,
+    height: u32,
+    opacity: u32, // 0-255
+    shown: Option<usize>, // Frame currently composited
+}
+
+impl Item {
+    // Area covered by the item, clipped to a `width` x `height` window
+    fn bounds(&self, width: u32, height: u32) -> Option<DirtyRect> {
+        let x0 = self.x.max(0);
+        let y0 = self.y.max(0);
+        let x1 = (self.x + self.width as i32).min(width as i32);
+        let y1 = (self.y + self.height as i32).min(height as i32);
+        (x0 < x1 && y0 < y1).then(|| DirtyRect {
+            x: x0 as u32,
+            y: y0 as u32,
+            width: (x1 - x0) as u32,
+            height: (y1 - y0) as u32,
+        })
+    }
+}
+
+/// A transparent, click-through window covering one monitor
+struct Layer {
+    window: Rc<Window>,
+    surface: Surface<Rc<Window>, Rc<Window>>,
+    width: u32,
+    height: u32,
+    items: Vec<Item>, // Bottom to top
+}
+
+impl Layer {
+    // Advance every item and repaint only the areas of items whose frame changed
+    fn update(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut dirty = Vec::new();
+        for item in &mut self.items {
+            if let Some(Action::Paint(index)) = item.playback.update() {
+                let frames = item.playback.frames();
+                let changed = item.shown.map(|shown| frames.content_hash(shown)) != Some(frames.content_hash(index));
+                item.shown = Some(index);
+                if let Some(rect) = item.bounds(self.width, self.height).filter(|_| changed) {
+                    dirty.push(rect);
+                }
+            }
+        }
+
+        if dirty.is_empty() {
+            return Ok(());
+        }
+        self.present(&dirty)
+    }
+
+    fn present(&mut self, dirty: &[DirtyRect]) -> Result<(), Box<dyn std::error::Error>> {
+        let full = DirtyRect { x: 0, y: 0, width: self.width, height: self.height };
+        let mut buffer = self.surface.buffer_mut()?;
+
+        // Age 1 means the buffer still holds the last presented image, so only the damage is redrawn
+        if buffer.age() != 1 {
+            composite(&mut buffer, self.width, full, &self.items);
+            buffer.present()?;
+            return Ok(());
+        }
+
+        for rect in dirty {
+            composite(&mut buffer, self.width, *rect, &self.items);
+        }
+        let damage: Vec<Rect> = dirty
+            .iter()
+            .map(|rect| Rect {
+                x: rect.x,
+                y: rect.y,
+                width: NonZeroU32::new(rect.width).unwrap_or(NonZeroU32::MIN),
+                height: NonZeroU32::new(rect.height).unwrap_or(NonZeroU32::MIN),
+            })
+            .collect();
+        buffer.present_with_damage(&damage)?;
+        Ok(())
+    }
+}
+
+// Clear `rect` and draw every item overlapping it, bottom to top
+fn composite(buffer: &mut [u32], stride: u32, rect: DirtyRect, items: &[Item]) {
+    let stride = stride as usize;
+    for y in rect.y..rect.y + rect.height {
+        let start = y as usize * stride + rect.x as usize;
+        buffer[start..start + rect.width as usize].fill(0);
+    }
+
+    for item in items {
+        let Some(index) = item.shown else { continue };
+        let pixels = item.playback.frames().pixels(index);
+
+        let x0 = (rect.x as i32).max(item.x);
+        let x1 = ((rect.x + rect.width) as i32).min(item.x + item.width as i32);
+        let y0 = (rect.y as i32).max(item.y);
+        let y1 = ((rect.y + rect.height) as i32).min(item.y + item.height as i32);
+        if x0 >= x1 || y0 >= y1 {
+            continue;
+        }
+
+        let len = (x1 - x0) as usize;
+        for y in y0..y1 {
+            let src = (y - item.y) as usize * item.width as usize + (x0 - item.x) as usize;
+            let dst = y as usize * stride + x0 as usize;
+            if let Some(src) = pixels.get(src..src + len) {
+                pixels::blend_over(src, &mut buffer[dst..dst + len], item.opacity);
+            }
+        }
+    }
+}
+
+/// Play several animations in one process, composited into one window per monitor.
+/// `configs` are drawn bottom to top. The windows are always on top and click-through,
+/// since they cover the whole monitor.
+pub fn compose(configs: Vec<GifConfig>) -> Result<(), Box<dyn std::error::Error>> {
+    let event_loop = EventLoop::new()?;
+    let monitors: Vec<_> = event_loop.available_monitors().collect();
+    if monitors.is_empty() {
+        return Err("No monitors available".into());
+    }
+
+    // Group the items by the monitor they belong to, keeping z-order
+    let mut groups: Vec<(usize, Vec<Item>)> = Vec::new();
+    let mut cache = DecodeCache::new();
+    for config in &configs {
+        let (mut frames, width, height, padding) = match load_config(config, &mut cache) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", config.name, e);
+                continue;
+            }
+        };
+        frames.premultiply();
+
+        let monitor_id = if config.monitor < monitors.len() { config.monitor } else { 0 };
+        let size = monitors[monitor_id].size();
+        let (x, y) = place(&config.align, config.position, padding, (width, height), (size.width, size.height));
+        let item = Item {
+            playback: Playback::new(frames, SystemClock),
+            x,
+            y,
+            width,
+            height,
+            opacity: (config.opacity.clamp(0.0, 1.0) * 255.0).round() as u32,
+            shown: None,
+        };
+
+        match groups.iter_mut().find(|(id, _)| *id == monitor_id) {
+            Some((_, items)) => items.push(item),
+            None => groups.push((monitor_id, vec![item])),
+        }
+    }
+
+    // The decoded frames aren't needed once every item has its own processed copy
+    drop(cache);
+
+    let mut layers = Vec::new();
+    for (monitor_id, items) in groups {
+        let monitor = &monitors[monitor_id];
+        let (width, height) = (monitor.size().width, monitor.size().height);
+        let position = (monitor.position().x, monitor.position().y);
+
+        // A monitor-sized window has to let clicks through, and stays on top like an overlay
+        let window = Rc::new(create_window(&event_loop, width, height, Some(position))?);
+        platform::set_overlay(&window, true);
+
+        let context = Context::new(window.clone())?;
+        let mut surface = Surface::new(&context, window.clone())?;
+        if let (Some(w), Some(h)) = (NonZeroU32::new(width), NonZeroU32::new(height)) {
+            surface.resize(w, h)?;
+        }
+
+        println!("Compositing {} animations on monitor {}", items.len(), monitor_id);
+        layers.push(Layer { window, surface, width, height, items });
+    }
+
+    if layers.is_empty() {
+        return Err("None of the animations could be loaded".into());
+    }
+
+    event_loop.run(move |event, elwt| {
+        match event {
+            Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                println!("Close requested, exiting...");
+                let mut ps = ProcessStore::load();
+                ps.remove_self();
+                elwt.exit();
+            }
+            Event::WindowEvent { event: WindowEvent::RedrawRequested, window_id } => {
+                // The system lost the window contents; draw everything again
+                if let Some(layer) = layers.iter_mut().find(|l| l.window.id() == window_id) {
+                    let full = DirtyRect { x: 0, y: 0, width: layer.width, height: layer.height };
+                    if let Err(e) = layer.present(&[full]) {
+                        eprintln!("Paint error: {}", e);
+                    }
+                }
+            }
+            Event::AboutToWait => {
+                for layer in &mut layers {
+                    if let Err(e) = layer.update() {
+                        eprintln!("Paint error: {}", e);
+                    }
+                }
+
+                // Sleep until the earliest item needs its next frame
+                let wake: Option<Instant> = layers
+                    .iter()
+                    .flat_map(|layer| layer.items.iter())
+                    .filter_map(|item| item.playback.next_deadline())
+                    .min();
+                elwt.set_control_flow(match wake {
+                    Some(deadline) => ControlFlow::WaitUntil(deadline),
+                    None => ControlFlow::Wait,
+                });
+            }
+            _ => (),
+        }
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use crate::cache::frame_buffer::FrameBuffer;
+use crate::playback::scheduler::Scheduler;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// Source of the current time, so playback can run against a virtual clock
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// Wall-clock time, used by the real player
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Time that only moves when told to, for simulations
+pub struct VirtualClock {
+    start: Instant,
+    now: Cell<Instant>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        let start = Instant::now();
+        Self { start, now: Cell::new(start) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+
+    pub fn advance_to(&self, time: Instant) {
+        self.now.set(self.now.get().max(time));
+    }
+
+    /// Virtual time since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        self.now.get() - self.start
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+/// Input to the playback engine besides the passing of time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaybackEvent {
+    /// Something other than the frame changed (opacity, for example); show the current frame again
+    Redraw,
+    /// Hold the current frame
+    Pause,
+    Resume,
+    /// Play at this multiple of the frame delays the buffer was built with
+    SetSpeed(f32),
+    CloseRequested,
+}
+
+/// What the window should do in response
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Paint(usize),
+    Exit,
+}
+
+/// Playback state without any windowing: which frame is up and when the next one is due.
+/// The caller feeds it time and events and carries out
+/// the returned actions.
+pub struct Playback<C: Clock> {
+    frames: FrameBuffer,
+    scheduler: Scheduler,
+    clock: C,
+    skipped: usize,
+    speed: f32,
+}
+
+impl<C: Clock> Playback<C> {
+    pub fn new(frames: FrameBuffer, clock: C) -> Self {
+        Self {
+            frames,
+            scheduler: Scheduler::new(),
+            clock,
+            skipped: 0,
+            speed: 1.0,
+        }
+    }
+
+    pub fn frames(&self) -> &FrameBuffer {
+        &self.frames
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Catch up with the clock. Returns the frame to paint if it changed.
+    pub fn update(&mut self) -> Option<Action> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let frames = &mut self.frames;
+        let speed = self.speed;
+        let mut steps = 0usize;
+        let advanced = self.scheduler.tick(self.clock.now(), || {
+            steps += 1;
+            frames.next().delay.div_f64(speed as f64)
+        });
+        self.skipped += steps.saturating_sub(1);
+        advanced.then(|| Action::Paint(self.frames.current_index()))
+    }
+
+    pub fn handle(&mut self, event: PlaybackEvent) -> Option<Action> {
+        match event {
+            PlaybackEvent::Redraw => {
+                (!self.frames.is_empty()).then(|| Action::Paint(self.frames.current_index()))
+            }
+            PlaybackEvent::Pause => {
+                self.scheduler.pause(self.clock.now());
+                None
+            }
+            PlaybackEvent::Resume => {
+                self.scheduler.resume(self.clock.now());
+                None
+            }
+            PlaybackEvent::SetSpeed(speed) => {
+                self.speed = speed.clamp(0.01, 100.0);
+                None
+            }
+            PlaybackEvent::CloseRequested => Some(Action::Exit),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.scheduler.is_paused()
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Frames passed over without being painted because playback fell behind
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// When `update` next needs to be called
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.scheduler.next_deadline()
+    }
+}
+
+/// Outcome of a headless playback run
+pub struct Simulation {
+    /// Virtual time of each paint and the frame painted
+    pub paints: Vec<(Duration, usize)>,
+    pub skipped: usize,
+}
+
+/// Play `frames` against a virtual clock for `duration` without opening a window.
+/// By default the clock jumps straight to each deadline, like the real player waking up
+/// on time; with `step` it moves in fixed increments instead, like a vsync-driven loop.
+pub fn simulate(frames: FrameBuffer, duration: Duration, step: Option<Duration>) -> Simulation {
+    let mut playback = Playback::new(frames, VirtualClock::new());
+    let mut paints = Vec::new();
+
+    while playback.clock().elapsed() < duration {
+        if let Some(Action::Paint(index)) = playback.update() {
+            paints.push((playback.clock().elapsed(), index));
+        }
+        match (step, playback.next_deadline()) {
+            (Some(step), _) => playback.clock().advance(step),
+            (None, Some(deadline)) => playback.clock().advance_to(deadline),
+            (None, None) => break,
+        }
+    }
+
+    Simulation {
+        paints,
+        skipped: playback.skipped(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::sequence::PlaybackMode;
+    use crate::types::Frame;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn frames(delays: &[u64]) -> FrameBuffer {
+        FrameBuffer::new(
+            delays
+                .iter()
+                .map(|&delay| Frame { buffer: Vec::new(), width: 1, height: 1, delay: ms(delay) })
+                .collect(),
+        )
+    }
+
+    fn due_in(playback: &Playback<VirtualClock>) -> Duration {
+        playback.next_deadline().unwrap() - playback.clock().now()
+    }
+
+    #[test]
+    fn paints_each_frame_at_its_deadline() {
+        let result = simulate(frames(&[100, 200, 50]), ms(700), None);
+        assert_eq!(
+            result.paints,
+            [(ms(0), 0), (ms(100), 1), (ms(300), 2), (ms(350), 0), (ms(450), 1), (ms(650), 2)]
+        );
+        assert_eq!(result.skipped, 0);
+    }
+
+    #[test]
+    fn long_runs_do_not_drift() {
+        let result = simulate(frames(&[30, 70]), ms(100_000), None);
+        assert_eq!(result.paints.len(), 2000);
+        assert!(result.paints.iter().all(|(time, _)| time.subsec_nanos() % 1_000_000 == 0));
+    }
+
+    #[test]
+    fn paints_follow_the_playback_mode() {
+        let mut buffer = frames(&[10, 10, 10]);
+        buffer.set_mode(PlaybackMode::PingPong);
+        let order: Vec<usize> = simulate(buffer, ms(60), None).paints.iter().map(|p| p.1).collect();
+        assert_eq!(order, [0, 1, 2, 1, 0, 1]);
+    }
+
+    #[test]
+    fn coarse_steps_skip_and_count_frames() {
+        let result = simulate(frames(&[10, 10, 10, 10]), ms(100), Some(ms(25)));
+        assert_eq!(result.paints, [(ms(0), 0), (ms(25), 2), (ms(50), 1), (ms(75), 3)]);
+        assert_eq!(result.skipped, 4);
+    }
+
+    #[test]
+    fn clock_jumps_past_the_catch_up_limit_restart_timing() {
+        let mut playback = Playback::new(frames(&[100, 100, 100]), VirtualClock::new());
+        assert_eq!(playback.update(), Some(Action::Paint(0)));
+
+        playback.clock().advance(ms(2000));
+        assert_eq!(playback.update(), Some(Action::Paint(1)));
+        assert_eq!(playback.skipped(), 0);
+        assert_eq!(due_in(&playback), ms(100));
+    }
+
+    #[test]
+    fn pause_holds_the_frame_and_resume_keeps_its_remaining_time() {
+        let mut playback = Playback::new(frames(&[100, 100]), VirtualClock::new());
+        playback.update();
+
+        playback.clock().advance(ms(30));
+        assert_eq!(playback.handle(PlaybackEvent::Pause), None);
+        assert!(playback.is_paused());
+        playback.clock().advance(ms(1000));
+        assert_eq!(playback.update(), None);
+
+        playback.handle(PlaybackEvent::Resume);
+        assert!(!playback.is_paused());
+        assert_eq!(due_in(&playback), ms(70));
+        playback.clock().advance(ms(70));
+        assert_eq!(playback.update(), Some(Action::Paint(1)));
+        assert_eq!(playback.skipped(), 0);
+    }
+
+    #[test]
+    fn speed_scales_the_next_delays() {
+        let mut playback = Playback::new(frames(&[100, 100]), VirtualClock::new());
+        playback.handle(PlaybackEvent::SetSpeed(2.0));
+        playback.update();
+        assert_eq!(due_in(&playback), ms(50));
+    }
+
+    #[test]
+    fn redraw_repaints_and_close_exits() {
+        let mut playback = Playback::new(frames(&[100, 100]), VirtualClock::new());
+        playback.update();
+        assert_eq!(playback.handle(PlaybackEvent::Redraw), Some(Action::Paint(0)));
+        assert_eq!(playback.handle(PlaybackEvent::CloseRequested), Some(Action::Exit));
+    }
+}
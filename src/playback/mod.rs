@@ -1 +1,5 @@
+pub mod compositor;
+pub mod engine;
 pub mod player;
+pub mod scheduler;
+pub mod sequence;
@@ -1,149 +1,463 @@
+use crate::app::store::{GifConfig, OPACITY_RANGE};
+use crate::cache::decode_cache::DecodeCache;
 use crate::cache::frame_buffer::FrameBuffer;
 use crate::renderer::painter::Painter;
 use crate::renderer::window::create_window;
 use crate::app::process::ProcessStore;
+use crate::control::{self, Command, PlayerStatus, Request, Response};
+use crate::control::server::Server;
+use crate::processing::Padding;
+use crate::playback::engine::{Action, Playback, PlaybackEvent, SystemClock};
 use softbuffer::Context;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
-use winit::event::{Event, WindowEvent, ElementState, MouseButton};
-use winit::event_loop::{ControlFlow, EventLoop};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{Event, WindowEvent, ElementState, MouseButton, MouseScrollDelta};
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget};
+use winit::window::{Window, WindowId};
 
 use crate::platform;
 
-pub fn play(
-    mut frames: FrameBuffer,
-    width: u32,
-    height: u32,
-    overlay: bool,
-    click_through: bool,
+// How often the global Ctrl state is checked for temporarily disabling click-through
+const CTRL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Window placement and presentation settings for a player
+pub struct PlayerOptions {
+    pub overlay: bool,
+    pub click_through: bool,
+    pub position: Option<(i32, i32)>,
+    pub align: String,
+    pub monitor_id: usize,
+    pub opacity: f32,
+    /// Margin that effects added around the artwork; alignment applies to the artwork itself
+    pub padding: Padding,
+}
+
+/// Top-left corner of a `size` window, relative to its monitor, for an alignment.
+/// Aligns the visible artwork rather than the padded window, then shifts the
+/// window back by the padding so effects spill outside the aligned box.
+pub fn place(align: &str, position: Option<(i32, i32)>, padding: Padding, size: (u32, u32), monitor_size: (u32, u32)) -> (i32, i32) {
+    // We use i32 for calculations
+    let w = (size.0 - padding.left - padding.right) as i32;
+    let h = (size.1 - padding.top - padding.bottom) as i32;
+    let mw = monitor_size.0 as i32;
+    let mh = monitor_size.1 as i32;
+
+    let x = match align {
+        "top-left" | "bottom-left" => 0,
+        "top-right" | "bottom-right" => mw - w,
+        "center" => (mw - w) / 2,
+        "custom" => position.map(|p| p.0).unwrap_or(0),
+        _ => 0,
+    };
+
+    let y = match align {
+        "top-left" | "top-right" => 0,
+        "bottom-left" | "bottom-right" => mh - h,
+        "center" => (mh - h) / 2,
+        "custom" => position.map(|p| p.1).unwrap_or(0),
+        _ => 0,
+    };
+
+    (x - padding.left as i32, y - padding.top as i32)
+}
+
+// Desktop position of a `size` window for an alignment on a monitor (the primary one if
+// `monitor_id` doesn't exist)
+fn window_position(
+    elwt: &EventLoopWindowTarget<()>,
+    align: &str,
     position: Option<(i32, i32)>,
-    align: String,
     monitor_id: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let event_loop = EventLoop::new()?;
-    
-    // Calculate position based on monitor and alignment
-    let monitor = event_loop.available_monitors().nth(monitor_id)
-        .or_else(|| event_loop.primary_monitor());
-
-    let final_position = if let Some(monitor) = monitor {
+    padding: Padding,
+    size: (u32, u32),
+) -> Option<(i32, i32)> {
+    let monitor = elwt.available_monitors().nth(monitor_id).or_else(|| elwt.primary_monitor());
+    if let Some(monitor) = monitor {
         let m_pos = monitor.position();
-        let m_size = monitor.size();
-        
-        // Ensure window fits or at least starts correctly
-        // We use i32 for calculations
-        let w = width as i32;
-        let h = height as i32;
-        let mw = m_size.width as i32;
-        let mh = m_size.height as i32;
-        
-        let x = match align.as_str() {
-            "top-left" | "bottom-left" => m_pos.x,
-            "top-right" | "bottom-right" => m_pos.x + (mw - w),
-            "center" => m_pos.x + (mw - w) / 2,
-            "custom" => m_pos.x + position.map(|p| p.0).unwrap_or(0),
-            _ => m_pos.x,
-        };
-        
-        let y = match align.as_str() {
-            "top-left" | "top-right" => m_pos.y,
-            "bottom-left" | "bottom-right" => m_pos.y + (mh - h),
-            "center" => m_pos.y + (mh - h) / 2,
-            "custom" => m_pos.y + position.map(|p| p.1).unwrap_or(0),
-            _ => m_pos.y,
-        };
-        
-        Some((x, y))
+        let (x, y) = place(align, position, padding, size, (monitor.size().width, monitor.size().height));
+        Some((m_pos.x + x, m_pos.y + y))
     } else {
         position
-    };
+    }
+}
 
-    let window = Rc::new(create_window(&event_loop, width, height, final_position)?);
+// Speed already applied to a buffer's delays by `apply_timing`; a fixed FPS replaces it
+fn built_speed(config: &GifConfig) -> f32 {
+    if config.fps.is_some() { 1.0 } else { config.speed.clamp(0.1, 10.0) }
+}
 
-    if overlay {
-        platform::set_overlay(&window, click_through);
-    }
+/// Decode, process and time a library animation exactly as `play` would from its CLI flags
+pub fn load_config(config: &GifConfig, cache: &mut DecodeCache) -> Result<(FrameBuffer, u32, u32, Padding), Box<dyn std::error::Error>> {
+    let (info, frames, padding) = cache.load(&config.path, &config.process_options())?;
+    let mut buffer = FrameBuffer::new(frames);
+    apply_timing(&mut buffer, config);
+    Ok((buffer, info.width as u32, info.height as u32, padding))
+}
 
-    let context = Context::new(window.clone())?;
-    let mut painter = Painter::new(&context, window.clone())?;
+/// Apply an animation's frame range, order, speed and interpolation settings
+pub fn apply_timing(buffer: &mut FrameBuffer, config: &GifConfig) {
+    buffer.set_range(config.frame_range);
+    buffer.set_mode(config.mode);
+    buffer.set_timing(config.fps, config.speed);
+    if let Some(interpolation) = &config.interpolation {
+        buffer.interpolate(interpolation);
+    }
+}
 
-    let mut last_frame_time = Instant::now();
-    let mut current_delay = Duration::from_millis(0);
+/// One animation window with its own playback and interaction state
+struct PlayerWindow {
+    config: GifConfig, // Settings in effect, kept up to date by control commands
+    built_speed: f32,
+    window: Rc<Window>,
+    painter: Painter,
+    playback: Playback<SystemClock>,
+    click_through: bool,
 
     // State for manual dragging to avoid blocking the loop
-    let mut is_dragging = false;
-    let mut drag_start_mouse = (0.0, 0.0);
-    let mut ctrl_was_pressed = false;
+    is_dragging: bool,
+    drag_start_mouse: (f64, f64),
+    ctrl_was_pressed: bool,
+    ctrl_modifier: bool,
+}
 
-    println!("Starting event loop...");
-    event_loop.run(move |event, elwt| {
-        elwt.set_control_flow(ControlFlow::Poll);
-
-        // When click-through is enabled, poll for Ctrl key state globally in every frame
-        // This allows us to temporarily disable click-through when Ctrl is held
-        if click_through {
-            let ctrl_pressed = platform::is_ctrl_pressed();
-            
-            // Only toggle click-through when Ctrl state changes to avoid flickering
-            if ctrl_pressed != ctrl_was_pressed {
-                platform::set_click_through(&window, !ctrl_pressed);
-                ctrl_was_pressed = ctrl_pressed;
+impl PlayerWindow {
+    fn new(
+        event_loop: &EventLoop<()>,
+        config: GifConfig,
+        mut frames: FrameBuffer,
+        width: u32,
+        height: u32,
+        options: PlayerOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let PlayerOptions { overlay, click_through, position, align, monitor_id, opacity, padding } = options;
+
+        // Calculate position based on monitor and alignment
+        let final_position = window_position(event_loop, &align, position, monitor_id, padding, (width, height));
+
+        let window = Rc::new(create_window(event_loop, width, height, final_position)?);
+
+        if overlay {
+            platform::set_overlay(&window, click_through);
+        }
+
+        let context = Context::new(window.clone())?;
+        let mut painter = Painter::new(&context, window.clone())?;
+        painter.set_opacity(opacity);
+
+        // Convert once up front so each paint is a copy
+        let start = Instant::now();
+        frames.premultiply();
+        println!("Prepared {} frames in {:.2?}", frames.len(), start.elapsed());
+
+        Ok(Self {
+            built_speed: built_speed(&config),
+            config,
+            window,
+            painter,
+            playback: Playback::new(frames, SystemClock),
+            click_through,
+            is_dragging: false,
+            drag_start_mouse: (0.0, 0.0),
+            ctrl_was_pressed: false,
+            ctrl_modifier: false,
+        })
+    }
+
+    /// Returns the engine's response; `Action::Exit` means this window should close
+    fn handle_window_event(&mut self, event: WindowEvent) -> Option<Action> {
+        match event {
+            WindowEvent::CloseRequested => {
+                return self.playback.handle(PlaybackEvent::CloseRequested);
+            },
+            WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+                // If click-through is enabled, we only get mouse events when Ctrl is held
+                // (because we disable click-through when Ctrl is pressed)
+                // If click-through is disabled, always allow interaction
+                if !self.click_through || self.ctrl_was_pressed {
+                    match state {
+                        ElementState::Pressed => {
+                            self.is_dragging = true;
+                        },
+                        ElementState::Released => {
+                            self.is_dragging = false;
+                        },
+                    }
+                }
+            },
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.ctrl_modifier = modifiers.state().control_key();
+            },
+            // Ctrl + scroll adjusts opacity at runtime without re-decoding
+            WindowEvent::MouseWheel { delta, .. } if self.ctrl_modifier || self.ctrl_was_pressed => {
+                let steps = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::PixelDelta(pos) => (pos.y / 40.0) as f32,
+                };
+                let new_opacity = (self.painter.opacity() + steps * 0.05).clamp(*OPACITY_RANGE.start(), *OPACITY_RANGE.end());
+                self.painter.set_opacity(new_opacity);
+                println!("Opacity set to {:.2}", new_opacity);
+
+                let action = self.playback.handle(PlaybackEvent::Redraw);
+                self.perform(action);
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                if self.is_dragging {
+                    if let Ok(current_pos) = self.window.outer_position() {
+                        let delta_x = position.x - self.drag_start_mouse.0;
+                        let delta_y = position.y - self.drag_start_mouse.1;
+
+                        let new_pos = winit::dpi::PhysicalPosition::new(
+                            current_pos.x + delta_x as i32,
+                            current_pos.y + delta_y as i32,
+                        );
+                        self.window.set_outer_position(new_pos);
+                    }
+                } else {
+                    self.drag_start_mouse = (position.x, position.y);
+                }
+            },
+            _ => ()
+        }
+        None
+    }
+
+    // Temporarily disable click-through while Ctrl is held
+    fn poll_ctrl(&mut self) {
+        if !self.click_through {
+            return;
+        }
+        let ctrl_pressed = platform::is_ctrl_pressed();
+
+        // Only toggle click-through when Ctrl state changes to avoid flickering
+        if ctrl_pressed != self.ctrl_was_pressed {
+            platform::set_click_through(&self.window, !ctrl_pressed);
+            self.ctrl_was_pressed = ctrl_pressed;
+        }
+    }
+
+    fn status(&self) -> PlayerStatus {
+        PlayerStatus {
+            name: self.config.name.clone(),
+            paused: self.playback.is_paused(),
+            speed: self.built_speed * self.playback.speed(),
+            opacity: self.painter.opacity(),
+            frame: self.playback.frames().current_index(),
+            frames: self.playback.frames().len(),
+            position: self.window.outer_position().ok().map(|p| (p.x, p.y)),
+        }
+    }
+
+    // Carry out a control command aimed at this window (`quit` and `status` are handled by the caller)
+    fn control(&mut self, elwt: &EventLoopWindowTarget<()>, command: Command) -> Result<(), String> {
+        match command {
+            Command::Pause => {
+                self.playback.handle(PlaybackEvent::Pause);
+            }
+            Command::Resume => {
+                self.playback.handle(PlaybackEvent::Resume);
+            }
+            Command::SetSpeed { speed } => {
+                if !(0.1..=10.0).contains(&speed) {
+                    return Err("Speed must be between 0.1 and 10".to_string());
+                }
+                self.config.speed = speed;
+                self.playback.handle(PlaybackEvent::SetSpeed(speed / self.built_speed));
             }
+            Command::SetOpacity { opacity } => {
+                if !OPACITY_RANGE.contains(&opacity) {
+                    return Err(format!("Opacity must be between {} and {}", OPACITY_RANGE.start(), OPACITY_RANGE.end()));
+                }
+                self.config.opacity = opacity;
+                self.painter.set_opacity(opacity);
+                let action = self.playback.handle(PlaybackEvent::Redraw);
+                self.perform(action);
+            }
+            Command::Move { x, y } => {
+                self.window.set_outer_position(PhysicalPosition::new(x, y));
+            }
+            Command::Reload { config } => {
+                let config = config.map(|config| *config).unwrap_or_else(|| self.config.clone());
+                self.reload(elwt, config).map_err(|e| e.to_string())?;
+            }
+            Command::Quit | Command::Status => {}
+        }
+        Ok(())
+    }
+
+    // Decode and process the file again with `config`, keeping the window and pause state.
+    // Overlay can only be turned on here; turning it off needs a new window.
+    fn reload(&mut self, elwt: &EventLoopWindowTarget<()>, config: GifConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let (mut frames, width, height, padding) = load_config(&config, &mut DecodeCache::new())?;
+        frames.premultiply();
+
+        let paused = self.playback.is_paused();
+        self.playback = Playback::new(frames, SystemClock);
+        self.built_speed = built_speed(&config);
+
+        let _ = self.window.request_inner_size(PhysicalSize::new(width, height));
+        if let Some((x, y)) = window_position(elwt, &config.align, config.position, config.monitor, padding, (width, height)) {
+            self.window.set_outer_position(PhysicalPosition::new(x, y));
+        }
+        if config.overlay {
+            platform::set_overlay(&self.window, self.click_through);
+        }
+        self.painter.set_opacity(config.opacity);
+        println!("Reloaded {:?}: {} frames", config.path, self.playback.frames().len());
+        self.config = config;
+
+        // Show the new first frame even while paused
+        let action = self.playback.handle(PlaybackEvent::Redraw);
+        self.perform(action);
+        if paused {
+            self.playback.handle(PlaybackEvent::Pause);
+        }
+        Ok(())
+    }
+
+    fn update(&mut self) {
+        let action = self.playback.update();
+        self.perform(action);
+    }
+
+    // Carry out a paint the playback engine asked for
+    fn perform(&mut self, action: Option<Action>) {
+        if let Some(Action::Paint(index)) = action {
+            let frames = self.playback.frames();
+            let frame = frames.get(index);
+            if let Err(e) = self.painter.paint(frames.pixels(index), frame.width, frame.height, frames.content_hash(index)) {
+                eprintln!("Paint error: {}", e);
+            }
+        }
+    }
+}
+
+pub fn play(
+    config: GifConfig,
+    frames: FrameBuffer,
+    width: u32,
+    height: u32,
+    options: PlayerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    play_many(vec![(config, frames, width, height, options)])
+}
+
+// Close a window; the process exits with its last one
+fn close(windows: &mut Vec<PlayerWindow>, index: usize, elwt: &EventLoopWindowTarget<()>) {
+    let closed = windows.remove(index);
+    let mut ps = ProcessStore::load();
+    if windows.is_empty() {
+        println!("Close requested, exiting...");
+        ps.remove_self();
+        elwt.exit();
+    } else {
+        println!("Closed {}", closed.config.name);
+        ps.remove_hosted(std::process::id(), &closed.config.name);
+    }
+}
+
+// Answer a request from the control endpoint
+fn handle_request(windows: &mut Vec<PlayerWindow>, request: &Request, elwt: &EventLoopWindowTarget<()>) -> Response {
+    let targets: Vec<usize> = (0..windows.len())
+        .filter(|&i| request.target.as_ref().is_none_or(|name| *name == windows[i].config.name))
+        .collect();
+    if targets.is_empty() {
+        return Response::error(format!("No animation named '{}' in this player", request.target.clone().unwrap_or_default()));
+    }
+
+    match &request.command {
+        Command::Status => Response::ok(targets.iter().map(|&i| windows[i].status()).collect()),
+        Command::Quit => {
+            for &i in targets.iter().rev() {
+                close(windows, i, elwt);
+            }
+            Response::ok(Vec::new())
         }
+        command => {
+            for &i in &targets {
+                if let Err(e) = windows[i].control(elwt, command.clone()) {
+                    return Response::error(format!("{}: {}", windows[i].config.name, e));
+                }
+            }
+            Response::ok(Vec::new())
+        }
+    }
+}
 
+/// Play several animations from one process, each in its own window, sharing one event loop
+/// and timer. Closing a window stops only that animation; the process exits with the last one.
+/// The process also listens for control requests (see `control`) while it runs.
+pub fn play_many(
+    players: Vec<(GifConfig, FrameBuffer, u32, u32, PlayerOptions)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let event_loop = EventLoop::new()?;
+
+    let mut windows = Vec::new();
+    for (config, frames, width, height, options) in players {
+        windows.push(PlayerWindow::new(&event_loop, config, frames, width, height, options)?);
+    }
+    let find = |windows: &[PlayerWindow], id: WindowId| windows.iter().position(|w| w.window.id() == id);
+
+    // Requests wake the loop with a user event, since it may be asleep until the next frame
+    let proxy = event_loop.create_proxy();
+    let mut server = match Server::start(move || proxy.send_event(()).is_ok()) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            eprintln!("Control endpoint unavailable (protocol {}): {}", control::PROTOCOL_VERSION, e);
+            None
+        }
+    };
+
+    let mut next_ctrl_poll = Instant::now();
+
+    println!("Starting event loop...");
+    event_loop.run(move |event, elwt| {
         match event {
-            Event::WindowEvent { event, window_id } if window_id == window.id() => {
-                match event {
-                    WindowEvent::CloseRequested => {
-                        println!("Close requested, exiting...");
-                        let mut ps = ProcessStore::load();
-                        ps.remove_self();
-                        elwt.exit();
-                    },
-                    WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
-                        // If click-through is enabled, we only get mouse events when Ctrl is held
-                        // (because we disable click-through when Ctrl is pressed)
-                        // If click-through is disabled, always allow interaction
-                        if !click_through || ctrl_was_pressed {
-                            match state {
-                                ElementState::Pressed => {
-                                    is_dragging = true;
-                                },
-                                ElementState::Released => {
-                                    is_dragging = false;
-                                },
-                            }
-                        }
-                    },
-                    WindowEvent::CursorMoved { position, .. } => {
-                        if is_dragging {
-                            if let Ok(current_pos) = window.outer_position() {
-                                let delta_x = position.x - drag_start_mouse.0;
-                                let delta_y = position.y - drag_start_mouse.1;
-                                
-                                let new_pos = winit::dpi::PhysicalPosition::new(
-                                    current_pos.x + delta_x as i32,
-                                    current_pos.y + delta_y as i32,
-                                );
-                                window.set_outer_position(new_pos);
-                            }
-                        } else {
-                            drag_start_mouse = (position.x, position.y);
-                        }
-                    },
-                    _ => ()
+            Event::WindowEvent { event, window_id } => {
+                let Some(index) = find(&windows, window_id) else { return };
+                if windows[index].handle_window_event(event) == Some(Action::Exit) {
+                    close(&mut windows, index, elwt);
+                }
+            }
+            Event::UserEvent(()) => {
+                while let Some(pending) = server.as_ref().and_then(Server::try_recv) {
+                    let response = handle_request(&mut windows, &pending.request, elwt);
+                    pending.reply(response);
                 }
             }
             Event::AboutToWait => {
-                if last_frame_time.elapsed() >= current_delay {
-                    let frame = frames.next();
-                    current_delay = frame.delay;
-                    last_frame_time = Instant::now();
-
-                    if let Err(e) = painter.paint(frame) {
-                        eprintln!("Paint error: {}", e);
+                if windows.is_empty() {
+                    // Stop listening (and remove the endpoint) on the way out
+                    server = None;
+                    return;
+                }
+                let now = Instant::now();
+                // When click-through is enabled, poll the global Ctrl key state on a slow timer
+                // This allows us to temporarily disable click-through when Ctrl is held
+                let click_through = windows.iter().any(|w| w.click_through);
+                if click_through && now >= next_ctrl_poll {
+                    for window in &mut windows {
+                        window.poll_ctrl();
                     }
+                    next_ctrl_poll = now + CTRL_POLL_INTERVAL;
                 }
+
+                for window in &mut windows {
+                    window.update();
+                }
+
+                // Sleep until the next frame (or Ctrl poll) is due instead of spinning
+                let deadline = windows.iter().filter_map(|w| w.playback.next_deadline()).min();
+                let wake = match deadline {
+                    Some(deadline) if click_through => Some(deadline.min(next_ctrl_poll)),
+                    Some(deadline) => Some(deadline),
+                    None if click_through => Some(next_ctrl_poll),
+                    None => None,
+                };
+                elwt.set_control_flow(match wake {
+                    Some(deadline) => ControlFlow::WaitUntil(deadline),
+                    None => ControlFlow::Wait,
+                });
             }
             _ => (),
         }
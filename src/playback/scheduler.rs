@@ -0,0 +1,164 @@
+use std::time::{Duration, Instant};
+
+/// How far playback may fall behind before it stops skipping frames to catch up and
+/// simply restarts timing from now (after a system sleep or a long stall, for example)
+pub const CATCH_UP_LIMIT: Duration = Duration::from_millis(500);
+
+// Zero-delay frames would never move the deadline forward
+const MIN_DELAY: Duration = Duration::from_millis(1);
+
+/// Decides when to advance to the next frame. Deadlines are accumulated from the frame
+/// delays rather than from the time a frame was actually shown, so playback doesn't drift.
+/// Shared by the player and the GUI preview so both keep the same timing.
+pub struct Scheduler {
+    deadline: Option<Instant>, // When the frame on screen should be replaced
+    paused: Option<Option<Duration>>, // While paused, how much of the current frame was left
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self { deadline: None, paused: None }
+    }
+
+    /// Hold the current frame until `resume`
+    pub fn pause(&mut self, now: Instant) {
+        if self.paused.is_none() {
+            self.paused = Some(self.deadline.take().map(|deadline| deadline.saturating_duration_since(now)));
+        }
+    }
+
+    /// Continue with whatever was left of the frame shown when paused
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(remaining) = self.paused.take() {
+            self.deadline = remaining.map(|remaining| now + remaining);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Forget the current deadline; the next `tick` shows a frame immediately
+    pub fn reset(&mut self) {
+        self.deadline = None;
+    }
+
+    /// When the next frame is due, if one has been shown yet
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Advance playback to `now`. `advance` moves to the next frame and returns its delay.
+    /// Frames whose whole slot has already passed are skipped, so this may call `advance`
+    /// several times. Returns true if the frame on screen changed.
+    pub fn tick(&mut self, now: Instant, mut advance: impl FnMut() -> Duration) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+        let deadline = match self.deadline {
+            Some(deadline) if now < deadline => return false,
+            // Too far behind to be worth catching up: continue from here
+            Some(deadline) if now - deadline <= CATCH_UP_LIMIT => deadline,
+            _ => now,
+        };
+
+        let mut deadline = deadline + advance().max(MIN_DELAY);
+        while deadline <= now {
+            deadline += advance().max(MIN_DELAY);
+        }
+        self.deadline = Some(deadline);
+        true
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(100);
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    // Ticks at `now`, returning how many frames were advanced
+    fn tick(scheduler: &mut Scheduler, now: Instant) -> usize {
+        let mut steps = 0;
+        scheduler.tick(now, || {
+            steps += 1;
+            DELAY
+        });
+        steps
+    }
+
+    #[test]
+    fn first_tick_shows_a_frame_and_waits_for_its_delay() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        assert_eq!(tick(&mut scheduler, start), 1);
+        assert_eq!(scheduler.next_deadline(), Some(start + DELAY));
+        assert_eq!(tick(&mut scheduler, start + ms(99)), 0);
+        assert_eq!(tick(&mut scheduler, start + ms(100)), 1);
+        assert_eq!(scheduler.next_deadline(), Some(start + ms(200)));
+    }
+
+    #[test]
+    fn late_ticks_skip_frames_without_drifting() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        tick(&mut scheduler, start);
+
+        // Slots ending at 200 and 300 have passed; the frame due at 300 stays until 400
+        assert_eq!(tick(&mut scheduler, start + ms(350)), 3);
+        assert_eq!(scheduler.next_deadline(), Some(start + ms(400)));
+    }
+
+    #[test]
+    fn falling_too_far_behind_restarts_timing() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        tick(&mut scheduler, start);
+
+        // Exactly at the limit still catches up
+        assert_eq!(tick(&mut scheduler, start + DELAY + CATCH_UP_LIMIT), 6);
+        assert_eq!(scheduler.next_deadline(), Some(start + ms(700)));
+
+        // Past it, one frame is shown and timing continues from now
+        let late = start + ms(700) + CATCH_UP_LIMIT + ms(1);
+        assert_eq!(tick(&mut scheduler, late), 1);
+        assert_eq!(scheduler.next_deadline(), Some(late + DELAY));
+    }
+
+    #[test]
+    fn pause_keeps_the_rest_of_the_frame() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        tick(&mut scheduler, start);
+
+        scheduler.pause(start + ms(40));
+        assert!(scheduler.is_paused());
+        assert_eq!(tick(&mut scheduler, start + ms(5000)), 0);
+
+        scheduler.resume(start + ms(5000));
+        assert!(!scheduler.is_paused());
+        assert_eq!(scheduler.next_deadline(), Some(start + ms(5060)));
+        assert_eq!(tick(&mut scheduler, start + ms(5059)), 0);
+        assert_eq!(tick(&mut scheduler, start + ms(5060)), 1);
+    }
+
+    #[test]
+    fn pause_before_the_first_frame() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.pause(start);
+        assert_eq!(tick(&mut scheduler, start), 0);
+        scheduler.resume(start + ms(10));
+        assert_eq!(tick(&mut scheduler, start + ms(10)), 1);
+    }
+}
@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Order in which frames are shown
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlaybackMode {
+    #[default]
+    Forward,
+    Reverse,
+    /// Forward then backward (boomerang), without repeating the end frames
+    PingPong,
+    /// Any frame at each step, for glitch-style effects
+    Random,
+    /// Every frame once per cycle, in a new random order each cycle
+    Shuffle,
+}
+
+impl PlaybackMode {
+    pub const ALL: [PlaybackMode; 5] = [
+        PlaybackMode::Forward,
+        PlaybackMode::Reverse,
+        PlaybackMode::PingPong,
+        PlaybackMode::Random,
+        PlaybackMode::Shuffle,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlaybackMode::Forward => "forward",
+            PlaybackMode::Reverse => "reverse",
+            PlaybackMode::PingPong => "ping-pong",
+            PlaybackMode::Random => "random",
+            PlaybackMode::Shuffle => "shuffle",
+        }
+    }
+
+    /// Whether frames are shown next to their neighbours in the file, which in-between
+    /// frames rely on; tweens in a random order would only flicker
+    pub fn can_interpolate(&self) -> bool {
+        matches!(self, PlaybackMode::Forward | PlaybackMode::Reverse | PlaybackMode::PingPong)
+    }
+}
+
+impl FromStr for PlaybackMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PlaybackMode::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .ok_or_else(|| format!("Unknown playback mode '{}' (forward, reverse, ping-pong, random, shuffle)", s))
+    }
+}
+
+impl fmt::Display for PlaybackMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Half-open range of frame indices to loop over
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FrameRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// CLI form: `start..end`
+impl FromStr for FrameRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid frame range '{}', expected start..end", s);
+        let (start, end) = s.split_once("..").ok_or_else(invalid)?;
+        let start: usize = start.trim().parse().map_err(|_| invalid())?;
+        let end: usize = end.trim().parse().map_err(|_| invalid())?;
+        if start >= end {
+            return Err(invalid());
+        }
+        Ok(FrameRange { start, end })
+    }
+}
+
+impl fmt::Display for FrameRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// Works out which frame index comes next for a playback mode and range.
+/// Holds no frames itself, so the player and the GUI preview can share it.
+pub struct FrameSequence {
+    len: usize,
+    mode: PlaybackMode,
+    start: usize,
+    end: usize,
+    current: usize,
+    started: bool,
+    forward: bool,       // Ping-pong direction
+    order: Vec<usize>,   // Shuffle order for the current cycle
+    order_pos: usize,
+    rng: u64,
+}
+
+impl FrameSequence {
+    pub fn new(len: usize) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+
+        let mut sequence = Self {
+            len,
+            mode: PlaybackMode::Forward,
+            start: 0,
+            end: len,
+            current: 0,
+            started: false,
+            forward: true,
+            order: Vec::new(),
+            order_pos: 0,
+            rng: seed | 1,
+        };
+        sequence.reset();
+        sequence
+    }
+
+    pub fn mode(&self) -> PlaybackMode {
+        self.mode
+    }
+
+    /// The active range, after clamping to the frame count
+    pub fn range(&self) -> FrameRange {
+        FrameRange { start: self.start, end: self.end }
+    }
+
+    pub fn set_mode(&mut self, mode: PlaybackMode) {
+        self.mode = mode;
+        self.reset();
+    }
+
+    /// Restrict playback to a range; out-of-bounds or empty ranges fall back to all frames
+    pub fn set_range(&mut self, range: Option<FrameRange>) {
+        let end = range.map(|r| r.end.min(self.len)).unwrap_or(self.len);
+        let start = range.map(|r| r.start).unwrap_or(0);
+        (self.start, self.end) = if start < end { (start, end) } else { (0, self.len) };
+        self.reset();
+    }
+
+    /// Start over from the first frame of the sequence
+    pub fn reset(&mut self) {
+        self.started = false;
+        self.forward = self.mode != PlaybackMode::Reverse;
+        self.current = match self.mode {
+            PlaybackMode::Reverse => self.end.saturating_sub(1),
+            PlaybackMode::Random => self.random_index(),
+            PlaybackMode::Shuffle => {
+                self.shuffle();
+                self.order.first().copied().unwrap_or(self.start)
+            }
+            PlaybackMode::Forward | PlaybackMode::PingPong => self.start,
+        };
+        self.order_pos = 0;
+    }
+
+    /// Number of frames shown before the sequence starts repeating
+    /// (for random and shuffle, one pass worth of frames)
+    pub fn cycle_len(&self) -> usize {
+        let len = self.end - self.start;
+        match self.mode {
+            PlaybackMode::PingPong if len > 1 => 2 * (len - 1),
+            _ => len,
+        }
+    }
+
+    /// Index of the frame most recently returned by `advance`
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Move to the next frame and return its index. The first call returns the first frame.
+    pub fn advance(&mut self) -> usize {
+        if !self.started || self.end - self.start <= 1 {
+            self.started = true;
+            return self.current;
+        }
+
+        self.current = match self.mode {
+            PlaybackMode::Forward => {
+                if self.current + 1 >= self.end { self.start } else { self.current + 1 }
+            }
+            PlaybackMode::Reverse => {
+                if self.current <= self.start { self.end - 1 } else { self.current - 1 }
+            }
+            PlaybackMode::PingPong => {
+                if self.forward && self.current + 1 >= self.end {
+                    self.forward = false;
+                } else if !self.forward && self.current <= self.start {
+                    self.forward = true;
+                }
+                if self.forward { self.current + 1 } else { self.current - 1 }
+            }
+            PlaybackMode::Random => {
+                // Never show the same frame twice in a row
+                let offset = 1 + self.next_random() as usize % (self.end - self.start - 1);
+                self.start + (self.current - self.start + offset) % (self.end - self.start)
+            }
+            PlaybackMode::Shuffle => {
+                self.order_pos += 1;
+                if self.order_pos >= self.order.len() {
+                    self.shuffle();
+                    self.order_pos = 0;
+                    // Don't repeat the last frame of the previous cycle
+                    if self.order[0] == self.current {
+                        let last = self.order.len() - 1;
+                        self.order.swap(0, last);
+                    }
+                }
+                self.order[self.order_pos]
+            }
+        };
+        self.current
+    }
+
+    fn random_index(&mut self) -> usize {
+        if self.end <= self.start {
+            return self.start;
+        }
+        self.start + self.next_random() as usize % (self.end - self.start)
+    }
+
+    // Fisher-Yates over the active range
+    fn shuffle(&mut self) {
+        self.order = (self.start..self.end).collect();
+        for i in (1..self.order.len()).rev() {
+            let j = self.next_random() as usize % (i + 1);
+            self.order.swap(i, j);
+        }
+    }
+
+    // xorshift64*, plenty for picking frames
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(len: usize, mode: PlaybackMode, range: Option<FrameRange>) -> FrameSequence {
+        let mut sequence = FrameSequence::new(len);
+        sequence.set_range(range);
+        sequence.set_mode(mode);
+        sequence
+    }
+
+    fn take(sequence: &mut FrameSequence, steps: usize) -> Vec<usize> {
+        (0..steps).map(|_| sequence.advance()).collect()
+    }
+
+    fn range(start: usize, end: usize) -> Option<FrameRange> {
+        Some(FrameRange { start, end })
+    }
+
+    #[test]
+    fn forward_and_reverse_wrap_around() {
+        assert_eq!(take(&mut sequence(4, PlaybackMode::Forward, None), 6), [0, 1, 2, 3, 0, 1]);
+        assert_eq!(take(&mut sequence(4, PlaybackMode::Reverse, None), 6), [3, 2, 1, 0, 3, 2]);
+    }
+
+    #[test]
+    fn ping_pong_does_not_repeat_end_frames() {
+        let mut s = sequence(4, PlaybackMode::PingPong, None);
+        assert_eq!(s.cycle_len(), 6);
+        assert_eq!(take(&mut s, 9), [0, 1, 2, 3, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn modes_stay_inside_the_range() {
+        assert_eq!(take(&mut sequence(6, PlaybackMode::Forward, range(2, 5)), 5), [2, 3, 4, 2, 3]);
+        assert_eq!(take(&mut sequence(6, PlaybackMode::Reverse, range(2, 5)), 5), [4, 3, 2, 4, 3]);
+        assert_eq!(take(&mut sequence(6, PlaybackMode::PingPong, range(1, 4)), 6), [1, 2, 3, 2, 1, 2]);
+    }
+
+    #[test]
+    fn ranges_are_clamped_or_ignored() {
+        // End past the last frame is clamped, an empty result falls back to every frame
+        assert_eq!(sequence(5, PlaybackMode::Forward, range(3, 10)).range(), FrameRange { start: 3, end: 5 });
+        assert_eq!(sequence(5, PlaybackMode::Forward, range(7, 9)).range(), FrameRange { start: 0, end: 5 });
+        assert_eq!(take(&mut sequence(5, PlaybackMode::PingPong, range(2, 3)), 3), [2, 2, 2]);
+    }
+
+    #[test]
+    fn random_never_repeats_a_frame() {
+        let mut s = sequence(8, PlaybackMode::Random, range(2, 6));
+        let frames = take(&mut s, 500);
+        assert!(frames.iter().all(|f| (2..6).contains(f)));
+        assert!(frames.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn shuffle_shows_each_frame_once_per_cycle() {
+        let mut s = sequence(6, PlaybackMode::Shuffle, range(1, 6));
+        let frames = take(&mut s, 5 * 40);
+        for cycle in frames.chunks(5) {
+            let mut sorted = cycle.to_vec();
+            sorted.sort();
+            assert_eq!(sorted, [1, 2, 3, 4, 5]);
+        }
+        assert!(frames.windows(2).all(|w| w[0] != w[1]));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::cache::mask_cache::{self, MaskKey};
+use crate::processing::chroma::{self, ChromaKey};
+use crate::types::Frame;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::path::Path;
+
+/// How a solid background gets turned into transparency
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "mode", rename_all = "kebab-case")]
+pub enum BackgroundRemoval {
+    /// Clear every pixel close to the key color, wherever it is
+    ChromaKey(ChromaKey),
+    /// Clear only regions connected to the canvas border, keeping interior pixels of the same color
+    EdgeFill(EdgeFill),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EdgeFill {
+    /// Background color; `None` auto-detects it from the corner pixels of the first frame
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+    /// Normalized color distance (0.0-1.0) the fill is allowed to spread across
+    #[serde(default = "default_edge_tolerance")]
+    pub tolerance: f32,
+}
+
+pub fn default_edge_tolerance() -> f32 {
+    0.1
+}
+
+impl Default for EdgeFill {
+    fn default() -> Self {
+        Self {
+            color: None,
+            tolerance: default_edge_tolerance(),
+        }
+    }
+}
+
+impl BackgroundRemoval {
+    pub fn color(&self) -> Option<[u8; 3]> {
+        match self {
+            BackgroundRemoval::ChromaKey(key) => key.color,
+            BackgroundRemoval::EdgeFill(fill) => fill.color,
+        }
+    }
+
+    pub fn color_mut(&mut self) -> &mut Option<[u8; 3]> {
+        match self {
+            BackgroundRemoval::ChromaKey(key) => &mut key.color,
+            BackgroundRemoval::EdgeFill(fill) => &mut fill.color,
+        }
+    }
+
+    pub fn tolerance_mut(&mut self) -> &mut f32 {
+        match self {
+            BackgroundRemoval::ChromaKey(key) => &mut key.tolerance,
+            BackgroundRemoval::EdgeFill(fill) => &mut fill.tolerance,
+        }
+    }
+}
+
+/// `upstream` stands for the settings applied to the frames before this stage; it goes into
+/// the mask cache key along with the source file.
+pub fn apply<S: Hash>(frames: &mut [Frame], removal: &BackgroundRemoval, source: Option<&Path>, upstream: &S) {
+    match removal {
+        BackgroundRemoval::ChromaKey(key) => chroma::apply(frames, key),
+        BackgroundRemoval::EdgeFill(fill) => apply_edge_fill(frames, fill, source, upstream),
+    }
+}
+
+/// Flood-fill each frame from its borders and clear the background-connected pixels.
+/// Masks are cached on disk per source file, so the fill only runs again when the file,
+/// the fill settings or anything applied before it changes.
+fn apply_edge_fill<S: Hash>(frames: &mut [Frame], fill: &EdgeFill, source: Option<&Path>, upstream: &S) {
+    let Some(color) = fill.color.or_else(|| frames.first().and_then(chroma::detect_key_color)) else {
+        return;
+    };
+
+    let key = source.and_then(|path| MaskKey::new(path, &(color, fill.tolerance.to_bits(), upstream)));
+    let cached = key.as_ref().and_then(|k| mask_cache::load(k, frames));
+
+    let masks = match cached {
+        Some(masks) => masks,
+        None => {
+            let masks: Vec<Vec<bool>> = frames.iter().map(|f| edge_mask(f, color, fill.tolerance)).collect();
+            if let Some(Err(e)) = key.as_ref().map(|k| mask_cache::save(k, frames, &masks)) {
+                eprintln!("Failed to cache background masks: {}", e);
+            }
+            masks
+        }
+    };
+
+    for (frame, mask) in frames.iter_mut().zip(&masks) {
+        for (pixel, &is_background) in frame.buffer.chunks_exact_mut(4).zip(mask) {
+            if is_background {
+                pixel[3] = 0;
+            }
+        }
+    }
+}
+
+/// Mark every pixel reachable from the border through background-colored (or already
+/// transparent) pixels, using a 4-connected breadth-first fill
+pub fn edge_mask(frame: &Frame, color: [u8; 3], tolerance: f32) -> Vec<bool> {
+    let (w, h) = (frame.width as usize, frame.height as usize);
+    let mut mask = vec![false; w * h];
+    if w == 0 || h == 0 {
+        return mask;
+    }
+
+    let is_background = |i: usize| {
+        let p = &frame.buffer[i * 4..i * 4 + 4];
+        p[3] == 0 || chroma::color_distance([p[0], p[1], p[2]], color) <= tolerance
+    };
+
+    let mut queue = VecDeque::new();
+    let seed = |i: usize, mask: &mut Vec<bool>, queue: &mut VecDeque<usize>| {
+        if !mask[i] && is_background(i) {
+            mask[i] = true;
+            queue.push_back(i);
+        }
+    };
+
+    for x in 0..w {
+        seed(x, &mut mask, &mut queue);
+        seed((h - 1) * w + x, &mut mask, &mut queue);
+    }
+    for y in 0..h {
+        seed(y * w, &mut mask, &mut queue);
+        seed(y * w + w - 1, &mut mask, &mut queue);
+    }
+
+    while let Some(i) = queue.pop_front() {
+        let (x, y) = (i % w, i / w);
+        if x > 0 { seed(i - 1, &mut mask, &mut queue); }
+        if x + 1 < w { seed(i + 1, &mut mask, &mut queue); }
+        if y > 0 { seed(i - w, &mut mask, &mut queue); }
+        if y + 1 < h { seed(i + w, &mut mask, &mut queue); }
+    }
+
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+    // `rows` of '.' (white background) and '#' (black art)
+    fn frame(rows: &[&str]) -> Frame {
+        let buffer = rows.iter().flat_map(|r| r.chars()).flat_map(|c| if c == '#' { BLACK } else { WHITE }).collect();
+        Frame { buffer, width: rows[0].len() as u32, height: rows.len() as u32, delay: Duration::from_millis(100) }
+    }
+
+    fn alpha(frame: &Frame) -> String {
+        frame.buffer.chunks_exact(4).map(|p| if p[3] == 0 { ' ' } else { 'x' }).collect()
+    }
+
+    #[test]
+    fn edge_fill_keeps_enclosed_background() {
+        let mut frames = vec![frame(&[".....", ".###.", ".#.#.", ".###.", "....."])];
+        apply(&mut frames, &BackgroundRemoval::EdgeFill(EdgeFill::default()), None, &());
+        assert_eq!(alpha(&frames[0]), ["     ", " xxx ", " xxx ", " xxx ", "     "].concat());
+    }
+
+    #[test]
+    fn chroma_key_clears_enclosed_background_too() {
+        let mut frames = vec![frame(&[".....", ".###.", ".#.#.", ".###.", "....."])];
+        apply(&mut frames, &BackgroundRemoval::ChromaKey(ChromaKey::default()), None, &());
+        assert_eq!(alpha(&frames[0]), ["     ", " xxx ", " x x ", " xxx ", "     "].concat());
+    }
+
+    #[test]
+    fn fill_spreads_through_transparent_pixels() {
+        // The right column is already transparent and black, which still counts as background
+        let mut f = frame(&["..#", "#.#"]);
+        f.buffer[2 * 4 + 3] = 0;
+        f.buffer[5 * 4 + 3] = 0;
+        assert_eq!(edge_mask(&f, [255, 255, 255], 0.1), [true, true, true, false, true, true]);
+    }
+
+    #[test]
+    fn empty_frames_have_empty_masks() {
+        let f = Frame { buffer: Vec::new(), width: 0, height: 3, delay: Duration::ZERO };
+        assert!(edge_mask(&f, [0, 0, 0], 0.1).is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::types::Frame;
+use serde::{Deserialize, Serialize};
+
+/// Chroma-key settings for GIFs that ship with a solid background instead of transparency
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChromaKey {
+    /// Key color; `None` auto-detects it from the corner pixels of the first frame
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+    /// Normalized color distance (0.0-1.0) under which pixels become fully transparent
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f32,
+    /// Extra distance beyond the tolerance over which alpha ramps back up, for soft edges
+    #[serde(default = "default_softness")]
+    pub softness: f32,
+}
+
+pub fn default_tolerance() -> f32 {
+    0.1
+}
+
+pub fn default_softness() -> f32 {
+    0.05
+}
+
+impl Default for ChromaKey {
+    fn default() -> Self {
+        Self {
+            color: None,
+            tolerance: default_tolerance(),
+            softness: default_softness(),
+        }
+    }
+}
+
+// Largest possible RGB distance, used to normalize to 0.0-1.0
+const MAX_DISTANCE: f32 = 441.673; // sqrt(3 * 255^2)
+
+pub fn apply(frames: &mut [Frame], key: &ChromaKey) {
+    let Some(color) = key.color.or_else(|| frames.first().and_then(detect_key_color)) else {
+        return;
+    };
+
+    let tolerance = key.tolerance.clamp(0.0, 1.0);
+    let softness = key.softness.clamp(0.0, 1.0);
+
+    for frame in frames.iter_mut() {
+        for pixel in frame.buffer.chunks_exact_mut(4) {
+            if pixel[3] == 0 {
+                continue;
+            }
+
+            let d = color_distance([pixel[0], pixel[1], pixel[2]], color);
+            if d <= tolerance {
+                pixel[3] = 0;
+            } else if d < tolerance + softness {
+                let factor = (d - tolerance) / softness;
+                pixel[3] = (pixel[3] as f32 * factor) as u8;
+            }
+        }
+    }
+}
+
+/// Pick the most common color among the opaque corners of the frame. Transparent corners
+/// are skipped, since their color is meaningless; `None` if every corner is transparent.
+pub fn detect_key_color(frame: &Frame) -> Option<[u8; 3]> {
+    let (w, h) = (frame.width as usize, frame.height as usize);
+    if w == 0 || h == 0 {
+        return None;
+    }
+
+    let corners: Vec<[u8; 3]> = [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)]
+        .iter()
+        .map(|&(x, y)| (y * w + x) * 4)
+        .map(|i| &frame.buffer[i..i + 4])
+        .filter(|p| p[3] != 0)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+
+    corners
+        .iter()
+        .max_by_key(|c| corners.iter().filter(|o| color_distance(**c, **o) < 0.05).count())
+        .copied()
+}
+
+pub fn color_distance(a: [u8; 3], b: [u8; 3]) -> f32 {
+    let dr = a[0] as f32 - b[0] as f32;
+    let dg = a[1] as f32 - b[1] as f32;
+    let db = a[2] as f32 - b[2] as f32;
+    (dr * dr + dg * dg + db * db).sqrt() / MAX_DISTANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn frame(width: u32, height: u32, pixels: &[[u8; 4]]) -> Frame {
+        Frame { buffer: pixels.concat(), width, height, delay: Duration::from_millis(100) }
+    }
+
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn detects_the_most_common_corner() {
+        let f = frame(3, 2, &[GREEN, RED, RED, GREEN, RED, GREEN]);
+        assert_eq!(detect_key_color(&f), Some([0, 255, 0]));
+    }
+
+    #[test]
+    fn transparent_corners_are_ignored() {
+        let f = frame(2, 2, &[CLEAR, CLEAR, CLEAR, GREEN]);
+        assert_eq!(detect_key_color(&f), Some([0, 255, 0]));
+        let f = frame(2, 2, &[CLEAR; 4]);
+        assert_eq!(detect_key_color(&f), None);
+    }
+
+    #[test]
+    fn apply_clears_the_key_and_keeps_the_rest() {
+        let mut frames = vec![frame(3, 1, &[GREEN, [0, 250, 0, 255], RED])];
+        let key = ChromaKey { color: Some([0, 255, 0]), tolerance: 0.05, softness: 0.0 };
+        apply(&mut frames, &key);
+        let alpha: Vec<u8> = frames[0].buffer.chunks_exact(4).map(|p| p[3]).collect();
+        assert_eq!(alpha, [0, 0, 255]);
+    }
+
+    #[test]
+    fn softness_ramps_alpha() {
+        // Distance 0.1 sits halfway through a 0.05..0.15 ramp
+        let gray = (0.1 * MAX_DISTANCE / 3f32.sqrt()).round() as u8;
+        let mut frames = vec![frame(1, 1, &[[gray, gray, gray, 255]])];
+        apply(&mut frames, &ChromaKey { color: Some([0, 0, 0]), tolerance: 0.05, softness: 0.1 });
+        assert!((120..=135).contains(&frames[0].buffer[3]), "{}", frames[0].buffer[3]);
+    }
+
+    #[test]
+    fn no_key_leaves_frames_alone() {
+        let mut frames = vec![frame(2, 2, &[CLEAR; 4])];
+        apply(&mut frames, &ChromaKey::default());
+        assert_eq!(frames[0].buffer, [CLEAR; 4].concat());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::types::Frame;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Halo cleanup for art that was anti-aliased against a matte color before being
+/// exported with 1-bit GIF transparency
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Defringe {
+    /// Color the edges were blended against (usually white)
+    #[serde(default = "default_matte")]
+    pub matte: [u8; 3],
+    /// Pixels to strip from the alpha edge
+    #[serde(default)]
+    pub erode: u32,
+    /// Pixels over which the alpha edge fades out, after eroding
+    #[serde(default)]
+    pub feather: u32,
+}
+
+pub fn default_matte() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+impl Default for Defringe {
+    fn default() -> Self {
+        Self {
+            matte: default_matte(),
+            erode: 0,
+            feather: 0,
+        }
+    }
+}
+
+pub fn apply(frames: &mut [Frame], defringe: &Defringe) {
+    let limit = (defringe.erode + defringe.feather).max(1);
+
+    for frame in frames.iter_mut() {
+        let distances = edge_distances(frame, limit);
+
+        for (pixel, &d) in frame.buffer.chunks_exact_mut(4).zip(&distances) {
+            if pixel[3] == 0 {
+                continue;
+            }
+
+            // Edge and semi-transparent pixels get the matte color un-blended
+            if d == 1 || pixel[3] < 255 {
+                unmatte(pixel, defringe.matte);
+            }
+
+            if d <= defringe.erode {
+                pixel[3] = 0;
+            } else if d <= defringe.erode + defringe.feather {
+                let factor = (d - defringe.erode) as f32 / (defringe.feather + 1) as f32;
+                pixel[3] = (pixel[3] as f32 * factor) as u8;
+            }
+        }
+    }
+}
+
+/// Treat the pixel as `color * a + matte * (1 - a)` and recover the smallest alpha and
+/// matching color that explain it (the classic "color to alpha" operation)
+fn unmatte(pixel: &mut [u8], matte: [u8; 3]) {
+    let mut alpha: f32 = 0.0;
+    for c in 0..3 {
+        let (v, m) = (pixel[c] as f32, matte[c] as f32);
+        let a = if v > m {
+            (v - m) / (255.0 - m)
+        } else if v < m {
+            (m - v) / m
+        } else {
+            0.0
+        };
+        alpha = alpha.max(a);
+    }
+
+    if alpha <= 0.0 {
+        pixel[3] = 0;
+        return;
+    }
+
+    for c in 0..3 {
+        let (v, m) = (pixel[c] as f32, matte[c] as f32);
+        pixel[c] = ((v - m) / alpha + m).round().clamp(0.0, 255.0) as u8;
+    }
+    pixel[3] = (pixel[3] as f32 * alpha).round() as u8;
+}
+
+/// Chessboard distance from every pixel to the nearest fully transparent one, computed
+/// with a multi-source fill and capped at `limit + 1`. Transparent pixels are 0.
+fn edge_distances(frame: &Frame, limit: u32) -> Vec<u32> {
+    let (w, h) = (frame.width as usize, frame.height as usize);
+    let mut dist = vec![limit + 1; w * h];
+    let mut queue = VecDeque::new();
+
+    for (i, pixel) in frame.buffer.chunks_exact(4).enumerate() {
+        if pixel[3] == 0 {
+            dist[i] = 0;
+            queue.push_back(i);
+        }
+    }
+
+    while let Some(i) = queue.pop_front() {
+        let next = dist[i] + 1;
+        if next > limit {
+            continue;
+        }
+
+        let (x, y) = ((i % w) as isize, (i / w) as isize);
+        for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= w as isize || ny >= h as isize {
+                continue;
+            }
+            let n = ny as usize * w + nx as usize;
+            if dist[n] > next {
+                dist[n] = next;
+                queue.push_back(n);
+            }
+        }
+    }
+
+    dist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn frame(width: u32, pixels: &[[u8; 4]]) -> Frame {
+        let height = pixels.len() as u32 / width;
+        Frame { buffer: pixels.concat(), width, height, delay: Duration::from_millis(100) }
+    }
+
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+    const RED: [u8; 4] = [255, 0, 0, 255];
+
+    #[test]
+    fn unmatte_recovers_color_blended_against_white() {
+        // Half red over white
+        let mut pixel = [255, 128, 128, 255];
+        unmatte(&mut pixel, [255, 255, 255]);
+        assert_eq!(pixel, [255, 0, 0, 127]);
+
+        // The matte color itself disappears
+        let mut pixel = [255, 255, 255, 255];
+        unmatte(&mut pixel, [255, 255, 255]);
+        assert_eq!(pixel[3], 0);
+    }
+
+    #[test]
+    fn distances_count_steps_to_transparency() {
+        let f = frame(5, &[CLEAR, RED, RED, RED, RED]);
+        assert_eq!(edge_distances(&f, 2), [0, 1, 2, 3, 3]);
+    }
+
+    #[test]
+    fn only_edge_pixels_are_unmatted() {
+        let halo = [255, 128, 128, 255];
+        let mut frames = vec![frame(3, &[CLEAR, halo, halo])];
+        apply(&mut frames, &Defringe::default());
+        assert_eq!(&frames[0].buffer[4..8], [255, 0, 0, 127]);
+        assert_eq!(&frames[0].buffer[8..12], halo);
+    }
+
+    #[test]
+    fn erode_then_feather() {
+        let mut frames = vec![frame(5, &[CLEAR, RED, RED, RED, RED])];
+        apply(&mut frames, &Defringe { matte: [255, 255, 255], erode: 1, feather: 1 });
+        let alpha: Vec<u8> = frames[0].buffer.chunks_exact(4).map(|p| p[3]).collect();
+        assert_eq!(alpha, [0, 0, 127, 255, 255]);
+    }
+}
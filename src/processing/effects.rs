@@ -0,0 +1,354 @@
+use crate::processing::Padding;
+use crate::types::{AnimationInfo, Frame};
+use serde::{Deserialize, Serialize};
+
+/// Solid outline drawn around the opaque pixels
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Outline {
+    #[serde(default = "default_outline_color")]
+    pub color: [u8; 3],
+    #[serde(default = "default_outline_thickness")]
+    pub thickness: u32,
+}
+
+pub fn default_outline_color() -> [u8; 3] {
+    [0, 0, 0]
+}
+
+pub fn default_outline_thickness() -> u32 {
+    2
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            color: default_outline_color(),
+            thickness: default_outline_thickness(),
+        }
+    }
+}
+
+/// Soft drop shadow cast by the opaque pixels (including the outline, if any)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Shadow {
+    #[serde(default = "default_shadow_offset")]
+    pub offset: (i32, i32),
+    #[serde(default = "default_shadow_blur")]
+    pub blur: u32,
+    #[serde(default = "default_shadow_color")]
+    pub color: [u8; 3],
+    #[serde(default = "default_shadow_opacity")]
+    pub opacity: f32,
+}
+
+pub fn default_shadow_offset() -> (i32, i32) {
+    (4, 4)
+}
+
+pub fn default_shadow_blur() -> u32 {
+    4
+}
+
+pub fn default_shadow_color() -> [u8; 3] {
+    [0, 0, 0]
+}
+
+pub fn default_shadow_opacity() -> f32 {
+    0.5
+}
+
+impl Default for Shadow {
+    fn default() -> Self {
+        Self {
+            offset: default_shadow_offset(),
+            blur: default_shadow_blur(),
+            color: default_shadow_color(),
+            opacity: default_shadow_opacity(),
+        }
+    }
+}
+
+impl Outline {
+    fn padding(&self) -> Padding {
+        let t = self.thickness;
+        Padding { left: t, top: t, right: t, bottom: t }
+    }
+}
+
+impl Shadow {
+    // Three box blurs approximate a gaussian; small blurs use fewer passes of radius 1 so the
+    // total spread (passes * radius) never exceeds `blur`
+    fn blur_passes(&self) -> (usize, usize) {
+        let passes = self.blur.min(3) as usize;
+        let radius = (self.blur as usize).checked_div(passes).unwrap_or(0);
+        (passes, radius)
+    }
+
+    // Room needed on each side for the blurred, offset copy
+    fn padding(&self) -> Padding {
+        let (dx, dy) = (self.offset.0 as i64, self.offset.1 as i64);
+        let (passes, radius) = self.blur_passes();
+        let spread = (passes * radius) as i64;
+        let side = |v: i64| v.clamp(0, u32::MAX as i64) as u32;
+        Padding {
+            left: side(spread - dx),
+            top: side(spread - dy),
+            right: side(spread + dx),
+            bottom: side(spread + dy),
+        }
+    }
+}
+
+pub fn apply_outline(info: &mut AnimationInfo, frames: &mut [Frame], outline: &Outline) -> Padding {
+    if outline.thickness == 0 {
+        return Padding::default();
+    }
+
+    let padding = pad(info, frames, outline.padding());
+    for frame in frames.iter_mut() {
+        let distances = distance_to_opaque(frame);
+        let radius = outline.thickness as f32;
+
+        let layer: Vec<u8> = distances
+            .iter()
+            .map(|&d| ((radius + 0.5 - d).clamp(0.0, 1.0) * 255.0) as u8)
+            .collect();
+        composite_under(frame, &layer, outline.color, 1.0);
+    }
+    padding
+}
+
+pub fn apply_shadow(info: &mut AnimationInfo, frames: &mut [Frame], shadow: &Shadow) -> Padding {
+    let padding = pad(info, frames, shadow.padding());
+    for frame in frames.iter_mut() {
+        let (w, h) = (frame.width as usize, frame.height as usize);
+        let alpha: Vec<u8> = frame.buffer.chunks_exact(4).map(|p| p[3]).collect();
+
+        // Shift the silhouette by the offset
+        let (dx, dy) = shadow.offset;
+        let mut layer = vec![0u8; w * h];
+        for y in 0..h as i32 {
+            for x in 0..w as i32 {
+                let (sx, sy) = (x - dx, y - dy);
+                if sx >= 0 && sy >= 0 && sx < w as i32 && sy < h as i32 {
+                    layer[y as usize * w + x as usize] = alpha[sy as usize * w + sx as usize];
+                }
+            }
+        }
+
+        let (passes, radius) = shadow.blur_passes();
+        for _ in 0..passes {
+            box_blur(&mut layer, w, h, radius);
+        }
+
+        composite_under(frame, &layer, shadow.color, shadow.opacity.clamp(0.0, 1.0));
+    }
+    padding
+}
+
+/// Grow every frame by `padding`, filling the new area with transparency.
+/// The padding is cut down where the canvas would outgrow what a GIF can describe (65535 px);
+/// returns the padding actually added.
+pub fn pad(info: &mut AnimationInfo, frames: &mut [Frame], padding: Padding) -> Padding {
+    let (left, right) = fit_padding(info.width, padding.left, padding.right);
+    let (top, bottom) = fit_padding(info.height, padding.top, padding.bottom);
+    let padding = Padding { left, top, right, bottom };
+    if padding == Padding::default() {
+        return padding;
+    }
+
+    for frame in frames.iter_mut() {
+        let new_width = frame.width + padding.left + padding.right;
+        let new_height = frame.height + padding.top + padding.bottom;
+        let mut buffer = vec![0u8; new_width as usize * new_height as usize * 4];
+
+        let row_len = frame.width as usize * 4;
+        for y in 0..frame.height as usize {
+            let src = y * row_len;
+            let dst = ((y + padding.top as usize) * new_width as usize + padding.left as usize) * 4;
+            buffer[dst..dst + row_len].copy_from_slice(&frame.buffer[src..src + row_len]);
+        }
+
+        frame.buffer = buffer;
+        frame.width = new_width;
+        frame.height = new_height;
+    }
+
+    info.width += (padding.left + padding.right) as u16;
+    info.height += (padding.top + padding.bottom) as u16;
+    padding
+}
+
+// Shrink both sides proportionally so `size + a + b` stays within u16
+fn fit_padding(size: u16, a: u32, b: u32) -> (u32, u32) {
+    let room = (u16::MAX - size) as u64;
+    let total = a as u64 + b as u64;
+    if total <= room {
+        return (a, b);
+    }
+    let a = (a as u64 * room / total) as u32;
+    (a, (room - a as u64) as u32)
+}
+
+/// Composite a single-color layer (given as per-pixel coverage) underneath the frame
+fn composite_under(frame: &mut Frame, coverage: &[u8], color: [u8; 3], opacity: f32) {
+    for (pixel, &c) in frame.buffer.chunks_exact_mut(4).zip(coverage) {
+        let under_a = c as f32 / 255.0 * opacity;
+        if under_a <= 0.0 {
+            continue;
+        }
+
+        let top_a = pixel[3] as f32 / 255.0;
+        let out_a = top_a + under_a * (1.0 - top_a);
+        for i in 0..3 {
+            let top = pixel[i] as f32 * top_a;
+            let under = color[i] as f32 * under_a * (1.0 - top_a);
+            pixel[i] = ((top + under) / out_a).round() as u8;
+        }
+        pixel[3] = (out_a * 255.0).round() as u8;
+    }
+}
+
+/// Approximate euclidean distance from each pixel to the nearest mostly-opaque one,
+/// using a two-pass 3-4 chamfer transform
+fn distance_to_opaque(frame: &Frame) -> Vec<f32> {
+    let (w, h) = (frame.width as usize, frame.height as usize);
+    let far = u32::MAX / 2;
+    let mut dist: Vec<u32> = frame
+        .buffer
+        .chunks_exact(4)
+        .map(|p| if p[3] >= 128 { 0 } else { far })
+        .collect();
+
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            let mut d = dist[i];
+            if x > 0 { d = d.min(dist[i - 1] + 3); }
+            if y > 0 {
+                d = d.min(dist[i - w] + 3);
+                if x > 0 { d = d.min(dist[i - w - 1] + 4); }
+                if x + 1 < w { d = d.min(dist[i - w + 1] + 4); }
+            }
+            dist[i] = d;
+        }
+    }
+    for y in (0..h).rev() {
+        for x in (0..w).rev() {
+            let i = y * w + x;
+            let mut d = dist[i];
+            if x + 1 < w { d = d.min(dist[i + 1] + 3); }
+            if y + 1 < h {
+                d = d.min(dist[i + w] + 3);
+                if x + 1 < w { d = d.min(dist[i + w + 1] + 4); }
+                if x > 0 { d = d.min(dist[i + w - 1] + 4); }
+            }
+            dist[i] = d;
+        }
+    }
+
+    dist.into_iter().map(|d| d as f32 / 3.0).collect()
+}
+
+fn box_blur(layer: &mut [u8], w: usize, h: usize, radius: usize) {
+    let mut tmp = vec![0u8; layer.len()];
+    blur_pass(layer, &mut tmp, w, h, radius, 1, w);
+    blur_pass(&tmp, layer, h, w, radius, w, 1);
+}
+
+// Sliding-window average along one axis; `step` walks a line, `stride` moves between lines
+fn blur_pass(src: &[u8], dst: &mut [u8], len: usize, lines: usize, radius: usize, step: usize, stride: usize) {
+    let window = (radius * 2 + 1) as u32;
+    for line in 0..lines {
+        let base = line * stride;
+        let at = |i: isize| -> u32 {
+            if i < 0 || i >= len as isize { 0 } else { src[base + i as usize * step] as u32 }
+        };
+
+        let mut sum: u32 = (-(radius as isize)..=radius as isize).map(at).sum();
+        for i in 0..len as isize {
+            dst[base + i as usize * step] = (sum / window) as u8;
+            sum += at(i + radius as isize + 1);
+            sum -= at(i - radius as isize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // One opaque white pixel in the middle of a transparent 3x3 canvas
+    fn dot() -> (AnimationInfo, Vec<Frame>) {
+        let mut buffer = vec![0u8; 3 * 3 * 4];
+        buffer[16..20].copy_from_slice(&[255, 255, 255, 255]);
+        let info = AnimationInfo {
+            width: 3,
+            height: 3,
+            frame_count: 1,
+            duration: Duration::from_millis(100),
+            palette: Vec::new(),
+            loops: None,
+            format: "gif",
+        };
+        (info, vec![Frame { buffer, width: 3, height: 3, delay: Duration::from_millis(100) }])
+    }
+
+    fn pixel(frame: &Frame, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * frame.width + x) * 4) as usize;
+        frame.buffer[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn outline_grows_the_canvas_and_surrounds_the_art() {
+        let (mut info, mut frames) = dot();
+        let padding = apply_outline(&mut info, &mut frames, &Outline { color: [255, 0, 0], thickness: 2 });
+        assert_eq!(padding, Padding { left: 2, top: 2, right: 2, bottom: 2 });
+        assert_eq!((info.width, info.height), (7, 7));
+        assert_eq!((frames[0].width, frames[0].height), (7, 7));
+
+        assert_eq!(pixel(&frames[0], 3, 3), [255, 255, 255, 255]);
+        assert_eq!(pixel(&frames[0], 2, 3), [255, 0, 0, 255]);
+        // Half covered at the rim, anti-aliased
+        assert_eq!(pixel(&frames[0], 1, 3), [255, 0, 0, 127]);
+        assert_eq!(pixel(&frames[0], 0, 0)[3], 0);
+    }
+
+    #[test]
+    fn shadow_padding_follows_offset_and_blur() {
+        let shadow = Shadow { offset: (2, -1), blur: 0, ..Shadow::default() };
+        assert_eq!(shadow.padding(), Padding { left: 0, top: 1, right: 2, bottom: 0 });
+
+        let shadow = Shadow { offset: (0, 0), blur: 7, ..Shadow::default() };
+        assert_eq!(shadow.blur_passes(), (3, 2));
+        assert_eq!(shadow.padding(), Padding { left: 6, top: 6, right: 6, bottom: 6 });
+
+        assert_eq!(Shadow { blur: 2, ..Shadow::default() }.blur_passes(), (2, 1));
+    }
+
+    #[test]
+    fn shadow_lands_at_the_offset() {
+        let (mut info, mut frames) = dot();
+        let shadow = Shadow { offset: (1, 1), blur: 0, color: [0, 0, 0], opacity: 1.0 };
+        apply_shadow(&mut info, &mut frames, &shadow);
+        assert_eq!((info.width, info.height), (4, 4));
+        assert_eq!(pixel(&frames[0], 1, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(&frames[0], 2, 2), [0, 0, 0, 255]);
+        assert_eq!(pixel(&frames[0], 0, 0)[3], 0);
+    }
+
+    #[test]
+    fn padding_is_cut_to_fit_the_canvas_limit() {
+        assert_eq!(fit_padding(10, 4, 6), (4, 6));
+        assert_eq!(fit_padding(u16::MAX - 5, 4, 6), (2, 3));
+        assert_eq!(fit_padding(u16::MAX, 4, 6), (0, 0));
+    }
+
+    #[test]
+    fn box_blur_spreads_and_keeps_the_sum() {
+        let mut layer = vec![0, 0, 0, 0, 90, 0, 0, 0, 0];
+        box_blur(&mut layer, 3, 3, 1);
+        assert_eq!(layer, [10; 9]);
+    }
+}
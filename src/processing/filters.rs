@@ -0,0 +1,225 @@
+use crate::processing::{format_hex_color, parse_hex_color};
+use crate::types::Frame;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// One step of a per-animation color filter stack, modelled after the CSS filter functions.
+/// `amount` values blend between the original (0.0) and the full effect (1.0).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ColorFilter {
+    Tint { color: [u8; 3], amount: f32 },
+    Grayscale { amount: f32 },
+    Sepia { amount: f32 },
+    HueRotate { degrees: f32 },
+    Saturation { factor: f32 },
+    Brightness { factor: f32 },
+    Contrast { factor: f32 },
+    Invert { amount: f32 },
+}
+
+impl ColorFilter {
+    pub const NAMES: [&'static str; 8] = [
+        "tint", "grayscale", "sepia", "hue-rotate", "saturation", "brightness", "contrast", "invert",
+    ];
+
+    /// The filter with its neutral-ish starting value, as added from the GUI
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "tint" => ColorFilter::Tint { color: [255, 128, 0], amount: 0.5 },
+            "grayscale" => ColorFilter::Grayscale { amount: 1.0 },
+            "sepia" => ColorFilter::Sepia { amount: 1.0 },
+            "hue-rotate" => ColorFilter::HueRotate { degrees: 90.0 },
+            "saturation" => ColorFilter::Saturation { factor: 1.5 },
+            "brightness" => ColorFilter::Brightness { factor: 1.2 },
+            "contrast" => ColorFilter::Contrast { factor: 1.2 },
+            "invert" => ColorFilter::Invert { amount: 1.0 },
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorFilter::Tint { .. } => "tint",
+            ColorFilter::Grayscale { .. } => "grayscale",
+            ColorFilter::Sepia { .. } => "sepia",
+            ColorFilter::HueRotate { .. } => "hue-rotate",
+            ColorFilter::Saturation { .. } => "saturation",
+            ColorFilter::Brightness { .. } => "brightness",
+            ColorFilter::Contrast { .. } => "contrast",
+            ColorFilter::Invert { .. } => "invert",
+        }
+    }
+
+    fn apply_rgb(&self, [r, g, b]: [f32; 3]) -> [f32; 3] {
+        let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        match *self {
+            ColorFilter::Tint { color, amount } => {
+                let tinted = color.map(|c| luma * c as f32 / 255.0);
+                mix([r, g, b], tinted, amount)
+            }
+            ColorFilter::Grayscale { amount } => mix([r, g, b], [luma; 3], amount),
+            ColorFilter::Sepia { amount } => {
+                let sepia = [
+                    0.393 * r + 0.769 * g + 0.189 * b,
+                    0.349 * r + 0.686 * g + 0.168 * b,
+                    0.272 * r + 0.534 * g + 0.131 * b,
+                ];
+                mix([r, g, b], sepia, amount)
+            }
+            ColorFilter::HueRotate { degrees } => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                let m = [
+                    [0.213 + cos * 0.787 - sin * 0.213, 0.715 - cos * 0.715 - sin * 0.715, 0.072 - cos * 0.072 + sin * 0.928],
+                    [0.213 - cos * 0.213 + sin * 0.143, 0.715 + cos * 0.285 + sin * 0.140, 0.072 - cos * 0.072 - sin * 0.283],
+                    [0.213 - cos * 0.213 - sin * 0.787, 0.715 - cos * 0.715 + sin * 0.715, 0.072 + cos * 0.928 + sin * 0.072],
+                ];
+                m.map(|row| row[0] * r + row[1] * g + row[2] * b)
+            }
+            ColorFilter::Saturation { factor } => [r, g, b].map(|c| luma + (c - luma) * factor),
+            ColorFilter::Brightness { factor } => [r, g, b].map(|c| c * factor),
+            ColorFilter::Contrast { factor } => [r, g, b].map(|c| (c - 0.5) * factor + 0.5),
+            ColorFilter::Invert { amount } => mix([r, g, b], [1.0 - r, 1.0 - g, 1.0 - b], amount),
+        }
+    }
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], amount: f32) -> [f32; 3] {
+    let t = amount.clamp(0.0, 1.0);
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+}
+
+/// Run every frame through the filter stack, in order
+pub fn apply(frames: &mut [Frame], filters: &[ColorFilter]) {
+    if filters.is_empty() {
+        return;
+    }
+
+    for frame in frames.iter_mut() {
+        for pixel in frame.buffer.chunks_exact_mut(4) {
+            if pixel[3] == 0 {
+                continue;
+            }
+
+            let mut rgb = [pixel[0], pixel[1], pixel[2]].map(|c| c as f32 / 255.0);
+            for filter in filters {
+                rgb = filter.apply_rgb(rgb).map(|c| c.clamp(0.0, 1.0));
+            }
+            for (channel, value) in pixel.iter_mut().zip(rgb) {
+                *channel = (value * 255.0).round() as u8;
+            }
+        }
+    }
+}
+
+/// CLI form: `name[=value]`, e.g. `grayscale`, `hue-rotate=90`, `tint=#ff8800:0.5`
+impl FromStr for ColorFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (s.trim(), None),
+        };
+
+        let number = |default: Option<f32>| -> Result<f32, String> {
+            match value {
+                Some(v) => v.parse().map_err(|_| format!("Invalid value '{}' for filter '{}'", v, name)),
+                None => default.ok_or_else(|| format!("Filter '{}' needs a value, e.g. {}=1.5", name, name)),
+            }
+        };
+
+        Ok(match name {
+            "tint" => {
+                let spec = value.ok_or("Filter 'tint' needs a color, e.g. tint=#ff8800:0.5")?;
+                let (color, amount) = match spec.split_once(':') {
+                    Some((color, amount)) => (color, amount.parse().map_err(|_| format!("Invalid tint amount '{}'", amount))?),
+                    None => (spec, 1.0),
+                };
+                ColorFilter::Tint { color: parse_hex_color(color)?, amount }
+            }
+            "grayscale" => ColorFilter::Grayscale { amount: number(Some(1.0))? },
+            "sepia" => ColorFilter::Sepia { amount: number(Some(1.0))? },
+            "hue-rotate" => ColorFilter::HueRotate { degrees: number(None)? },
+            "saturation" => ColorFilter::Saturation { factor: number(None)? },
+            "brightness" => ColorFilter::Brightness { factor: number(None)? },
+            "contrast" => ColorFilter::Contrast { factor: number(None)? },
+            "invert" => ColorFilter::Invert { amount: number(Some(1.0))? },
+            _ => return Err(format!("Unknown filter '{}', expected one of: {}", name, ColorFilter::NAMES.join(", "))),
+        })
+    }
+}
+
+impl fmt::Display for ColorFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorFilter::Tint { color, amount } => write!(f, "tint={}:{}", format_hex_color(*color), amount),
+            ColorFilter::Grayscale { amount } | ColorFilter::Sepia { amount } | ColorFilter::Invert { amount } => {
+                write!(f, "{}={}", self.name(), amount)
+            }
+            ColorFilter::HueRotate { degrees } => write!(f, "hue-rotate={}", degrees),
+            ColorFilter::Saturation { factor } | ColorFilter::Brightness { factor } | ColorFilter::Contrast { factor } => {
+                write!(f, "{}={}", self.name(), factor)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn filtered(pixel: [u8; 4], filters: &[ColorFilter]) -> [u8; 4] {
+        let mut frames = vec![Frame { buffer: pixel.to_vec(), width: 1, height: 1, delay: Duration::ZERO }];
+        apply(&mut frames, filters);
+        frames[0].buffer[..].try_into().unwrap()
+    }
+
+    #[test]
+    fn filters_recolor_opaque_pixels() {
+        assert_eq!(filtered([255, 0, 0, 255], &[ColorFilter::Grayscale { amount: 1.0 }]), [54, 54, 54, 255]);
+        assert_eq!(filtered([255, 0, 0, 200], &[ColorFilter::Invert { amount: 1.0 }]), [0, 255, 255, 200]);
+        assert_eq!(filtered([100, 100, 100, 255], &[ColorFilter::Brightness { factor: 2.0 }]), [200, 200, 200, 255]);
+        // Half strength lands halfway
+        assert_eq!(filtered([0, 0, 0, 255], &[ColorFilter::Invert { amount: 0.5 }]), [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn hue_rotate_full_turn_is_identity() {
+        assert_eq!(filtered([200, 50, 10, 255], &[ColorFilter::HueRotate { degrees: 360.0 }]), [200, 50, 10, 255]);
+    }
+
+    #[test]
+    fn stack_runs_in_order_and_clamps_between_steps() {
+        // Brightening first saturates at white, so darkening afterwards can't bring the color back
+        let stack = [ColorFilter::Brightness { factor: 4.0 }, ColorFilter::Brightness { factor: 0.5 }];
+        assert_eq!(filtered([100, 0, 0, 255], &stack), [128, 0, 0, 255]);
+        let stack = [ColorFilter::Brightness { factor: 0.5 }, ColorFilter::Brightness { factor: 4.0 }];
+        assert_eq!(filtered([100, 0, 0, 255], &stack), [200, 0, 0, 255]);
+    }
+
+    #[test]
+    fn transparent_pixels_are_left_alone() {
+        assert_eq!(filtered([10, 20, 30, 0], &[ColorFilter::Invert { amount: 1.0 }]), [10, 20, 30, 0]);
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for text in ["tint=#ff8800:0.5", "grayscale=1", "hue-rotate=90", "contrast=1.2", "invert=0.25"] {
+            let filter: ColorFilter = text.parse().unwrap();
+            assert_eq!(filter.to_string(), text);
+        }
+        assert_eq!("sepia".parse(), Ok(ColorFilter::Sepia { amount: 1.0 }));
+        assert_eq!("tint=#000000".parse(), Ok(ColorFilter::Tint { color: [0, 0, 0], amount: 1.0 }));
+    }
+
+    #[test]
+    fn parse_rejects_bad_input() {
+        assert!("blur=2".parse::<ColorFilter>().is_err());
+        assert!("saturation".parse::<ColorFilter>().is_err());
+        assert!("brightness=lots".parse::<ColorFilter>().is_err());
+        assert!("tint".parse::<ColorFilter>().is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::types::Frame;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How in-between frames are generated
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum InterpolationMethod {
+    /// Alpha cross-fade between neighbouring frames
+    #[default]
+    Blend,
+    /// Block-based motion estimation, falling back to a cross-fade inside each block
+    Motion,
+}
+
+impl InterpolationMethod {
+    pub const ALL: [InterpolationMethod; 2] = [InterpolationMethod::Blend, InterpolationMethod::Motion];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InterpolationMethod::Blend => "blend",
+            InterpolationMethod::Motion => "motion",
+        }
+    }
+}
+
+impl FromStr for InterpolationMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InterpolationMethod::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .ok_or_else(|| format!("Unknown interpolation method '{}' (blend, motion)", s))
+    }
+}
+
+impl fmt::Display for InterpolationMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Raise playback to `target_fps` by generating frames between the original ones
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Interpolation {
+    #[serde(default = "default_target_fps")]
+    pub target_fps: u32,
+    #[serde(default)]
+    pub method: InterpolationMethod,
+}
+
+pub fn default_target_fps() -> u32 {
+    60
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Self {
+            target_fps: default_target_fps(),
+            method: InterpolationMethod::default(),
+        }
+    }
+}
+
+const BLOCK: usize = 8;
+const SEARCH: i32 = 6;
+
+/// Expand `frames` with in-between frames. Generated frames are cached in the returned list,
+/// so playback cost stays the same, at the price of memory.
+/// Frames that are held longer than usual keep their hold and only transition at the end.
+/// With `wrap`, the last frame also transitions into the first (looping forward or in reverse);
+/// ping-pong playback never crosses that seam, so it should pass `false`.
+pub fn apply(frames: Vec<Frame>, settings: &Interpolation, wrap: bool) -> Vec<Frame> {
+    if frames.len() < 2 || settings.target_fps == 0 {
+        return frames;
+    }
+
+    let step = Duration::from_secs_f64(1.0 / settings.target_fps as f64);
+
+    // The typical frame delay; transitions never take longer than this
+    let mut delays: Vec<Duration> = frames.iter().map(|f| f.delay).collect();
+    delays.sort();
+    let transition = delays[delays.len() / 2];
+
+    let mut out = Vec::with_capacity(frames.len());
+    for (i, frame) in frames.iter().enumerate() {
+        let next = match frames.get(i + 1) {
+            Some(next) => next,
+            None if wrap => &frames[0],
+            None => {
+                out.push(frame.clone());
+                continue;
+            }
+        };
+
+        let fade = frame.delay.min(transition);
+        let steps = (fade.as_secs_f64() / step.as_secs_f64()).round() as u32;
+        if steps < 2 || frame.width != next.width || frame.height != next.height {
+            out.push(frame.clone());
+            continue;
+        }
+
+        let vectors = match settings.method {
+            InterpolationMethod::Blend => None,
+            InterpolationMethod::Motion => Some(estimate_motion(frame, next)),
+        };
+
+        // The original frame keeps the hold plus the first step of the transition
+        let hold = frame.delay - fade;
+        let mut first = frame.clone();
+        first.delay = hold + fade / steps;
+        out.push(first);
+
+        for k in 1..steps {
+            let t = k as f32 / steps as f32;
+            let buffer = match &vectors {
+                Some(vectors) => motion_blend(frame, next, vectors, t),
+                None => cross_fade(&frame.buffer, &next.buffer, t),
+            };
+            // Spread rounding so the steps add up to the original delay
+            let delay = fade * (k + 1) / steps - fade * k / steps;
+            out.push(Frame {
+                buffer,
+                width: frame.width,
+                height: frame.height,
+                delay,
+            });
+        }
+    }
+    out
+}
+
+// Blend two RGBA pixels in premultiplied space so fully transparent pixels don't bleed color
+fn mix(a: &[u8], b: &[u8], t: f32) -> [u8; 4] {
+    let aa = a[3] as f32 / 255.0 * (1.0 - t);
+    let ab = b[3] as f32 / 255.0 * t;
+    let alpha = aa + ab;
+    if alpha <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |c: usize| ((a[c] as f32 * aa + b[c] as f32 * ab) / alpha).round() as u8;
+    [channel(0), channel(1), channel(2), (alpha * 255.0).round() as u8]
+}
+
+fn cross_fade(a: &[u8], b: &[u8], t: f32) -> Vec<u8> {
+    let mut out = vec![0u8; a.len()];
+    for ((o, pa), pb) in out.chunks_exact_mut(4).zip(a.chunks_exact(4)).zip(b.chunks_exact(4)) {
+        o.copy_from_slice(&mix(pa, pb, t));
+    }
+    out
+}
+
+// Sum of absolute differences between a block of `a` at (bx, by) and `b` shifted by (dx, dy)
+fn block_cost(a: &Frame, b: &Frame, bx: usize, by: usize, dx: i32, dy: i32, limit: u32) -> u32 {
+    let (w, h) = (a.width as i32, a.height as i32);
+    let mut cost = 0u32;
+    for y in by..(by + BLOCK).min(h as usize) {
+        let sy = y as i32 + dy;
+        for x in bx..(bx + BLOCK).min(w as usize) {
+            let sx = x as i32 + dx;
+            if sx < 0 || sy < 0 || sx >= w || sy >= h {
+                cost += 255 * 4;
+                continue;
+            }
+            let ia = (y * w as usize + x) * 4;
+            let ib = (sy as usize * w as usize + sx as usize) * 4;
+            for c in 0..4 {
+                cost += a.buffer[ia + c].abs_diff(b.buffer[ib + c]) as u32;
+            }
+        }
+        if cost > limit {
+            break;
+        }
+    }
+    cost
+}
+
+// One motion vector per block, found by exhaustive search in a small window
+fn estimate_motion(a: &Frame, b: &Frame) -> Vec<(i32, i32)> {
+    let blocks_x = (a.width as usize).div_ceil(BLOCK);
+    let blocks_y = (a.height as usize).div_ceil(BLOCK);
+    let mut vectors = Vec::with_capacity(blocks_x * blocks_y);
+
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let (px, py) = (bx * BLOCK, by * BLOCK);
+            let mut best: (i32, i32) = (0, 0);
+            let mut best_cost = block_cost(a, b, px, py, 0, 0, u32::MAX);
+            if best_cost > 0 {
+                for dy in -SEARCH..=SEARCH {
+                    for dx in -SEARCH..=SEARCH {
+                        let cost = block_cost(a, b, px, py, dx, dy, best_cost);
+                        // Prefer shorter vectors on ties, which keeps flat areas still
+                        if cost < best_cost || (cost == best_cost && dx.abs() + dy.abs() < best.0.abs() + best.1.abs()) {
+                            best = (dx, dy);
+                            best_cost = cost;
+                        }
+                    }
+                }
+            }
+            vectors.push(best);
+        }
+    }
+    vectors
+}
+
+// Sample `a` back along each block's vector and `b` forward along it, then cross-fade
+fn motion_blend(a: &Frame, b: &Frame, vectors: &[(i32, i32)], t: f32) -> Vec<u8> {
+    let (w, h) = (a.width as usize, a.height as usize);
+    let blocks_x = w.div_ceil(BLOCK);
+    let mut out = vec![0u8; a.buffer.len()];
+
+    let sample = |frame: &Frame, x: i32, y: i32| -> [u8; 4] {
+        let x = x.clamp(0, w as i32 - 1) as usize;
+        let y = y.clamp(0, h as i32 - 1) as usize;
+        let i = (y * w + x) * 4;
+        [frame.buffer[i], frame.buffer[i + 1], frame.buffer[i + 2], frame.buffer[i + 3]]
+    };
+
+    for y in 0..h {
+        for x in 0..w {
+            let (dx, dy) = vectors[(y / BLOCK) * blocks_x + x / BLOCK];
+            let ax = x as i32 - (dx as f32 * t).round() as i32;
+            let ay = y as i32 - (dy as f32 * t).round() as i32;
+            let bx = x as i32 + (dx as f32 * (1.0 - t)).round() as i32;
+            let by = y as i32 + (dy as f32 * (1.0 - t)).round() as i32;
+            let i = (y * w + x) * 4;
+            out[i..i + 4].copy_from_slice(&mix(&sample(a, ax, ay), &sample(b, bx, by), t));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(rgba: [u8; 4], delay_ms: u64) -> Frame {
+        Frame { buffer: rgba.repeat(4), width: 2, height: 2, delay: Duration::from_millis(delay_ms) }
+    }
+
+    fn delays(frames: &[Frame]) -> Vec<u64> {
+        frames.iter().map(|f| f.delay.as_millis() as u64).collect()
+    }
+
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+    #[test]
+    fn steps_fill_the_delay_without_changing_the_total() {
+        let frames = vec![solid(BLACK, 100), solid(WHITE, 100)];
+        let settings = Interpolation { target_fps: 40, method: InterpolationMethod::Blend };
+
+        let out = apply(frames.clone(), &settings, false);
+        assert_eq!(delays(&out), [25, 25, 25, 25, 100]);
+        assert_eq!(&out[2].buffer[..4], [128, 128, 128, 255]);
+
+        let out = apply(frames, &settings, true);
+        assert_eq!(delays(&out), [25; 8]);
+        assert_eq!(&out[6].buffer[..4], [128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn long_holds_transition_only_at_the_end() {
+        let frames = vec![solid(BLACK, 100), solid(WHITE, 100), solid(BLACK, 400)];
+        let out = apply(frames, &Interpolation { target_fps: 40, method: InterpolationMethod::Blend }, true);
+        assert_eq!(delays(&out)[8..], [325, 25, 25, 25]);
+        assert_eq!(out[8].buffer, BLACK.repeat(4));
+    }
+
+    #[test]
+    fn nothing_is_added_when_the_target_is_too_low() {
+        let frames = vec![solid(BLACK, 100), solid(WHITE, 100)];
+        let out = apply(frames, &Interpolation { target_fps: 10, method: InterpolationMethod::Blend }, true);
+        assert_eq!(delays(&out), [100, 100]);
+    }
+
+    #[test]
+    fn transparent_pixels_do_not_bleed_color() {
+        assert_eq!(mix(&[255, 0, 0, 255], &[0, 0, 0, 0], 0.5), [255, 0, 0, 128]);
+        assert_eq!(mix(&[0, 0, 0, 0], &[0, 0, 0, 0], 0.5), [0, 0, 0, 0]);
+    }
+
+    // A 2x2 square at `x` on a transparent 16x8 canvas
+    fn square_at(x: usize) -> Frame {
+        let mut buffer = vec![0u8; 16 * 8 * 4];
+        for y in 2..4 {
+            for x in x..x + 2 {
+                buffer[(y * 16 + x) * 4..(y * 16 + x) * 4 + 4].copy_from_slice(&WHITE);
+            }
+        }
+        Frame { buffer, width: 16, height: 8, delay: Duration::from_millis(100) }
+    }
+
+    #[test]
+    fn motion_moves_the_square_instead_of_fading_it() {
+        let (a, b) = (square_at(1), square_at(5));
+        let vectors = estimate_motion(&a, &b);
+        assert_eq!(vectors, [(4, 0), (0, 0)]);
+
+        let mid = motion_blend(&a, &b, &vectors, 0.5);
+        let alpha_at = |x: usize| mid[(2 * 16 + x) * 4 + 3];
+        assert_eq!([alpha_at(2), alpha_at(3), alpha_at(4), alpha_at(5)], [0, 255, 255, 0]);
+    }
+
+    #[test]
+    fn methods_parse_and_display() {
+        assert_eq!("motion".parse(), Ok(InterpolationMethod::Motion));
+        assert_eq!(InterpolationMethod::Blend.to_string(), "blend");
+        assert!("optical-flow".parse::<InterpolationMethod>().is_err());
+    }
+}
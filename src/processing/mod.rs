@@ -0,0 +1,128 @@
+pub mod background;
+pub mod chroma;
+pub mod defringe;
+pub mod effects;
+pub mod filters;
+pub mod interpolate;
+pub mod scale;
+pub mod trim;
+
+use crate::decoder::{self, palette::PaletteSwap};
+use crate::types::{AnimationInfo, Frame};
+use background::BackgroundRemoval;
+use defringe::Defringe;
+use effects::{Outline, Shadow};
+use filters::ColorFilter;
+use std::path::Path;
+use trim::CropRect;
+
+/// Everything that gets applied to decoded frames once at load time, before playback.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProcessOptions {
+    /// Applied to the color tables by the decoder (see `load`), not by `process`
+    pub palette: Vec<PaletteSwap>,
+    /// Manual crop in original canvas pixels, applied before anything else
+    pub crop: Option<CropRect>,
+    /// Crop away transparent margins once the background has been removed
+    pub trim: bool,
+    pub scale: Option<f32>,
+    pub background: Option<BackgroundRemoval>,
+    pub defringe: Option<Defringe>,
+    pub filters: Vec<ColorFilter>,
+    pub outline: Option<Outline>,
+    pub shadow: Option<Shadow>,
+}
+
+impl ProcessOptions {
+    // Settings that change the pixels background removal sees, so its cached masks are only
+    // reused when all of them match. Keep in step with the order in `process`.
+    fn background_inputs(&self) -> impl std::hash::Hash + '_ {
+        (&self.palette, self.crop)
+    }
+}
+
+/// Transparent margin added around the original canvas by effects that draw outside it.
+/// Placement uses it to keep the artwork itself where the user aligned it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Padding {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl Padding {
+    pub fn grow(self, other: Padding) -> Padding {
+        Padding {
+            left: self.left + other.left,
+            top: self.top + other.top,
+            right: self.right + other.right,
+            bottom: self.bottom + other.bottom,
+        }
+    }
+}
+
+/// Decode a file and run it through the whole pipeline, palette swaps included
+pub fn load(path: &Path, options: &ProcessOptions) -> Result<(AnimationInfo, Vec<Frame>, Padding), Box<dyn std::error::Error>> {
+    let (mut info, mut frames) = decoder::load_animation_with_palette(path, &options.palette)?;
+    let padding = process(&mut info, &mut frames, options, Some(path))?;
+    Ok((info, frames, padding))
+}
+
+/// Run the processing pipeline over the decoded frames in place.
+/// Background removal happens before scaling so colors are matched on the original pixels,
+/// while outline and shadow come last so their sizes are in screen pixels.
+/// `source` is the file the frames came from, used to key on-disk caches.
+/// Returns the padding the effects added around the canvas, or why the settings can't be applied.
+pub fn process(info: &mut AnimationInfo, frames: &mut [Frame], options: &ProcessOptions, source: Option<&Path>) -> Result<Padding, String> {
+    if let Some(rect) = options.crop {
+        trim::crop(info, frames, rect);
+    }
+
+    if let Some(removal) = &options.background {
+        background::apply(frames, removal, source, &options.background_inputs());
+    }
+
+    // Cleaning up the alpha edge must happen at original resolution, before resampling
+    // smears the halo into neighbouring pixels
+    if let Some(defringe) = &options.defringe {
+        defringe::apply(frames, defringe);
+    }
+
+    // Trimming before scaling keeps the resize work (and the window) as small as possible
+    if options.trim {
+        trim::auto_trim(info, frames);
+    }
+
+    if let Some(s) = options.scale {
+        scale::apply(info, frames, s)?;
+    }
+
+    // Filters recolor the artwork only, so outline and shadow keep their chosen colors
+    filters::apply(frames, &options.filters);
+
+    let mut padding = Padding::default();
+    if let Some(outline) = &options.outline {
+        padding = padding.grow(effects::apply_outline(info, frames, outline));
+    }
+    if let Some(shadow) = &options.shadow {
+        padding = padding.grow(effects::apply_shadow(info, frames, shadow));
+    }
+
+    Ok(padding)
+}
+
+/// Parse a color written as `#rrggbb` or `rrggbb`
+pub fn parse_hex_color(s: &str) -> Result<[u8; 3], String> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid color '{}', expected #rrggbb", s));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn format_hex_color(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
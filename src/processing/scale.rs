@@ -0,0 +1,28 @@
+use crate::types::{AnimationInfo, Frame};
+use image::{ImageBuffer, Rgba, imageops::FilterType};
+
+/// Resize every frame by `factor`, skipping the work entirely for a factor of 1.0.
+/// Fails without touching the frames if the result would be too large for a canvas.
+pub fn apply(info: &mut AnimationInfo, frames: &mut [Frame], factor: f32) -> Result<(), String> {
+    if factor <= 0.0 || (factor.abs() - 1.0).abs() <= f32::EPSILON {
+        return Ok(());
+    }
+
+    let scaled = |size: u16| u16::try_from(((size as f32 * factor) as u32).max(1));
+    let (Ok(new_width), Ok(new_height)) = (scaled(info.width), scaled(info.height)) else {
+        return Err(format!("Scaling {}x{} by {} exceeds the 65535 px canvas limit", info.width, info.height, factor));
+    };
+
+    for frame in frames.iter_mut() {
+        let old_buffer = std::mem::take(&mut frame.buffer);
+        if let Some(img) = ImageBuffer::<Rgba<u8>, _>::from_raw(frame.width, frame.height, old_buffer) {
+            let resized = image::imageops::resize(&img, new_width as u32, new_height as u32, FilterType::Lanczos3);
+            frame.width = resized.width();
+            frame.height = resized.height();
+            frame.buffer = resized.into_raw();
+        }
+    }
+    info.width = new_width;
+    info.height = new_height;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::types::{AnimationInfo, Frame};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Rectangle in original canvas pixels
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Hash)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Cut every frame down to `rect`, clamped to the canvas
+pub fn crop(info: &mut AnimationInfo, frames: &mut [Frame], rect: CropRect) {
+    let (canvas_w, canvas_h) = (info.width as u32, info.height as u32);
+    let x = rect.x.min(canvas_w.saturating_sub(1));
+    let y = rect.y.min(canvas_h.saturating_sub(1));
+    let width = rect.width.min(canvas_w - x).max(1);
+    let height = rect.height.min(canvas_h - y).max(1);
+
+    if (x, y, width, height) == (0, 0, canvas_w, canvas_h) {
+        return;
+    }
+
+    for frame in frames.iter_mut() {
+        let row_len = width as usize * 4;
+        let mut buffer = Vec::with_capacity(row_len * height as usize);
+        for row in y..y + height {
+            let start = ((row * frame.width + x) * 4) as usize;
+            buffer.extend_from_slice(&frame.buffer[start..start + row_len]);
+        }
+
+        frame.buffer = buffer;
+        frame.width = width;
+        frame.height = height;
+    }
+
+    info.width = width as u16;
+    info.height = height as u16;
+}
+
+/// Union bounding box of the non-transparent pixels across all frames,
+/// or `None` if every frame is fully transparent
+pub fn content_bounds(frames: &[Frame]) -> Option<CropRect> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None; // min x, min y, max x, max y
+
+    for frame in frames {
+        for (i, pixel) in frame.buffer.chunks_exact(4).enumerate() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            let (x, y) = (i as u32 % frame.width, i as u32 / frame.width);
+            bounds = Some(match bounds {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            });
+        }
+    }
+
+    bounds.map(|(x0, y0, x1, y1)| CropRect {
+        x: x0,
+        y: y0,
+        width: x1 - x0 + 1,
+        height: y1 - y0 + 1,
+    })
+}
+
+/// Crop away transparent margins shared by every frame
+pub fn auto_trim(info: &mut AnimationInfo, frames: &mut [Frame]) {
+    if let Some(bounds) = content_bounds(frames) {
+        crop(info, frames, bounds);
+    }
+}
+
+/// CLI form: `x,y,width,height`
+impl FromStr for CropRect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<u32> = s
+            .split(',')
+            .map(|p| p.trim().parse::<u32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid crop '{}', expected x,y,width,height", s))?;
+
+        match parts[..] {
+            [x, y, width, height] if width > 0 && height > 0 => Ok(CropRect { x, y, width, height }),
+            _ => Err(format!("Invalid crop '{}', expected x,y,width,height", s)),
+        }
+    }
+}
+
+impl fmt::Display for CropRect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // `rows` of '.' (transparent) and '#' (opaque); the alpha doubles as the pixel's red channel
+    // so crops can be checked by position
+    fn frame(rows: &[&str]) -> Frame {
+        let buffer = rows
+            .iter()
+            .flat_map(|r| r.chars())
+            .enumerate()
+            .flat_map(|(i, c)| [i as u8, 0, 0, if c == '#' { 255 } else { 0 }])
+            .collect();
+        Frame { buffer, width: rows[0].len() as u32, height: rows.len() as u32, delay: Duration::from_millis(100) }
+    }
+
+    fn canvas(frames: &[Frame]) -> AnimationInfo {
+        AnimationInfo {
+            width: frames[0].width as u16,
+            height: frames[0].height as u16,
+            frame_count: frames.len(),
+            duration: Duration::from_millis(100),
+            palette: Vec::new(),
+            loops: None,
+            format: "gif",
+        }
+    }
+
+    fn reds(frame: &Frame) -> Vec<u8> {
+        frame.buffer.chunks_exact(4).map(|p| p[0]).collect()
+    }
+
+    #[test]
+    fn bounds_cover_every_frame() {
+        let frames = [frame(&["....", ".#..", "...."]), frame(&["....", "....", "...#"])];
+        assert_eq!(content_bounds(&frames), Some(CropRect { x: 1, y: 1, width: 3, height: 2 }));
+        assert_eq!(content_bounds(&[frame(&["..", ".."])]), None);
+    }
+
+    #[test]
+    fn auto_trim_crops_shared_margins() {
+        let mut frames = vec![frame(&["....", ".#..", "..#.", "...."])];
+        let mut info = canvas(&frames);
+        auto_trim(&mut info, &mut frames);
+        assert_eq!((info.width, info.height), (2, 2));
+        assert_eq!((frames[0].width, frames[0].height), (2, 2));
+        assert_eq!(reds(&frames[0]), [5, 6, 9, 10]);
+    }
+
+    #[test]
+    fn crop_is_clamped_to_the_canvas() {
+        let mut frames = vec![frame(&["###", "###"])];
+        let mut info = canvas(&frames);
+        crop(&mut info, &mut frames, CropRect { x: 2, y: 1, width: 10, height: 10 });
+        assert_eq!((info.width, info.height), (1, 1));
+        assert_eq!(reds(&frames[0]), [5]);
+
+        let mut frames = vec![frame(&["###", "###"])];
+        let mut info = canvas(&frames);
+        crop(&mut info, &mut frames, CropRect { x: 9, y: 9, width: 1, height: 1 });
+        assert_eq!(reds(&frames[0]), [5]);
+    }
+
+    #[test]
+    fn crop_parses_and_displays() {
+        let rect: CropRect = " 1, 2,3 ,4".parse().unwrap();
+        assert_eq!(rect, CropRect { x: 1, y: 2, width: 3, height: 4 });
+        assert_eq!(rect.to_string(), "1,2,3,4");
+        assert!("1,2,3".parse::<CropRect>().is_err());
+        assert!("1,2,0,4".parse::<CropRect>().is_err());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod painter;
+pub mod pixels;
 pub mod window;
@@ -1,11 +1,63 @@
-use crate::types::Frame;
-use softbuffer::{Context, Surface};
+use crate::renderer::pixels;
+use softbuffer::{Context, Rect, Surface};
 use std::num::NonZeroU32;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use winit::window::Window;
 
+// How often paint timings are written to the log
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
+
 pub struct Painter {
     surface: Surface<Rc<Window>, Rc<Window>>,
+    opacity: u32, // Global alpha factor (0-255) applied on top of per-pixel alpha
+    size: Option<(u32, u32)>, // Current surface size, so it is only resized when it changes
+    presented: Option<(u64, u32)>, // Content hash and opacity of what is on screen
+    row: Vec<u32>, // Scratch row for comparing faded pixels
+    stats: PaintStats,
+}
+
+/// CPU time spent painting, summarized in the log every `STATS_INTERVAL`
+struct PaintStats {
+    frames: u32,
+    unchanged: u32, // Paints skipped because the window already showed that content
+    total: Duration,
+    max: Duration,
+    since: Instant,
+}
+
+impl PaintStats {
+    fn new() -> Self {
+        Self {
+            frames: 0,
+            unchanged: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+            since: Instant::now(),
+        }
+    }
+
+    fn record(&mut self, elapsed: Option<Duration>) {
+        match elapsed {
+            Some(elapsed) => {
+                self.frames += 1;
+                self.total += elapsed;
+                self.max = self.max.max(elapsed);
+            }
+            None => self.unchanged += 1,
+        }
+
+        if self.since.elapsed() >= STATS_INTERVAL {
+            println!(
+                "Paint: {} frames, avg {:.2?}, max {:.2?}, {} unchanged",
+                self.frames,
+                self.total / self.frames.max(1),
+                self.max,
+                self.unchanged
+            );
+            *self = Self::new();
+        }
+    }
 }
 
 impl Painter {
@@ -14,39 +66,61 @@ impl Painter {
         window: Rc<Window>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let surface = Surface::new(context, window)?;
-        Ok(Self { surface })
+        Ok(Self {
+            surface,
+            opacity: 255,
+            size: None,
+            presented: None,
+            row: Vec::new(),
+            stats: PaintStats::new(),
+        })
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = (opacity.clamp(0.0, 1.0) * 255.0).round() as u32;
     }
 
-    pub fn paint(&mut self, frame: &Frame) -> Result<(), Box<dyn std::error::Error>> {
-        let width = frame.width;
-        let height = frame.height;
+    pub fn opacity(&self) -> f32 {
+        self.opacity as f32 / 255.0
+    }
 
-        if let (Some(w), Some(h)) = (NonZeroU32::new(width), NonZeroU32::new(height)) {
-            self.surface.resize(w, h)?;
+    /// Present a frame already converted with `pixels::premultiply`. `content_hash` identifies
+    /// the pixels, so showing the same content again (identical frames, a static image,
+    /// a repaint request) costs nothing. Otherwise only the area that changed is presented.
+    pub fn paint(&mut self, pixels: &[u32], width: u32, height: u32, content_hash: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let key = (content_hash, self.opacity);
+        if self.size == Some((width, height)) && self.presented == Some(key) {
+            self.stats.record(None);
+            return Ok(());
         }
 
-        let mut buffer = self.surface.buffer_mut()?;
+        let start = Instant::now();
 
-        for (i, pixel) in buffer.iter_mut().enumerate() {
-            let offset = i * 4;
-            if offset + 3 < frame.buffer.len() {
-                let r = frame.buffer[offset] as u32;
-                let g = frame.buffer[offset + 1] as u32;
-                let b = frame.buffer[offset + 2] as u32;
-                let a = frame.buffer[offset + 3] as u32;
-
-                // Premultiply alpha for correct blending with DWM
-                // r_pre = (r * a) / 255
-                let r_pre = (r * a) / 255;
-                let g_pre = (g * a) / 255;
-                let b_pre = (b * a) / 255;
-
-                // 0xAARRGGBB
-                *pixel = (a << 24) | (r_pre << 16) | (g_pre << 8) | b_pre;
+        let resized = self.size != Some((width, height));
+        if resized {
+            if let (Some(w), Some(h)) = (NonZeroU32::new(width), NonZeroU32::new(height)) {
+                self.surface.resize(w, h)?;
             }
+            self.size = Some((width, height));
+        }
+
+        let mut buffer = self.surface.buffer_mut()?;
+        // Age 1 means the buffer still holds the last presented frame, so it can be diffed
+        if resized || buffer.age() != 1 {
+            pixels::copy_with_opacity(pixels, &mut buffer, self.opacity);
+            buffer.present()?;
+        } else if let Some(dirty) = pixels::copy_changed(pixels, &mut buffer, width as usize, self.opacity, &mut self.row) {
+            let rect = Rect {
+                x: dirty.x,
+                y: dirty.y,
+                width: NonZeroU32::new(dirty.width).unwrap_or(NonZeroU32::MIN),
+                height: NonZeroU32::new(dirty.height).unwrap_or(NonZeroU32::MIN),
+            };
+            buffer.present_with_damage(&[rect])?;
         }
+        self.presented = Some(key);
 
-        buffer.present()?;
+        self.stats.record(Some(start.elapsed()));
         Ok(())
     }
 }
@@ -0,0 +1,202 @@
+//! Conversion from decoded RGBA frames to the surface's native pixel format:
+//! premultiplied alpha packed as 0xAARRGGBB.
+
+/// Convert straight-alpha RGBA bytes to premultiplied 0xAARRGGBB pixels.
+/// Done once per frame at load time so painting is a plain copy.
+pub fn premultiply(rgba: &[u8]) -> Vec<u32> {
+    rgba.chunks_exact(4)
+        .map(|p| {
+            let a = p[3] as u32;
+            let r = (p[0] as u32 * a + 127) / 255;
+            let g = (p[1] as u32 * a + 127) / 255;
+            let b = (p[2] as u32 * a + 127) / 255;
+            (a << 24) | (r << 16) | (g << 8) | b
+        })
+        .collect()
+}
+
+// Scale all four channels of a premultiplied pixel by `opacity` / 256, two channels at a time
+#[inline(always)]
+fn scale_pixel(pixel: u32, opacity: u32) -> u32 {
+    let rb = ((pixel & 0x00FF_00FF) * opacity + 0x0080_0080) >> 8;
+    let ag = ((pixel >> 8) & 0x00FF_00FF) * opacity + 0x0080_0080;
+    (rb & 0x00FF_00FF) | (ag & 0xFF00_FF00)
+}
+
+/// Copy premultiplied pixels into `dst`, fading them by a global opacity (0-255).
+/// Premultiplied pixels fade by scaling every channel, which works on fixed-size chunks
+/// the compiler can vectorize.
+pub fn copy_with_opacity(src: &[u32], dst: &mut [u32], opacity: u32) {
+    if opacity >= 255 {
+        let len = src.len().min(dst.len());
+        dst[..len].copy_from_slice(&src[..len]);
+        return;
+    }
+
+    // Map 0-255 onto 0-256 so the shift by 8 doesn't darken full-opacity channels
+    let opacity = opacity + (opacity >> 7);
+
+    const CHUNK: usize = 8;
+    let mut dst_chunks = dst.chunks_exact_mut(CHUNK);
+    let mut src_chunks = src.chunks_exact(CHUNK);
+    for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+        for (d, s) in d.iter_mut().zip(s) {
+            *d = scale_pixel(*s, opacity);
+        }
+    }
+    for (d, s) in dst_chunks.into_remainder().iter_mut().zip(src_chunks.remainder()) {
+        *d = scale_pixel(*s, opacity);
+    }
+}
+
+/// Bounding box of the pixels that changed between two frames
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Like `copy_with_opacity`, but only writes the pixels that differ from what `dst` already
+/// holds and returns the area that changed (`None` if nothing did). `scratch` holds one faded row.
+pub fn copy_changed(src: &[u32], dst: &mut [u32], width: usize, opacity: u32, scratch: &mut Vec<u32>) -> Option<DirtyRect> {
+    if width == 0 {
+        return None;
+    }
+    scratch.resize(width, 0);
+
+    let mut dirty: Option<(usize, usize, usize, usize)> = None; // min x, min y, max x, max y
+    for (y, (d, s)) in dst.chunks_exact_mut(width).zip(src.chunks_exact(width)).enumerate() {
+        let s = if opacity >= 255 {
+            s
+        } else {
+            copy_with_opacity(s, scratch, opacity);
+            &scratch[..]
+        };
+        if d == s {
+            continue;
+        }
+
+        let first = d.iter().zip(s).position(|(a, b)| a != b).unwrap_or(0);
+        let last = width - 1 - d.iter().rev().zip(s.iter().rev()).position(|(a, b)| a != b).unwrap_or(0);
+        d[first..=last].copy_from_slice(&s[first..=last]);
+
+        dirty = Some(match dirty {
+            Some((x0, y0, x1, _)) => (x0.min(first), y0, x1.max(last), y),
+            None => (first, y, last, y),
+        });
+    }
+
+    dirty.map(|(x0, y0, x1, y1)| DirtyRect {
+        x: x0 as u32,
+        y: y0 as u32,
+        width: (x1 - x0 + 1) as u32,
+        height: (y1 - y0 + 1) as u32,
+    })
+}
+
+/// Composite premultiplied `src` over `dst` ("source over"), fading `src` by `opacity` (0-255)
+pub fn blend_over(src: &[u32], dst: &mut [u32], opacity: u32) {
+    let opacity = opacity.min(255);
+    let opacity = opacity + (opacity >> 7);
+    for (d, &s) in dst.iter_mut().zip(src) {
+        let s = if opacity >= 256 { s } else { scale_pixel(s, opacity) };
+        let alpha = s >> 24;
+        *d = match alpha {
+            0 => *d,
+            255 => s,
+            _ => {
+                let keep = 255 - alpha;
+                s + scale_pixel(*d, keep + (keep >> 7))
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn premultiply_packs_argb_and_rounds() {
+        assert_eq!(premultiply(&[255, 128, 0, 255]), [0xFFFF_8000]);
+        assert_eq!(premultiply(&[255, 128, 0, 128]), [0x8080_4000]);
+        assert_eq!(premultiply(&[255, 255, 255, 0]), [0]);
+    }
+
+    #[test]
+    fn scale_pixel_scales_every_channel() {
+        assert_eq!(scale_pixel(0xFFFF_FFFF, 256), 0xFFFF_FFFF);
+        assert_eq!(scale_pixel(0xFF80_4020, 128), 0x8040_2010);
+        assert_eq!(scale_pixel(0xFFFF_FFFF, 0), 0);
+    }
+
+    #[test]
+    fn copy_with_opacity_fades_chunks_and_remainder() {
+        // 8 pixels go through the chunked loop, the last 3 through the remainder
+        let src = vec![0xFFFF_FFFF; 11];
+        let mut dst = vec![0; 11];
+        copy_with_opacity(&src, &mut dst, 128);
+        assert!(dst.iter().all(|&p| p == 0x8080_8080), "{:x?}", dst);
+
+        copy_with_opacity(&src, &mut dst, 255);
+        assert_eq!(dst, src);
+        copy_with_opacity(&src, &mut dst, 0);
+        assert!(dst.iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn copy_changed_reports_the_changed_box() {
+        // 4x3, with changes at (1,0) and (3,2)
+        let mut dst = vec![0; 12];
+        let mut src = dst.clone();
+        src[1] = 0xFF00_0000;
+        src[11] = 0xFFFF_FFFF;
+        let mut scratch = Vec::new();
+
+        let dirty = copy_changed(&src, &mut dst, 4, 255, &mut scratch);
+        assert_eq!(dirty, Some(DirtyRect { x: 1, y: 0, width: 3, height: 3 }));
+        assert_eq!(dst, src);
+
+        assert_eq!(copy_changed(&src, &mut dst, 4, 255, &mut scratch), None);
+    }
+
+    #[test]
+    fn copy_changed_compares_faded_pixels() {
+        let src = vec![0xFFFF_FFFF; 4];
+        let mut dst = vec![0x8080_8080; 4];
+        let mut scratch = Vec::new();
+        assert_eq!(copy_changed(&src, &mut dst, 2, 128, &mut scratch), None);
+
+        dst[3] = 0;
+        let dirty = copy_changed(&src, &mut dst, 2, 128, &mut scratch);
+        assert_eq!(dirty, Some(DirtyRect { x: 1, y: 1, width: 1, height: 1 }));
+        assert_eq!(dst, [0x8080_8080; 4]);
+    }
+
+    #[test]
+    fn copy_changed_ignores_zero_width() {
+        assert_eq!(copy_changed(&[1], &mut [0], 0, 255, &mut Vec::new()), None);
+    }
+
+    #[test]
+    fn blend_over_composites_source_over() {
+        let mut dst = vec![0xFF00_00FF, 0xFF00_00FF, 0xFF00_00FF, 0];
+        // Opaque red, transparent, half-transparent premultiplied red, opaque red on nothing
+        let src = [0xFFFF_0000, 0, 0x8080_0000, 0xFFFF_0000];
+        blend_over(&src, &mut dst, 255);
+        assert_eq!(dst, [0xFFFF_0000, 0xFF00_00FF, 0xFF80_007F, 0xFFFF_0000]);
+    }
+
+    #[test]
+    fn blend_over_fades_the_source_first() {
+        let mut dst = vec![0xFF00_00FF];
+        blend_over(&[0xFFFF_0000], &mut dst, 128);
+        assert_eq!(dst, [0xFF80_007F]);
+
+        let mut dst = vec![0xFF00_00FF];
+        blend_over(&[0xFFFF_0000], &mut dst, 0);
+        assert_eq!(dst, [0xFF00_00FF]);
+    }
+}
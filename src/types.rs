@@ -8,10 +8,25 @@ pub struct Frame {
     pub delay: Duration,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct AnimationInfo {
     pub width: u16,
     pub height: u16,
     pub frame_count: usize,
     pub duration: Duration,
+    pub palette: Vec<[u8; 3]>, // Global color table as stored in the file (GIF only)
+    pub loops: Option<u32>, // Times the file asks to be played in total, None for forever
+    pub format: &'static str, // "gif" or "apng"
+}
+
+/// A frame as stored in the file, before it is composited onto the canvas
+#[derive(Clone, Debug)]
+pub struct FrameInfo {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub delay: Duration,
+    pub disposal: &'static str, // What happens to the frame's area before the next one
+    pub blend: &'static str, // "over" draws onto the canvas, "source" replaces it
 }